@@ -1,23 +1,42 @@
-use crate::db_enums::*;
 use crate::sim::*;
 use crate::database::*;
 use crate::pokemon::*;
 use crate::controller::*;
+use crate::logging::*;
+
+// switching out partway through a turn, the controller picks the replacement unless it's forced
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SwitchOut {
+    Pivot,
+    BatonPass,
+    // substitute hp
+    ShedTail(u16),
+    // the replacement is picked at random
+    Forced
+}
 
 #[derive(Debug)]
 pub struct Side {
     pub team: Vec<Pokemon>,
     pub effects: SideEffects,
-    pub active_pokemon: usize
+    pub active_pokemon: usize,
+    pub pending_switch: Option<SwitchOut>
 }
 
 impl Side {
     
-    pub fn new(pokemon: Vec<Pokemon>) -> Self {
+    pub fn new(mut pokemon: Vec<Pokemon>, is_side1: bool) -> Self {
+
+        for (slot, mon) in pokemon.iter_mut().enumerate() {
+            mon.is_side1 = is_side1;
+            mon.team_slot = slot as u8;
+        }
+
         Self {
             team: pokemon,
             effects: SideEffects::default(),
-            active_pokemon: 0
+            active_pokemon: 0,
+            pending_switch: None
         }
     }
 
@@ -29,19 +48,31 @@ impl Side {
         &mut self.team[self.active_pokemon]
     }
 
-    pub fn try_apply_status(&mut self, status: NonVolatileStatus) {
+    pub fn has_healthy_reserve(&self) -> bool {
+        (0..self.team.len()).any(|idx| self.is_valid_switch_in(idx))
+    }
 
-        let pokemon = self.get_active_mut();
+    pub fn is_valid_switch_in(&self, idx: usize) -> bool {
+        idx != self.active_pokemon && self.team.get(idx).is_some_and(|mon| mon.hp > 0)
+    }
 
-        if pokemon.non_volatile_status.is_none() {
-            pokemon.non_volatile_status = Some(status);
-        }
+    pub fn all_fainted(&self) -> bool {
+        self.team.iter().all(|mon| mon.hp == 0)
+    }
+
+    pub fn get_team_hp(&self) -> Box<[u16]> {
+        self.team.iter().map(|mon| mon.hp).collect()
     }
 }
 
 pub struct Conditions {
     pub weather: Option<(Weather, u8)>,
-    pub terrain: Option<(Terrain, u8)>
+    pub terrain: Option<(Terrain, u8)>,
+    pub trick_room_turns: u8,
+    pub gravity_turns: u8,
+    // set from the active pokemon's abilities
+    pub neutralizing_gas: bool,
+    pub weather_negated: bool
 }
 
 impl Conditions {
@@ -49,7 +80,11 @@ impl Conditions {
     pub fn default() -> Self {
         Self {
             weather: None,
-            terrain: None
+            terrain: None,
+            trick_room_turns: 0,
+            gravity_turns: 0,
+            neutralizing_gas: false,
+            weather_negated: false
         }
     }
 
@@ -75,18 +110,40 @@ impl Conditions {
                 self.terrain = None;
             }
         }
+
+        self.trick_room_turns = self.trick_room_turns.saturating_sub(1);
+        self.gravity_turns = self.gravity_turns.saturating_sub(1);
+
+    }
+
+    pub fn is_trick_room(&self) -> bool {
+        self.trick_room_turns > 0
+    }
+
+    pub fn is_gravity(&self) -> bool {
+        self.gravity_turns > 0
+    }
+
+    // air lock and cloud nine hide the weather without ending it
+    pub fn get_weather(&self) -> Option<Weather> {
+        if self.weather_negated {
+            None
+        }
+        else {
+            self.weather.map(|(weather, _)| weather)
+        }
     }
 
     pub fn is_sunny(&self) -> bool {
-        matches!(self.weather, Some((Weather::Sun | Weather::ExtremeSun, _)))
+        matches!(self.get_weather(), Some(Weather::Sun | Weather::ExtremeSun))
     }
 
     pub fn is_rain(&self) -> bool {
-        matches!(self.weather, Some((Weather::Rain | Weather::HeavyRain, _)))
+        matches!(self.get_weather(), Some(Weather::Rain | Weather::HeavyRain))
     }
 
     pub fn is_weather(&self, weather: Weather) -> bool {
-        self.weather.is_some_and(|current| current.0 == weather)
+        self.get_weather() == Some(weather)
     }
 
     pub fn is_terrain(&self, terrain: Terrain) -> bool {
@@ -103,17 +160,51 @@ pub struct BattleState {
 impl BattleState {
     pub fn new(side1_pokemon: Vec<Pokemon>, side2_pokemon: Vec<Pokemon>) -> Self {
         Self {
-            side1: Side::new(side1_pokemon),
-            side2: Side::new(side2_pokemon),
+            side1: Side::new(side1_pokemon, true),
+            side2: Side::new(side2_pokemon, false),
             conditions: Conditions::default()
         }
     }
+
+    pub fn split_mut(&mut self, is_side1: bool) -> (&mut Side, &mut Side, &mut Conditions) {
+        if is_side1 {
+            (&mut self.side1, &mut self.side2, &mut self.conditions)
+        }
+        else {
+            (&mut self.side2, &mut self.side1, &mut self.conditions)
+        }
+    }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum BattleAction {
     Move(ID),
     Switch(u8),
-    Item(ID)
+    #[allow(dead_code)]
+    Item(ID),
+    Forfeit
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BattleWinner {
+    Side1,
+    Side2,
+    Draw
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BattleEndReason {
+    AllFainted,
+    Forfeit
+}
+
+#[derive(Debug, PartialEq)]
+pub struct BattleResult {
+    pub winner: BattleWinner,
+    pub reason: BattleEndReason,
+    pub turns: u32,
+    pub side1_hp: Box<[u16]>,
+    pub side2_hp: Box<[u16]>
 }
 
 pub struct Battle {
@@ -125,36 +216,196 @@ pub struct Battle {
 
 impl Battle {
 
-    pub fn new_battle(data_handler: &'static DataHandler, side1_pokemon: Vec<Pokemon>, side2_pokemon: Vec<Pokemon>, generation: u8) -> Self {
+    pub fn new_battle(data_handler: &'static DataHandler, side1_pokemon: Vec<Pokemon>, side2_pokemon: Vec<Pokemon>, generation: u8, seed: u64) -> Self {
         Self {
             state: BattleState::new(side1_pokemon, side2_pokemon),
-            simulator: Simulator::new(data_handler, generation),
-            controller1: Box::new(TextController::new(&data_handler)),
-            controller2: Box::new(TextController::new(&data_handler)),
-            //controller2: Box::new(RandomController::new())
+            simulator: Simulator::new(data_handler, generation, seed),
+            controller1: Box::new(TextController::new(data_handler)),
+            controller2: Box::new(TextController::new(data_handler)),
+            //controller2: Box::new(RandomController::new(seed))
+        }
+    }
+
+    pub fn add_event_sink(&mut self, sink: Box<dyn EventSink>) {
+        self.simulator.add_event_sink(sink);
+    }
+
+    pub fn set_controllers(&mut self, controller1: Box<dyn Controller>, controller2: Box<dyn Controller>) {
+        self.controller1 = controller1;
+        self.controller2 = controller2;
+    }
+
+    // only used to replay battles for now
+    #[allow(dead_code)]
+    pub fn get_rng_state(&self) -> u64 {
+        self.simulator.get_rng_state()
+    }
+
+    #[allow(dead_code)]
+    pub fn set_rng_state(&mut self, state: u64) {
+        self.simulator.set_rng_state(state);
+    }
+
+    pub fn get_winner(&self) -> Option<BattleWinner> {
+        match (self.state.side1.all_fainted(), self.state.side2.all_fainted()) {
+            (true, true) => Some(BattleWinner::Draw),
+            (false, true) => Some(BattleWinner::Side1),
+            (true, false) => Some(BattleWinner::Side2),
+            (false, false) => None
         }
     }
 
     pub fn battle_ended(&self) -> bool {
-        false
+        self.get_winner().is_some()
+    }
+
+    fn get_result(&self, winner: BattleWinner, reason: BattleEndReason) -> BattleResult {
+        BattleResult {
+            winner,
+            reason,
+            turns: self.simulator.get_turn(),
+            side1_hp: self.state.side1.get_team_hp(),
+            side2_hp: self.state.side2.get_team_hp()
+        }
     }
 
-    pub fn simulate(&mut self) {
+    pub fn simulate(&mut self) -> BattleResult {
+
+        self.simulator.start_battle(&mut self.state);
 
         while !self.battle_ended() {
 
-            let side1_action = self.controller1.get_action(&self.state, true);
-            let side2_action = self.controller2.get_action(&self.state, false);
-    
-            self.simulator.simulate_turn(side1_action, side2_action, &mut self.state);
-        
-            if self.state.side1.get_active().hp == 0 {
-                self.state.side1.active_pokemon = self.controller1.get_switch_in(&self.state, true) as usize;
+            let side1_action = match self.state.side1.get_active().get_locked_move() {
+                Some(move_id) => BattleAction::Move(move_id),
+                None => self.controller1.get_action(&self.state, true, &self.simulator.legal_actions(&self.state, true))
+            };
+
+            let side2_action = match self.state.side2.get_active().get_locked_move() {
+                Some(move_id) => BattleAction::Move(move_id),
+                None => self.controller2.get_action(&self.state, false, &self.simulator.legal_actions(&self.state, false))
+            };
+
+            let forfeit_winner = match (&side1_action, &side2_action) {
+                (BattleAction::Forfeit, BattleAction::Forfeit) => Some(BattleWinner::Draw),
+                (BattleAction::Forfeit, _) => Some(BattleWinner::Side2),
+                (_, BattleAction::Forfeit) => Some(BattleWinner::Side1),
+                _ => None
+            };
+
+            if let Some(winner) = forfeit_winner {
+                return self.get_result(winner, BattleEndReason::Forfeit);
             }
-            
-            if self.state.side2.get_active().hp == 0 {
-                self.state.side2.active_pokemon = self.controller2.get_switch_in(&self.state, false) as usize;
+
+            self.simulator.simulate_turn(side1_action, side2_action, &mut self.state, [self.controller1.as_ref(), self.controller2.as_ref()]);
+
+            // hazards can knock out the replacements too
+            while !self.battle_ended() && (self.state.side1.get_active().hp == 0 || self.state.side2.get_active().hp == 0) {
+                self.simulator.replace_fainted(&mut self.state, [self.controller1.as_ref(), self.controller2.as_ref()]);
             }
         }
+
+        self.get_result(self.get_winner().unwrap(), BattleEndReason::AllFainted)
+
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::test_utils::*;
+
+    const TEAM1: &str = "
+Volcarona
+Level: 50
+Ability: Flame Body
+- Flamethrower
+- Bug Buzz
+- Quiver Dance
+- Giga Drain
+
+Lapras
+Level: 50
+Ability: Water Absorb
+- Hydro Pump
+- Blizzard
+- Thunder
+- Sing";
+
+    const TEAM2: &str = "
+Haxorus
+Level: 50
+Ability: Mold Breaker
+- Outrage
+- Earthquake
+- X-Scissor
+- Dragon Dance
+
+Aggron
+Level: 50
+Ability: Rock Head
+- Head Smash
+- Double-Edge
+- Earthquake
+- Iron Head";
+
+    fn run_battle(seed: u64, rng_state: Option<u64>) -> (BattleResult, String) {
+
+        let recorder = EventRecorder::default();
+
+        let mut battle = Battle::new_battle(get_data_handler(), parse_team(TEAM1, 5), parse_team(TEAM2, 5), 5, seed);
+        battle.set_controllers(Box::new(FirstChoiceController), Box::new(FirstChoiceController));
+        battle.add_event_sink(Box::new(recorder.clone()));
+
+        if let Some(state) = rng_state {
+            battle.set_rng_state(state);
+        }
+
+        let result = battle.simulate();
+
+        (result, format!("{:?}", recorder.events()))
+
+    }
+
+    #[test]
+    fn same_seed_gives_same_battle() {
+
+        let (result1, events1) = run_battle(0x1234_5678_9ABC_DEF0, None);
+        let (result2, events2) = run_battle(0x1234_5678_9ABC_DEF0, None);
+
+        assert_eq!(result1, result2);
+        assert_eq!(events1, events2);
+    }
+
+    #[test]
+    fn restoring_rng_state_replays_battle() {
+
+        let battle = Battle::new_battle(get_data_handler(), parse_team(TEAM1, 5), parse_team(TEAM2, 5), 5, 42);
+        assert_eq!(battle.get_rng_state(), 42);
+
+        let (result1, events1) = run_battle(42, None);
+        let (result2, events2) = run_battle(7, Some(42));
+
+        assert_eq!(result1, result2);
+        assert_eq!(events1, events2);
+    }
+
+    #[test]
+    fn seeded_random_controllers_replay() {
+
+        let run = |controller_seed: u64| {
+
+            let recorder = EventRecorder::default();
+
+            let mut battle = Battle::new_battle(get_data_handler(), parse_team(TEAM1, 5), parse_team(TEAM2, 5), 5, 42);
+            battle.set_controllers(Box::new(RandomController::new(controller_seed)), Box::new(RandomController::new(controller_seed + 1)));
+            battle.add_event_sink(Box::new(recorder.clone()));
+
+            let result = battle.simulate();
+
+            (result, format!("{:?}", recorder.events()))
+        };
+
+        assert_eq!(run(3), run(3));
+    }
+}
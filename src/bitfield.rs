@@ -43,9 +43,11 @@ macro_rules! fields {
 macro_rules! bitfield {
     ($name:ident($type:ty); $($bits:tt)|+ $($read_fn:ident $set_fn:ident)+) => {
         
-        #[derive(Debug)]
+        #[derive(Debug, Clone, Copy)]
         pub struct $name($type);
 
+        // not every field has to be read
+        #[allow(dead_code)]
         impl $name {
             const NUM_BITS: usize = std::mem::size_of::<$type>() * 8;
             pub fn default() -> Self {
@@ -1,9 +1,13 @@
 use crate::battle::*;
 use crate::pokemon::*;
 use crate::database::DataHandler;
+use crate::db_enums::MOVE_STRUGGLE;
+use crate::rng::BattleRng;
+use rand::Rng;
+use std::cell::RefCell;
 
 pub trait Controller {
-    fn get_action(&self, state: &BattleState, is_side1: bool) -> BattleAction;
+    fn get_action(&self, state: &BattleState, is_side1: bool, legal_actions: &[BattleAction]) -> BattleAction;
     fn get_switch_in(&self, state: &BattleState, is_side1: bool) -> u8;
 }
 
@@ -13,7 +17,7 @@ pub struct TextController {
 
 impl Controller for TextController {
 
-    fn get_action(&self, state: &BattleState, is_side1: bool) -> BattleAction {
+    fn get_action(&self, state: &BattleState, is_side1: bool, legal_actions: &[BattleAction]) -> BattleAction {
 
         let mut my_side = &state.side1;
         let mut other_side = &state.side2;
@@ -49,6 +53,11 @@ impl Controller for TextController {
 
         Self::write_end_line();
 
+        if legal_actions.contains(&BattleAction::Move(MOVE_STRUGGLE)) {
+            println!("{} has no moves left!", my_mon.name);
+            return BattleAction::Move(MOVE_STRUGGLE);
+        }
+
         for (idx, my_move_option) in my_mon.moves.iter().enumerate() {
 
             let Some(my_move) = my_move_option
@@ -56,13 +65,14 @@ impl Controller for TextController {
                 continue;
             };
 
-            let move_data = self.data_handler.get_move(*my_move);
+            let move_data = self.data_handler.get_move(my_move.id);
 
-            println!("[{}] {}", idx + 1, move_data.name);
+            println!("[{}] {} ({}/{} PP)", idx + 1, move_data.name, my_move.pp, my_move.max_pp);
         
         }
 
         println!("[S]witch");
+        println!("[F]orfeit");
 
         let stdin = std::io::stdin();
         let mut input = String::new();
@@ -70,6 +80,7 @@ impl Controller for TextController {
         loop {
 
             println!("What will you do?");
+            input.clear();
             stdin.read_line(&mut input).unwrap();
 
             let Some(char) = input.chars().next()
@@ -78,11 +89,44 @@ impl Controller for TextController {
             };
 
             match char.to_ascii_lowercase() {
-                '1' => return BattleAction::Move(my_mon.moves[0].unwrap()),
-                '2' => return BattleAction::Move(my_mon.moves[1].unwrap()),
-                '3' => return BattleAction::Move(my_mon.moves[2].unwrap()),
-                '4' => return BattleAction::Move(my_mon.moves[3].unwrap()),
-                's' => return BattleAction::Switch(self.get_switch_in(state, is_side1)),
+                '1'..='4' => {
+
+                    let Some(my_move) = my_mon.moves[char as usize - '1' as usize]
+                    else {
+                        continue;
+                    };
+
+                    if my_move.pp == 0 {
+                        println!("There's no PP left for this move!");
+                        continue;
+                    }
+
+                    if !legal_actions.contains(&BattleAction::Move(my_move.id)) {
+                        println!("{} can't use that move right now!", my_mon.name);
+                        continue;
+                    }
+
+                    return BattleAction::Move(my_move.id);
+
+                }
+                's' => {
+
+                    if !legal_actions.iter().any(|action| matches!(action, BattleAction::Switch(_))) {
+                        println!("{} can't be switched out!", my_mon.name);
+                        continue;
+                    }
+
+                    let switch = BattleAction::Switch(self.get_switch_in(state, is_side1));
+
+                    if !legal_actions.contains(&switch) {
+                        println!("That pokemon can't be switched in!");
+                        continue;
+                    }
+
+                    return switch;
+
+                }
+                'f' => return BattleAction::Forfeit,
                 _ => {}
             }
         }
@@ -102,7 +146,7 @@ impl Controller for TextController {
 
         for (idx, mon) in my_side.team.iter().enumerate() {
 
-            if mon.hp == 0 {
+            if !my_side.is_valid_switch_in(idx) {
                 continue;
             }
 
@@ -113,18 +157,20 @@ impl Controller for TextController {
         loop {
 
             println!("Who will you switch in?");
+            input.clear();
             stdin.read_line(&mut input).unwrap();
 
-            let Some(num) = input.chars().next().map(|char| char as u8 - '1' as u8)
+            let Some(idx) = input.trim().parse::<usize>().ok().and_then(|num| num.checked_sub(1))
             else {
                 continue;
             };
 
-            if num >= 6 {
+            if !my_side.is_valid_switch_in(idx) {
+                println!("That pokemon can't be switched in!");
                 continue;
             }
 
-            return num;
+            return idx as u8;
 
         }
     }
@@ -173,21 +219,33 @@ impl TextController {
     }
 }
 
+// seeded separately from the battle so a battle against it can be replayed
+#[allow(dead_code)]
 pub struct RandomController {
+    rng: RefCell<BattleRng>
 }
 
 impl Controller for RandomController {
-    fn get_action(&self, state: &BattleState, is_side1: bool) -> BattleAction {
-        todo!()
+    fn get_action(&self, _state: &BattleState, _is_side1: bool, legal_actions: &[BattleAction]) -> BattleAction {
+        legal_actions[self.rng.borrow_mut().random_range(0..legal_actions.len())]
     }
     fn get_switch_in(&self, state: &BattleState, is_side1: bool) -> u8 {
-        todo!()
+
+        let my_side = if is_side1 {&state.side1} else {&state.side2};
+
+        let available_mons = my_side.team.iter().enumerate().filter(|(idx, mon)| *idx != my_side.active_pokemon && mon.hp > 0).map(|(idx, _)| idx as u8).collect::<Vec<_>>();
+
+        available_mons[self.rng.borrow_mut().random_range(0..available_mons.len())]
+
     }
 }
 
+#[allow(dead_code)]
 impl RandomController {
-    pub fn new() -> Self {
-        Self {}
+    pub fn new(seed: u64) -> Self {
+        Self {
+            rng: RefCell::new(BattleRng::new(seed))
+        }
     }
 }
 
@@ -212,23 +270,26 @@ bitfield!(
 
 pub struct Gen5AI {
     data_handler: &'static DataHandler,
-    flags: Gen5AIFlags
+    flags: Gen5AIFlags,
+    rng: RefCell<BattleRng>
 }
 
 impl Controller for Gen5AI {
     
-    fn get_action(&self, state: &BattleState, is_side1: bool) -> BattleAction {
+    // most of the scripts are still empty
+    #[allow(clippy::needless_ifs)]
+    fn get_action(&self, state: &BattleState, is_side1: bool, legal_actions: &[BattleAction]) -> BattleAction {
 
         let mut my_side = &state.side1;
         let mut other_side = &state.side2;
 
-        let my_mon = my_side.get_active();
-        let other_mon = other_side.get_active();
-
         if !is_side1 {
             std::mem::swap(&mut my_side, &mut other_side);
         }
 
+        let my_mon = my_side.get_active();
+        let _other_mon = other_side.get_active();
+
         let move_values = (0..4).map(|idx| {
 
             let Some(my_move) = my_mon.moves[idx]
@@ -236,9 +297,13 @@ impl Controller for Gen5AI {
                 return -100;
             };
 
-            let move_data = self.data_handler.get_move(my_move);
+            if my_move.pp == 0 || !legal_actions.contains(&BattleAction::Move(my_move.id)) {
+                return -100;
+            }
 
-            let mut score = 100;
+            let _move_data = self.data_handler.get_move_in_generation(my_move.id, Self::GENERATION);
+
+            let score = 100;
 
             // effectiveness
             if self.flags.get_script0() {
@@ -308,6 +373,10 @@ impl Controller for Gen5AI {
 
         }).collect::<Box<[_]>>();
 
+        if legal_actions.contains(&BattleAction::Move(MOVE_STRUGGLE)) {
+            return BattleAction::Move(MOVE_STRUGGLE);
+        }
+
         let mut max_moves = Vec::new();
         let max_score = move_values.iter().max().unwrap();
 
@@ -317,7 +386,7 @@ impl Controller for Gen5AI {
             }
         }
 
-        BattleAction::Move(my_mon.moves[max_moves[rand::random_range(0..max_moves.len())]].unwrap())
+        BattleAction::Move(my_mon.moves[max_moves[self.rng.borrow_mut().random_range(0..max_moves.len())]].unwrap().id)
 
     }
     fn get_switch_in(&self, state: &BattleState, is_side1: bool) -> u8 {
@@ -339,7 +408,8 @@ impl Controller for Gen5AI {
 
             let mut max_power = 0;
 
-            let opponent_mon_data = self.data_handler.get_pokemon_data(other_side.get_active().id);
+            let type_chart = self.data_handler.get_type_chart(Self::GENERATION);
+            let (opponent_type1, opponent_type2) = self.data_handler.get_pokemon_data(other_side.get_active().id).get_types(Self::GENERATION);
             
             for mon_move_option in mon.moves {
                 
@@ -348,7 +418,7 @@ impl Controller for Gen5AI {
                     continue;
                 };
 
-                let move_data = self.data_handler.get_move(mon_move);
+                let move_data = self.data_handler.get_move_in_generation(mon_move.id, Self::GENERATION);
 
                 if move_data.class == MoveClass::Status {
                     continue;
@@ -356,11 +426,11 @@ impl Controller for Gen5AI {
 
                 let mut power = move_data.power.unwrap_or(60) as u16;
 
-                power *= self.data_handler.type_chart.get(move_data.move_type, opponent_mon_data.type1) as u16;
+                power *= type_chart.get(move_data.move_type, opponent_type1) as u16;
                 power /= 100;
 
-                if let Some(type2) = opponent_mon_data.type2 {
-                    power *= self.data_handler.type_chart.get(move_data.move_type, type2) as u16;
+                if let Some(type2) = opponent_type2 {
+                    power *= type_chart.get(move_data.move_type, type2) as u16;
                     power /= 100;
                 }
 
@@ -380,16 +450,20 @@ impl Controller for Gen5AI {
             }
         }
 
-        return 0;
+        0
 
     }
 }
 
 impl Gen5AI {
-    pub fn new(data_handler: &'static DataHandler) -> Self {
+
+    const GENERATION: u8 = 5;
+
+    pub fn new(data_handler: &'static DataHandler, seed: u64) -> Self {
         Self {
             data_handler,
-            flags: Gen5AIFlags(0)
+            flags: Gen5AIFlags(0),
+            rng: RefCell::new(BattleRng::new(seed))
         }
     }
 }
\ No newline at end of file
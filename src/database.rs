@@ -3,6 +3,11 @@ use std::collections::HashMap;
 use crate::db_enums::*;
 use crate::pokemon::*;
 
+pub const LATEST_GENERATION: u8 = 9;
+
+// the physical/special split
+const VERSION_GROUP_DIAMOND_PEARL: u8 = 8;
+
 struct CSVDatabase {
     rows: Box<[Box<[String]>]>,
     column_indexes: HashMap<String, usize>
@@ -110,7 +115,11 @@ pub struct DataHandler {
     pub item_name_table: NameTable,
     pub move_name_table: NameTable,
     pub nature_name_table: NameTable,
-    pub type_chart: TypeChart,
+    type_charts: Vec<TypeChart>,
+    // (order of the version group it changed in, the move before the change) in ascending order
+    past_moves: HashMap<ID, Vec<(u8, Move)>>,
+    // order of the last version group in each generation
+    generation_version_groups: [u8; LATEST_GENERATION as usize],
     nature_chart: [(u8, u8); 25]
 }
 
@@ -120,12 +129,20 @@ impl DataHandler {
     
         let pokemon = CSVDatabase::load("data/pokemon.csv")?;
         let pokemon_types = CSVDatabase::load("data/pokemon_types.csv")?;
+        let pokemon_types_past = CSVDatabase::load("data/pokemon_types_past.csv")?;
         let pokemon_stats = CSVDatabase::load("data/pokemon_stats.csv")?;
         let pokemon_abilities = CSVDatabase::load("data/pokemon_abilities.csv")?;
+        let pokemon_abilities_past = CSVDatabase::load("data/pokemon_abilities_past.csv")?;
         let moves = CSVDatabase::load("data/moves.csv")?;
         let move_flag_map = CSVDatabase::load("data/move_flag_map.csv")?;
+        let move_changelog = CSVDatabase::load("data/move_changelog.csv")?;
+        let move_meta = CSVDatabase::load("data/move_meta.csv")?;
+        let move_meta_stat_changes = CSVDatabase::load("data/move_meta_stat_changes.csv")?;
+        let types = CSVDatabase::load("data/types.csv")?;
         let type_efficacy = CSVDatabase::load("data/type_efficacy.csv")?;
+        let type_efficacy_past = CSVDatabase::load("data/type_efficacy_past.csv")?;
         let natures = CSVDatabase::load("data/natures.csv")?;
+        let version_groups = CSVDatabase::load("data/version_groups.csv")?;
 
         let mut pokemon_data_table = HashMap::new();
         
@@ -150,7 +167,9 @@ impl DataHandler {
                 ability1: Ability::None,
                 ability2: None,
                 hidden_ability: None,
-                weight
+                weight,
+                past_types: Vec::new(),
+                past_abilities: Vec::new()
             });
         }
 
@@ -192,10 +211,39 @@ impl DataHandler {
             }
         }
 
+        for row in pokemon_types_past.row_iter() {
+
+            let pokemon_id = pokemon_types_past.get_cell(row, "pokemon_id");
+            let generation: u8 = pokemon_types_past.get_cell(row, "generation_id");
+            let pokemon_type = Type::from_db_id(pokemon_types_past.get_cell(row, "type_id"));
+            let slot: String = pokemon_types_past.get_cell(row, "slot");
+
+            let past_types = &mut pokemon_data_table.get_mut(&pokemon_id).unwrap().past_types;
+
+            let idx = match past_types.iter().position(|(last_generation, _, _)| *last_generation == generation) {
+                Some(idx) => idx,
+                None => {
+                    past_types.push((generation, Type::Normal, None));
+                    past_types.len() - 1
+                }
+            };
+
+            if slot == "1" {
+                past_types[idx].1 = pokemon_type;
+            }
+            else {
+                past_types[idx].2 = Some(pokemon_type);
+            }
+        }
+
+        for pokemon_data in pokemon_data_table.values_mut() {
+            pokemon_data.past_types.sort_by_key(|(last_generation, _, _)| *last_generation);
+        }
+
         for row in pokemon_abilities.row_iter() {
 
             let pokemon_id = pokemon_abilities.get_cell(row, "pokemon_id");
-            let ability_id = Ability::from_db_id(pokemon_abilities.get_cell(row, "ability_id"));
+            let ability_id = Ability::from_db_id(pokemon_abilities.get_cell::<ID>(row, "ability_id")).unwrap_or(Ability::None);
             let slot: String = pokemon_abilities.get_cell(row, "slot");
 
             let pokemon_data = pokemon_data_table.get_mut(&pokemon_id).unwrap();
@@ -208,6 +256,21 @@ impl DataHandler {
             }
         }
 
+        for row in pokemon_abilities_past.row_iter() {
+
+            let pokemon_id = pokemon_abilities_past.get_cell(row, "pokemon_id");
+            let generation = pokemon_abilities_past.get_cell(row, "generation_id");
+            let ability_id = Ability::from_db_id(pokemon_abilities_past.get_cell::<ID>(row, "ability_id")).unwrap_or(Ability::None);
+            let slot = pokemon_abilities_past.get_cell(row, "slot");
+
+            pokemon_data_table.get_mut(&pokemon_id).unwrap().past_abilities.push((generation, slot, ability_id));
+
+        }
+
+        for pokemon_data in pokemon_data_table.values_mut() {
+            pokemon_data.past_abilities.sort_by_key(|(last_generation, _, _)| *last_generation);
+        }
+
         let mut move_table = HashMap::new();
 
         for row in moves.row_iter() {
@@ -216,11 +279,13 @@ impl DataHandler {
             let move_type = Type::from_db_id(moves.get_cell(row, "type_id"));
             let class = MoveClass::from_db_id(moves.get_cell(row, "damage_class_id"));
             let priority = moves.get_cell(row, "priority");
+            let pp = moves.get_cell::<String>(row, "pp").parse().unwrap_or(0);
             let power = moves.get_cell::<String>(row, "power").parse().ok();
             let accuracy = moves.get_cell::<String>(row, "accuracy").parse().ok();
-            let effect = MoveEffect::from_db_id(moves.get_cell::<String>(row, "effect_id").parse().unwrap_or(ID::new(1).unwrap()));
+            // effects the enum doesn't have yet are treated as plain damage
+            let effect = moves.get_cell::<String>(row, "effect_id").parse::<ID>().ok().and_then(MoveEffect::from_db_id).unwrap_or(MoveEffect::NoEffect1);
             let effect_chance = moves.get_cell::<String>(row, "effect_chance").parse().ok();
-            let target = MoveTarget::from_db_id(moves.get_cell(row, "target_id"));
+            let target = MoveTarget::from_db_id(moves.get_cell::<u8>(row, "target_id")).unwrap_or(MoveTarget::SelectedPokemon);
             let identifier = moves.get_cell::<String>(row, "identifier");
 
             move_table.insert(id, Move {
@@ -229,12 +294,17 @@ impl DataHandler {
                 class,
                 move_type,
                 priority,
+                pp,
                 power,
                 accuracy,
                 effect,
                 effect_chance,
+                crit_rate: 0,
+                min_hits: 1,
+                max_hits: 1,
                 target,
-                flags: MoveFlags::default()
+                flags: MoveFlags::default(),
+                meta: MoveMeta::default()
             });
         }
 
@@ -272,6 +342,162 @@ impl DataHandler {
             }
         }
 
+        for row in move_meta.row_iter() {
+
+            let move_id = move_meta.get_cell(row, "move_id");
+            let crit_rate = move_meta.get_cell(row, "crit_rate");
+            let min_hits = move_meta.get_cell::<String>(row, "min_hits").parse().unwrap_or(1);
+            let max_hits = move_meta.get_cell::<String>(row, "max_hits").parse().unwrap_or(1);
+
+            let Some(used_move) = move_table.get_mut(&move_id)
+            else {
+                continue;
+            };
+
+            used_move.crit_rate = crit_rate;
+            used_move.min_hits = min_hits;
+            used_move.max_hits = max_hits;
+
+            let meta = &mut used_move.meta;
+
+            meta.category = MoveMetaCategory::from_db_id(move_meta.get_cell(row, "meta_category_id"));
+            meta.ailment = MoveAilment::from_db_id(move_meta.get_cell(row, "meta_ailment_id"));
+            meta.min_turns = move_meta.get_cell::<String>(row, "min_turns").parse().ok();
+            meta.max_turns = move_meta.get_cell::<String>(row, "max_turns").parse().ok();
+            meta.drain = move_meta.get_cell(row, "drain");
+            meta.healing = move_meta.get_cell(row, "healing");
+            meta.ailment_chance = move_meta.get_cell(row, "ailment_chance");
+            meta.flinch_chance = move_meta.get_cell(row, "flinch_chance");
+            meta.stat_chance = move_meta.get_cell(row, "stat_chance");
+
+        }
+
+        for row in move_meta_stat_changes.row_iter() {
+
+            let move_id = move_meta_stat_changes.get_cell(row, "move_id");
+            let stat_id = move_meta_stat_changes.get_cell(row, "stat_id");
+            let change = move_meta_stat_changes.get_cell(row, "change");
+
+            let stat = match stat_id {
+                STAT_ATTACK => Stat::Attack,
+                STAT_DEFENSE => Stat::Defense,
+                STAT_SPECIAL_ATTACK => Stat::SpecialAttack,
+                STAT_SPECIAL_DEFENSE => Stat::SpecialDefense,
+                STAT_SPEED => Stat::Speed,
+                STAT_ACCURACY => Stat::Accuracy,
+                STAT_EVASION => Stat::Evasion,
+                _ => continue
+            };
+
+            if let Some(used_move) = move_table.get_mut(&move_id) {
+                used_move.meta.stat_changes.push((stat, change));
+            }
+        }
+
+        let mut version_group_orders = HashMap::new();
+        let mut generation_version_groups = [0; LATEST_GENERATION as usize];
+
+        for row in version_groups.row_iter() {
+
+            let id: u8 = version_groups.get_cell(row, "id");
+            let generation: usize = version_groups.get_cell(row, "generation_id");
+            let order = version_groups.get_cell(row, "order");
+
+            version_group_orders.insert(id, order);
+            generation_version_groups[generation - 1] = generation_version_groups[generation - 1].max(order);
+
+        }
+
+        let mut move_changes = Vec::new();
+
+        for row in move_changelog.row_iter() {
+
+            let move_id: ID = move_changelog.get_cell(row, "move_id");
+            let version_group = move_changelog.get_cell(row, "changed_in_version_group_id");
+
+            move_changes.push((move_id, version_group_orders[&version_group], row));
+
+        }
+
+        // newest first so each older version builds on the one after it
+        move_changes.sort_by_key(|(move_id, order, _)| (*move_id, std::cmp::Reverse(*order)));
+
+        let mut past_moves: HashMap<ID, Vec<(u8, Move)>> = HashMap::new();
+
+        for (move_id, order, row) in move_changes {
+
+            let Some(current_move) = move_table.get(&move_id)
+            else {
+                continue;
+            };
+
+            let versions = past_moves.entry(move_id).or_default();
+            let mut past_move = versions.last().map_or(current_move, |(_, newer_move)| newer_move).clone();
+
+            // blank cells didn't change
+            if let Ok(type_id) = move_changelog.get_cell::<String>(row, "type_id").parse() {
+                past_move.move_type = Type::from_db_id(type_id);
+            }
+
+            if let Ok(power) = move_changelog.get_cell::<String>(row, "power").parse() {
+                past_move.power = Some(power);
+            }
+
+            if let Ok(pp) = move_changelog.get_cell::<String>(row, "pp").parse() {
+                past_move.pp = pp;
+            }
+
+            if let Ok(accuracy) = move_changelog.get_cell::<String>(row, "accuracy").parse() {
+                past_move.accuracy = Some(accuracy);
+            }
+
+            if let Ok(priority) = move_changelog.get_cell::<String>(row, "priority").parse() {
+                past_move.priority = priority;
+            }
+
+            if let Ok(target_id) = move_changelog.get_cell::<String>(row, "target_id").parse::<u8>() {
+                past_move.target = MoveTarget::from_db_id(target_id).unwrap_or(MoveTarget::SelectedPokemon);
+            }
+
+            if let Ok(effect_id) = move_changelog.get_cell::<String>(row, "effect_id").parse::<ID>() {
+                past_move.effect = MoveEffect::from_db_id(effect_id).unwrap_or(MoveEffect::NoEffect1);
+            }
+
+            if let Ok(effect_chance) = move_changelog.get_cell::<String>(row, "effect_chance").parse::<u8>() {
+                past_move.effect_chance = if effect_chance == 0 {None} else {Some(effect_chance)};
+            }
+
+            versions.push((order, past_move));
+
+        }
+
+        for versions in past_moves.values_mut() {
+            versions.reverse();
+        }
+
+        let split_order = version_group_orders[&VERSION_GROUP_DIAMOND_PEARL];
+
+        for (move_id, current_move) in &move_table {
+
+            if current_move.class == MoveClass::Status {
+                continue;
+            }
+
+            let versions = past_moves.entry(*move_id).or_default();
+
+            if !versions.iter().any(|(changed_in, _)| *changed_in == split_order) {
+                let idx = versions.iter().position(|(changed_in, _)| *changed_in > split_order).unwrap_or(versions.len());
+                let split_move = versions.get(idx).map_or(current_move, |(_, newer_move)| newer_move).clone();
+                versions.insert(idx, (split_order, split_move));
+            }
+
+            for (changed_in, past_move) in versions.iter_mut() {
+                if *changed_in <= split_order {
+                    past_move.class = past_move.move_type.get_split_class();
+                }
+            }
+        }
+
         let mut type_chart = TypeChart::empty();
 
         for row in type_efficacy.row_iter() {
@@ -284,6 +510,36 @@ impl DataHandler {
 
         }
 
+        // past rows hold the matchup up to and including their generation
+        let mut type_charts = vec![type_chart; LATEST_GENERATION as usize];
+
+        for row in type_efficacy_past.row_iter() {
+
+            let attacker = Type::from_db_id(type_efficacy_past.get_cell(row, "damage_type_id"));
+            let defender = Type::from_db_id(type_efficacy_past.get_cell(row, "target_type_id"));
+            let damage_factor = type_efficacy_past.get_cell(row, "damage_factor");
+            let last_generation: usize = type_efficacy_past.get_cell(row, "generation_id");
+
+            for chart in &mut type_charts[..last_generation] {
+                chart.set(attacker, defender, damage_factor);
+            }
+        }
+
+        // types that didn't exist yet are neutral both ways
+        for row in types.row_iter() {
+
+            let new_type = Type::from_db_id(types.get_cell(row, "id"));
+            let introduced: usize = types.get_cell(row, "generation_id");
+
+            if new_type == Type::Unknown {
+                continue;
+            }
+
+            for chart in &mut type_charts[..introduced - 1] {
+                chart.clear_type(new_type);
+            }
+        }
+
         let mut nature_chart = [(0, 0); 25];
 
         for row in natures.row_iter() {
@@ -310,7 +566,9 @@ impl DataHandler {
             item_name_table,
             move_name_table,
             nature_name_table,
-            type_chart,
+            type_charts,
+            past_moves,
+            generation_version_groups,
             nature_chart
         })
     }
@@ -319,11 +577,83 @@ impl DataHandler {
         &self.pokemon[&id]
     }
 
+    pub fn get_type_chart(&self, generation: u8) -> &TypeChart {
+        &self.type_charts[generation.clamp(1, LATEST_GENERATION) as usize - 1]
+    }
+
     pub fn get_move(&self, id: ID) -> &Move {
         &self.moves[&id]
     }
 
+    pub fn get_move_in_generation(&self, id: ID, generation: u8) -> &Move {
+        self.get_move_at_order(id, self.generation_version_groups[generation.clamp(1, LATEST_GENERATION) as usize - 1])
+    }
+
+    fn get_move_at_order(&self, id: ID, version_group_order: u8) -> &Move {
+
+        if let Some(versions) = self.past_moves.get(&id) {
+            for (changed_in, past_move) in versions {
+                if version_group_order < *changed_in {
+                    return past_move;
+                }
+            }
+        }
+
+        self.get_move(id)
+
+    }
+
     pub fn get_nature_changed_stats(&self, id: ID) -> (u8, u8) {
         self.nature_chart[id.get() as usize - 1]
     }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::test_utils::*;
+
+    #[test]
+    fn type_chart_eras() {
+
+        let data_handler = get_data_handler();
+
+        // (generation, attacking type, defending type, damage factor)
+        let cases = [
+            (1, Type::Ghost, Type::Psychic, 0),
+            (1, Type::Bug, Type::Poison, 200),
+            (1, Type::Poison, Type::Bug, 200),
+            (1, Type::Ice, Type::Fire, 100),
+            (1, Type::Dark, Type::Psychic, 100),
+            (2, Type::Ghost, Type::Psychic, 200),
+            (2, Type::Dark, Type::Psychic, 200),
+            (2, Type::Ice, Type::Fire, 50),
+            (5, Type::Ghost, Type::Steel, 50),
+            (5, Type::Dark, Type::Steel, 50),
+            (5, Type::Dragon, Type::Fairy, 100),
+            (6, Type::Ghost, Type::Steel, 100),
+            (6, Type::Dragon, Type::Fairy, 0),
+            (9, Type::Steel, Type::Fairy, 200)
+        ];
+
+        for (generation, attacker, defender, expected) in cases {
+            assert_eq!(data_handler.get_type_chart(generation).get(attacker, defender), expected, "gen {generation} {attacker:?} against {defender:?}");
+        }
+    }
+
+    #[test]
+    fn past_typings() {
+
+        let clefable = parse_team("Clefable\n- Pound", 6)[0].id;
+        let magnemite = parse_team("Magnemite\n- Tackle", 2)[0].id;
+
+        let clefable = get_data_handler().get_pokemon_data(clefable);
+        assert_eq!(clefable.get_types(5), (Type::Normal, None));
+        assert_eq!(clefable.get_types(6), (Type::Fairy, None));
+
+        let magnemite = get_data_handler().get_pokemon_data(magnemite);
+        assert_eq!(magnemite.get_types(1), (Type::Electric, None));
+        assert_eq!(magnemite.get_types(2), (Type::Electric, Some(Type::Steel)));
+    }
 }
\ No newline at end of file
@@ -10,6 +10,29 @@ pub const STAT_DEFENSE: u8 = 3;
 pub const STAT_SPECIAL_ATTACK: u8 = 4;
 pub const STAT_SPECIAL_DEFENSE: u8 = 5;
 pub const STAT_SPEED: u8 = 6;
+pub const STAT_ACCURACY: u8 = 7;
+pub const STAT_EVASION: u8 = 8;
+
+// looks the id up among the variants, the database has ids the enums leave out
+macro_rules! db_enum {
+    ($(#[$attr: meta])* pub enum $name: ident {$($variant: ident $(= $value: literal)?),+ $(,)?}) => {
+
+        $(#[$attr])*
+        pub enum $name {
+            $($variant $(= $value)?),+
+        }
+
+        impl $name {
+
+            const VARIANTS: &[Self] = &[$(Self::$variant),+];
+
+            pub fn from_db_id(id: impl Into<u16>) -> Option<Self> {
+                let id = id.into();
+                Self::VARIANTS.iter().find(|&&variant| variant as u16 == id).copied()
+            }
+        }
+    };
+}
 
 // remove at some point
 macro_rules! ids {
@@ -20,10 +43,26 @@ macro_rules! ids {
 
 ids!(
     POKEMON_PIKACHU     25
+    POKEMON_FARFETCHD   83
     POKEMON_MAROWAK     105
+    POKEMON_CHANSEY     113
     POKEMON_DITTO       132
     POKEMON_SHEDINJA    292
     POKEMON_CLAMPERL    366
+    POKEMON_SIRFETCHD   865
+    POKEMON_FARFETCHD_GALAR 10166
+    MOVE_STRUGGLE       165
+    MOVE_METEOR_BEAM    800
+    MOVE_FLIP_TURN      812
+    MOVE_TRIPLE_AXEL    813
+    MOVE_RAGING_FURY    833
+    MOVE_SILK_TRAP      852
+    MOVE_POPULATION_BOMB 860
+    MOVE_MORTAL_SPIN    866
+    MOVE_SHED_TAIL      880
+    MOVE_TIDY_UP        882
+    MOVE_PSYCHIC_NOISE  917
+    MOVE_BURNING_BULWARK 908
 );
 
 // #[repr(u16)]
@@ -968,6 +1007,7 @@ ids!(
 //     ShadowSky,
 // }
 
+db_enum! {
 #[repr(u16)]
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub enum Ability {
@@ -1340,13 +1380,11 @@ pub enum Ability {
     Decoy,
     Shield
 }
-
-impl Ability {
-    pub fn from_db_id(id: ID) -> Self {
-        unsafe {std::mem::transmute(id)}
-    }
 }
 
+db_enum! {
+// lost item, item drop and co are real item names
+#[allow(clippy::enum_variant_names)]
 #[repr(u16)]
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum Item {
@@ -3531,13 +3569,9 @@ pub enum Item {
     BlackAugurite = 10001,
     PeatBlock,
 }
-
-impl Item {
-    pub fn from_db_id(id: ID) -> Self {
-        unsafe {std::mem::transmute(id)}
-    }
 }
 
+db_enum! {
 #[repr(u8)]
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum MoveTarget {
@@ -3557,13 +3591,9 @@ pub enum MoveTarget {
     AllPokemon,
     AllAllies
 }
-
-impl MoveTarget {
-    pub fn from_db_id(id: u8) -> Self {
-        unsafe {std::mem::transmute(id)}
-    }
 }
 
+db_enum! {
 #[repr(u16)]
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub enum MoveEffect {
@@ -3600,7 +3630,7 @@ pub enum MoveEffect {
     LightScreen,
     TriAttack,
     Rest,
-    OHKO,
+    Ohko,
     RazorWind,
     SuperFang,
     DragonRage,
@@ -3666,8 +3696,8 @@ pub enum MoveEffect {
     PreventEscape,
     Nightmare,
     Minimize,
-    Curse = 112,
-    Protect,
+    Curse,
+    Protect = 112,
     Spikes,
     Identify,
     PerishSong,
@@ -3675,12 +3705,13 @@ pub enum MoveEffect {
     Endure,
     Rollout,
     Swagger,
-    IceBall,
+    FuryCutter,
     Attract,
     Return,
     Present,
     Frustration,
     Safeguard,
+    BurnChanceThawUser,
     Magnitude,
     BatonPass,
     Pursuit,
@@ -3890,10 +3921,25 @@ pub enum MoveEffect {
     Rototiller,
     StickyWeb,
     FellStinger,
+    PartingShot = 347,
+    CraftyShield = 350,
+    FlowerShield = 351,
+    KingsShield = 356,
+    DestroyProtect = 360,
+    SpikyShield = 362,
+    Geomancy = 366,
+    MatBlock = 377,
+    BanefulBunker = 384,
+    ThousandWaves = 374,
+    SpiritShackle = 385,
+    ThroatChop = 393,
+    CoreEnforcer = 402,
+    AuroraVeil = 407,
+    JawLock = 423,
+    NoRetreat = 425,
+    Octolock = 430,
+    CourtChange = 432,
+    Obstruct = 442,
+    ScaleShot = 443,
 }
-
-impl MoveEffect {
-    pub fn from_db_id(id: ID) -> Self {
-        unsafe {std::mem::transmute(id)}
-    }
 }
\ No newline at end of file
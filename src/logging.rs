@@ -1,7 +1,660 @@
+use crate::db_enums::*;
+use crate::pokemon::*;
+
 #[macro_export]
 macro_rules! log {
     ($string:literal $(,$arg:expr)*) => {
         let formatted = format!($string $(,$arg)*);
         println!("{}", formatted);
     }
+}
+
+// names can repeat, the side and team slot say which pokemon an event is about
+#[derive(Debug, Clone, PartialEq)]
+pub struct EventPokemon {
+    pub is_side1: bool,
+    pub team_slot: u8,
+    pub name: Box<str>
+}
+
+impl std::fmt::Display for EventPokemon {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.name)
+    }
+}
+
+// the text logger doesn't show every field, other sinks can
+#[allow(dead_code)]
+#[derive(Debug, Clone)]
+pub enum BattleEvent {
+    TurnStart(u32),
+    MoveUsed {pokemon: EventPokemon, move_id: ID, move_name: Box<str>},
+    Miss {pokemon: EventPokemon},
+    Failed,
+    NoPP {pokemon: EventPokemon},
+    PPReduced {pokemon: EventPokemon, move_id: ID, amount: u8},
+    Damage {pokemon: EventPokemon, amount: u16, crit: bool, effectiveness: u32},
+    HitCount {pokemon: EventPokemon, count: u8},
+    Heal {pokemon: EventPokemon, amount: u16},
+    Recoil {pokemon: EventPokemon, amount: u16},
+    IndirectDamage {pokemon: EventPokemon, amount: u16, cause: DamageCause},
+    PerishCount {pokemon: EventPokemon, count: u8},
+    StatChange {pokemon: EventPokemon, stat: Stat, stages: i8},
+    StatUnchanged {pokemon: EventPokemon, stat: Stat, raise: bool},
+    StatsReset {pokemon: EventPokemon},
+    StatusApplied {pokemon: EventPokemon, status: NonVolatileStatus},
+    StatusCured {pokemon: EventPokemon, status: NonVolatileStatus},
+    VolatileApplied {pokemon: EventPokemon, effect: VolatileStatusEffect},
+    VolatileActivated {pokemon: EventPokemon, effect: VolatileStatusEffect},
+    VolatileEnded {pokemon: EventPokemon, effect: VolatileStatusEffect},
+    CantMove {pokemon: EventPokemon, reason: CantMoveReason},
+    ItemActivated {pokemon: EventPokemon, item: Item},
+    AbilityChanged {pokemon: EventPokemon, ability: Ability},
+    AbilityActivated {pokemon: EventPokemon, ability: Ability},
+    ItemFrisked {pokemon: EventPokemon, target: EventPokemon, item: Item},
+    ItemStolen {pokemon: EventPokemon, target: EventPokemon, item: Item},
+    ItemKnockedOff {pokemon: EventPokemon, target: EventPokemon, item: Item},
+    MoveForewarned {pokemon: EventPokemon, move_name: Box<str>},
+    Faint {pokemon: EventPokemon},
+    Switch {pokemon: EventPokemon},
+    WeatherSet {weather: Weather},
+    WeatherEnded {weather: Weather},
+    TerrainSet {terrain: Terrain},
+    TrickRoomStarted {pokemon: EventPokemon},
+    TrickRoomEnded,
+    GravityStarted,
+    GravityEnded,
+    NeutralizingGasEnded,
+    HazardsCleared {pokemon: EventPokemon},
+    ToxicSpikesAbsorbed {pokemon: EventPokemon},
+    CourtChanged {pokemon: EventPokemon},
+    SideConditionStarted {pokemon: EventPokemon, condition: SideCondition},
+    SideConditionEnded {pokemon: EventPokemon, condition: SideCondition},
+    SideConditionBlocked {pokemon: EventPokemon, condition: SideCondition}
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum DamageCause {
+    Weather(Weather),
+    Burn,
+    Poison,
+    LeechSeed,
+    Nightmare,
+    Curse,
+    Bind,
+    Confusion,
+    StealthRock,
+    Spikes,
+    SpikyShield,
+    Item(Item),
+    Ability(Ability)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CantMoveReason {
+    Sleep,
+    Freeze,
+    Paralysis,
+    Flinch,
+    Recharge,
+    Infatuation,
+    Disable,
+    Taunt,
+    Torment,
+    Imprison,
+    HealBlock,
+    ThroatChop,
+    Gravity,
+    AssaultVest,
+    Trapped
+}
+
+pub trait EventSink {
+    fn on_event(&mut self, event: &BattleEvent);
+}
+
+pub struct TextLogger;
+
+impl EventSink for TextLogger {
+
+    fn on_event(&mut self, event: &BattleEvent) {
+
+        match event {
+            BattleEvent::TurnStart(_) => {
+                log!("");
+            }
+            BattleEvent::MoveUsed {pokemon, move_name, ..} => {
+                log!("{} used {}!", pokemon, move_name);
+            }
+            BattleEvent::Miss {pokemon} => {
+                log!("{}'s attack missed!", pokemon);
+            }
+            BattleEvent::Failed => {
+                log!("But it failed!");
+            }
+            BattleEvent::NoPP {pokemon} => {
+                log!("{} has no PP left for this move!", pokemon);
+            }
+            BattleEvent::PPReduced {pokemon, amount, ..} => {
+                log!("{}'s last move lost {} PP!", pokemon, amount);
+            }
+            BattleEvent::Damage {pokemon, crit, effectiveness, ..} => {
+
+                if *crit {
+                    log!("A critical hit!");
+                }
+
+                if *effectiveness > 100 {
+                    log!("It's super effective!");
+                }
+                else if *effectiveness == 0 {
+                    log!("It doesn't affect {}...", pokemon);
+                }
+                else if *effectiveness < 100 {
+                    log!("It's not very effective...");
+                }
+            }
+            BattleEvent::HitCount {pokemon, count} => {
+                if *count == 1 {
+                    log!("{} was hit 1 time!", pokemon);
+                }
+                else {
+                    log!("{} was hit {} times!", pokemon, count);
+                }
+            }
+            BattleEvent::Heal {pokemon, ..} => {
+                log!("{} regained health!", pokemon);
+            }
+            BattleEvent::Recoil {pokemon, ..} => {
+                log!("{} was damaged by the recoil!", pokemon);
+            }
+            BattleEvent::IndirectDamage {pokemon, cause, ..} => {
+                match cause {
+                    DamageCause::Weather(Weather::Sandstorm) => {
+                        log!("{} is buffeted by the sandstorm!", pokemon);
+                    }
+                    DamageCause::Weather(Weather::Hail) => {
+                        log!("{} is pelted by hail!", pokemon);
+                    }
+                    DamageCause::Weather(weather) => {
+                        log!("{} is hurt by the {:?}!", pokemon, weather);
+                    }
+                    DamageCause::Burn => {
+                        log!("{} is hurt by its burn!", pokemon);
+                    }
+                    DamageCause::Poison => {
+                        log!("{} is hurt by poison!", pokemon);
+                    }
+                    DamageCause::LeechSeed => {
+                        log!("{}'s health is sapped by Leech Seed!", pokemon);
+                    }
+                    DamageCause::Nightmare => {
+                        log!("{} is locked in a nightmare!", pokemon);
+                    }
+                    DamageCause::Curse => {
+                        log!("{} is afflicted by the curse!", pokemon);
+                    }
+                    DamageCause::Bind => {
+                        log!("{} is hurt by the bind!", pokemon);
+                    }
+                    DamageCause::Confusion => {
+                        log!("It hurt itself in its confusion!");
+                    }
+                    DamageCause::StealthRock => {
+                        log!("Pointed stones dug into {}!", pokemon);
+                    }
+                    DamageCause::Spikes => {
+                        log!("{} is hurt by the spikes!", pokemon);
+                    }
+                    DamageCause::SpikyShield => {
+                        log!("{} was hurt by the Spiky Shield!", pokemon);
+                    }
+                    DamageCause::Item(item) => {
+                        log!("{} is hurt by its {:?}!", pokemon, item);
+                    }
+                    DamageCause::Ability(ability) => {
+                        log!("{} is hurt by its {:?}!", pokemon, ability);
+                    }
+                }
+            }
+            BattleEvent::PerishCount {pokemon, count} => {
+                log!("{}'s perish count fell to {}!", pokemon, count);
+            }
+            BattleEvent::StatChange {pokemon, stat, stages} => {
+                log!(
+                    "{}'s {:?} {}!",
+                    pokemon,
+                    stat,
+                    match stages {
+                        ..=-3 => "severely fell",
+                        -2 => "harshly fell",
+                        -1 => "fell",
+                        0 => unreachable!(),
+                        1 => "rose",
+                        2 => "rose sharply",
+                        3.. => "rose drastically",
+                    }
+                );
+            }
+            BattleEvent::StatUnchanged {pokemon, stat, raise} => {
+                log!("{}'s {:?} won't go any {}!", pokemon, stat, if *raise {"higher"} else {"lower"});
+            }
+            BattleEvent::StatsReset {pokemon} => {
+                log!("{}'s stat changes were removed!", pokemon);
+            }
+            BattleEvent::StatusApplied {pokemon, status} => {
+                log!(
+                    "{} {}!",
+                    pokemon,
+                    match status {
+                        NonVolatileStatus::Burn => "was burned",
+                        NonVolatileStatus::Freeze => "was frozen solid",
+                        NonVolatileStatus::Paralysis => "is paralyzed! It may be unable to move",
+                        NonVolatileStatus::Poison => "was poisoned",
+                        NonVolatileStatus::BadlyPoison(_) => "was badly poisoned",
+                        NonVolatileStatus::Sleep(_) => "fell asleep"
+                    }
+                );
+            }
+            BattleEvent::StatusCured {pokemon, status} => {
+                match status {
+                    NonVolatileStatus::Sleep(_) => {
+                        log!("{} woke up!", pokemon);
+                    }
+                    NonVolatileStatus::Freeze => {
+                        log!("{} thawed out!", pokemon);
+                    }
+                    _ => {
+                        log!("{} was cured of its {:?}!", pokemon, status);
+                    }
+                }
+            }
+            BattleEvent::VolatileApplied {pokemon, effect} => {
+                match effect {
+                    VolatileStatusEffect::Confusion(_) => {
+                        log!("{} became confused!", pokemon);
+                    }
+                    VolatileStatusEffect::Seed => {
+                        log!("{} was seeded!", pokemon);
+                    }
+                    VolatileStatusEffect::Curse => {
+                        log!("{} was cursed!", pokemon);
+                    }
+                    VolatileStatusEffect::Nightmare => {
+                        log!("{} began having a nightmare!", pokemon);
+                    }
+                    VolatileStatusEffect::PerishSong(count) => {
+                        log!("{} will faint in {} turns!", pokemon, count);
+                    }
+                    VolatileStatusEffect::Ingrain => {
+                        log!("{} planted its roots!", pokemon);
+                    }
+                    VolatileStatusEffect::Bind {..} => {
+                        log!("{} was trapped!", pokemon);
+                    }
+                    VolatileStatusEffect::Trapped | VolatileStatusEffect::JawLock => {
+                        log!("{} can no longer escape!", pokemon);
+                    }
+                    VolatileStatusEffect::NoRetreat => {
+                        log!("{} can no longer escape because it used No Retreat!", pokemon);
+                    }
+                    VolatileStatusEffect::Octolock => {
+                        log!("{} can no longer escape because of Octolock!", pokemon);
+                    }
+                    VolatileStatusEffect::AquaRing => {
+                        log!("{} surrounded itself with a veil of water!", pokemon);
+                    }
+                    VolatileStatusEffect::Identified => {
+                        log!("{} was identified!", pokemon);
+                    }
+                    VolatileStatusEffect::Infatuation => {
+                        log!("{} fell in love!", pokemon);
+                    }
+                    VolatileStatusEffect::GettingPumped => {
+                        log!("{} is getting pumped!", pokemon);
+                    }
+                    VolatileStatusEffect::Substitute(_) => {
+                        log!("{} put in a substitute!", pokemon);
+                    }
+                    VolatileStatusEffect::Endure => {
+                        log!("{} braced itself!", pokemon);
+                    }
+                    VolatileStatusEffect::Disable(..) => {
+                        log!("{}'s move was disabled!", pokemon);
+                    }
+                    VolatileStatusEffect::Encore(..) => {
+                        log!("{} must do an encore!", pokemon);
+                    }
+                    VolatileStatusEffect::Taunt(_) => {
+                        log!("{} fell for the taunt!", pokemon);
+                    }
+                    VolatileStatusEffect::Torment => {
+                        log!("{} was subjected to torment!", pokemon);
+                    }
+                    VolatileStatusEffect::Imprison => {
+                        log!("{} sealed any moves its target shares with it!", pokemon);
+                    }
+                    VolatileStatusEffect::HealBlock(_) => {
+                        log!("{} was prevented from healing!", pokemon);
+                    }
+                    VolatileStatusEffect::Charging(_) => {
+                        log!("{} began charging power!", pokemon);
+                    }
+                    VolatileStatusEffect::SemiInvulernable(SemiInvulnerableKind::Airborne) => {
+                        log!("{} flew up high!", pokemon);
+                    }
+                    VolatileStatusEffect::SemiInvulernable(SemiInvulnerableKind::Underground) => {
+                        log!("{} burrowed its way under the ground!", pokemon);
+                    }
+                    VolatileStatusEffect::SemiInvulernable(SemiInvulnerableKind::Underwater) => {
+                        log!("{} hid underwater!", pokemon);
+                    }
+                    VolatileStatusEffect::SemiInvulernable(SemiInvulnerableKind::Vanished) => {
+                        log!("{} vanished instantly!", pokemon);
+                    }
+                    VolatileStatusEffect::SkyDropped => {
+                        log!("{} was taken into the sky!", pokemon);
+                    }
+                    VolatileStatusEffect::Uproar(_) => {
+                        log!("{} caused an uproar!", pokemon);
+                    }
+                    VolatileStatusEffect::Bide(..) => {
+                        log!("{} is storing energy!", pokemon);
+                    }
+                    VolatileStatusEffect::Protect(ProtectKind::WideGuard) => {
+                        log!("Wide Guard protected {}'s team!", pokemon);
+                    }
+                    VolatileStatusEffect::Protect(ProtectKind::QuickGuard) => {
+                        log!("Quick Guard protected {}'s team!", pokemon);
+                    }
+                    VolatileStatusEffect::Protect(ProtectKind::MatBlock) => {
+                        log!("{} intends to flip up a mat and block incoming attacks!", pokemon);
+                    }
+                    VolatileStatusEffect::Protect(ProtectKind::CraftyShield) => {
+                        log!("Crafty Shield protected {}'s team!", pokemon);
+                    }
+                    VolatileStatusEffect::Protect(_) => {
+                        log!("{} protected itself!", pokemon);
+                    }
+                    _ => {
+                        log!("{} is affected by {:?}!", pokemon, effect);
+                    }
+                }
+            }
+            BattleEvent::VolatileActivated {pokemon, effect} => {
+                match effect {
+                    VolatileStatusEffect::Confusion(_) => {
+                        log!("{} is confused!", pokemon);
+                    }
+                    VolatileStatusEffect::Infatuation => {
+                        log!("{} is in love!", pokemon);
+                    }
+                    VolatileStatusEffect::Substitute(_) => {
+                        log!("The substitute took damage for {}!", pokemon);
+                    }
+                    VolatileStatusEffect::Endure => {
+                        log!("{} endured the hit!", pokemon);
+                    }
+                    VolatileStatusEffect::Bide(..) => {
+                        log!("{} is storing energy!", pokemon);
+                    }
+                    VolatileStatusEffect::Protect(_) => {
+                        log!("{} protected itself!", pokemon);
+                    }
+                    _ => {
+                        log!("{}'s {:?} activated!", pokemon, effect);
+                    }
+                }
+            }
+            BattleEvent::VolatileEnded {pokemon, effect} => {
+                match effect {
+                    VolatileStatusEffect::Confusion(_) => {
+                        log!("{} snapped out of its confusion!", pokemon);
+                    }
+                    VolatileStatusEffect::Substitute(_) => {
+                        log!("{}'s substitute faded!", pokemon);
+                    }
+                    VolatileStatusEffect::Protect(_) => {
+                        log!("It broke through {}'s protection!", pokemon);
+                    }
+                    VolatileStatusEffect::Uproar(_) => {
+                        log!("{} calmed down.", pokemon);
+                    }
+                    VolatileStatusEffect::Disable(..) => {
+                        log!("{}'s move is no longer disabled!", pokemon);
+                    }
+                    VolatileStatusEffect::Encore(..) => {
+                        log!("{}'s encore ended!", pokemon);
+                    }
+                    VolatileStatusEffect::Taunt(_) => {
+                        log!("{} shook off the taunt!", pokemon);
+                    }
+                    VolatileStatusEffect::HealBlock(_) => {
+                        log!("{}'s Heal Block wore off!", pokemon);
+                    }
+                    VolatileStatusEffect::ThroatChop(_) => {
+                        log!("{} can use sound-based moves again!", pokemon);
+                    }
+                    VolatileStatusEffect::Bide(..) => {
+                        log!("{} unleashed its energy!", pokemon);
+                    }
+                    VolatileStatusEffect::Bind {..} => {
+                        log!("{} was freed from its binding!", pokemon);
+                    }
+                    _ => {
+                        log!("{}'s {:?} wore off!", pokemon, effect);
+                    }
+                }
+            }
+            BattleEvent::CantMove {pokemon, reason} => {
+                match reason {
+                    CantMoveReason::Sleep => {
+                        log!("{} is fast asleep.", pokemon);
+                    }
+                    CantMoveReason::Freeze => {
+                        log!("{} is frozen solid!", pokemon);
+                    }
+                    CantMoveReason::Paralysis => {
+                        log!("{} is paralyzed! It can't move!", pokemon);
+                    }
+                    CantMoveReason::Flinch => {
+                        log!("{} flinched and couldn't move!", pokemon);
+                    }
+                    CantMoveReason::Recharge => {
+                        log!("{} must recharge!", pokemon);
+                    }
+                    CantMoveReason::Infatuation => {
+                        log!("{} is immobilized by love!", pokemon);
+                    }
+                    CantMoveReason::Disable => {
+                        log!("{}'s move is disabled!", pokemon);
+                    }
+                    CantMoveReason::Taunt => {
+                        log!("{} can't use that move after the taunt!", pokemon);
+                    }
+                    CantMoveReason::Torment => {
+                        log!("{} can't use the same move twice in a row due to the torment!", pokemon);
+                    }
+                    CantMoveReason::Imprison => {
+                        log!("{} can't use its sealed move!", pokemon);
+                    }
+                    CantMoveReason::HealBlock => {
+                        log!("{} can't use that move because of Heal Block!", pokemon);
+                    }
+                    CantMoveReason::ThroatChop => {
+                        log!("The effects of Throat Chop prevent {} from using certain moves!", pokemon);
+                    }
+                    CantMoveReason::Gravity => {
+                        log!("{} can't use that move because of gravity!", pokemon);
+                    }
+                    CantMoveReason::AssaultVest => {
+                        log!("{} can't use status moves while holding an Assault Vest!", pokemon);
+                    }
+                    CantMoveReason::Trapped => {
+                        log!("{} can't escape!", pokemon);
+                    }
+                }
+            }
+            BattleEvent::ItemActivated {pokemon, item} => {
+                match item {
+                    Item::FocusSash => {
+                        log!("{} held on using their Focus Sash!", pokemon);
+                    }
+                    Item::FocusBand => {
+                        log!("{} held on using their Focus Band!", pokemon);
+                    }
+                    Item::QuickClaw | Item::CustapBerry => {
+                        log!("{}'s {:?} let it move first!", pokemon, item);
+                    }
+                    Item::PowerHerb => {
+                        log!("{} became fully charged due to its Power Herb!", pokemon);
+                    }
+                    _ => {
+                        log!("{}'s {:?} activated!", pokemon, item);
+                    }
+                }
+            }
+            BattleEvent::AbilityChanged {pokemon, ability} => {
+                log!("{}'s ability became {:?}!", pokemon, ability);
+            }
+            BattleEvent::AbilityActivated {pokemon, ability} => {
+                match ability {
+                    Ability::QuickDraw => {
+                        log!("{} can act faster than normal, thanks to its Quick Draw!", pokemon);
+                    }
+                    Ability::AirLock | Ability::CloudNine => {
+                        log!("The effects of the weather disappeared.");
+                    }
+                    Ability::Anticipation => {
+                        log!("{} shuddered!", pokemon);
+                    }
+                    Ability::Pressure => {
+                        log!("{} is exerting its pressure!", pokemon);
+                    }
+                    Ability::Unnerve | Ability::AsOneGlastrier | Ability::AsOneSpectrier => {
+                        log!("{}'s opponents are too nervous to eat Berries!", pokemon);
+                    }
+                    Ability::MoldBreaker => {
+                        log!("{} breaks the mold!", pokemon);
+                    }
+                    Ability::Teravolt => {
+                        log!("{} is radiating a bursting aura!", pokemon);
+                    }
+                    Ability::Turboblaze => {
+                        log!("{} is radiating a blazing aura!", pokemon);
+                    }
+                    Ability::NeutralizingGas => {
+                        log!("Neutralizing gas filled the area!");
+                    }
+                    Ability::Intimidate => {
+                        log!("{} intimidates its opponent!", pokemon);
+                    }
+                    Ability::ScreenCleaner => {
+                        log!("{}'s Screen Cleaner removed the screens from the field!", pokemon);
+                    }
+                    Ability::SupremeOverlord => {
+                        log!("{} gained strength from the fallen!", pokemon);
+                    }
+                    Ability::Protosynthesis | Ability::QuarkDrive => {
+                        log!("{}'s {:?} boosted its strongest stat!", pokemon, ability);
+                    }
+                    _ => {
+                        log!("{}'s {:?} activated!", pokemon, ability);
+                    }
+                }
+            }
+            BattleEvent::ItemFrisked {pokemon, target, item} => {
+                log!("{} frisked {} and found its {:?}!", pokemon, target, item);
+            }
+            BattleEvent::ItemStolen {pokemon, target, item} => {
+                log!("{} stole {}'s {:?}!", pokemon, target, item);
+            }
+            BattleEvent::ItemKnockedOff {pokemon, target, item} => {
+                log!("{} knocked off {}'s {:?}!", pokemon, target, item);
+            }
+            BattleEvent::MoveForewarned {pokemon, move_name} => {
+                log!("{} was alerted to {}!", pokemon, move_name);
+            }
+            BattleEvent::Faint {pokemon} => {
+                log!("{} fainted!", pokemon);
+            }
+            BattleEvent::Switch {pokemon} => {
+                log!("Go! {}!", pokemon);
+            }
+            BattleEvent::WeatherSet {weather} => {
+                log!("The weather became {:?}!", weather);
+            }
+            BattleEvent::WeatherEnded {weather} => {
+                log!("The {:?} subsided.", weather);
+            }
+            BattleEvent::TerrainSet {terrain} => {
+                log!("The battlefield became {:?} Terrain!", terrain);
+            }
+            BattleEvent::TrickRoomStarted {pokemon} => {
+                log!("{} twisted the dimensions!", pokemon);
+            }
+            BattleEvent::TrickRoomEnded => {
+                log!("The twisted dimensions returned to normal!");
+            }
+            BattleEvent::GravityStarted => {
+                log!("Gravity intensified!");
+            }
+            BattleEvent::GravityEnded => {
+                log!("Gravity returned to normal!");
+            }
+            BattleEvent::NeutralizingGasEnded => {
+                log!("The effects of the neutralizing gas wore off!");
+            }
+            BattleEvent::HazardsCleared {pokemon} => {
+                log!("The hazards around {}'s side of the field disappeared!", pokemon);
+            }
+            BattleEvent::ToxicSpikesAbsorbed {pokemon} => {
+                log!("The poison spikes disappeared from around {}'s feet!", pokemon);
+            }
+            BattleEvent::CourtChanged {pokemon} => {
+                log!("{} swapped the battle effects affecting each side of the field!", pokemon);
+            }
+            BattleEvent::SideConditionStarted {pokemon, condition} => {
+                match condition {
+                    SideCondition::Reflect => {
+                        log!("Reflect made {}'s team stronger against physical moves!", pokemon);
+                    }
+                    SideCondition::LightScreen => {
+                        log!("Light Screen made {}'s team stronger against special moves!", pokemon);
+                    }
+                    SideCondition::Safeguard => {
+                        log!("{}'s team cloaked itself in a mystical veil!", pokemon);
+                    }
+                    SideCondition::Mist => {
+                        log!("{}'s team became shrouded in mist!", pokemon);
+                    }
+                    SideCondition::AuroraVeil => {
+                        log!("Aurora Veil made {}'s team stronger against physical and special moves!", pokemon);
+                    }
+                    SideCondition::Tailwind => {
+                        log!("The Tailwind blew from behind {}'s team!", pokemon);
+                    }
+                    SideCondition::LuckyChant => {
+                        log!("Lucky Chant shielded {}'s team from critical hits!", pokemon);
+                    }
+                }
+            }
+            BattleEvent::SideConditionEnded {pokemon, condition} => {
+                log!("{}'s team's {:?} wore off!", pokemon, condition);
+            }
+            BattleEvent::SideConditionBlocked {pokemon, condition} => {
+                match condition {
+                    SideCondition::Safeguard => {
+                        log!("{} is protected by Safeguard!", pokemon);
+                    }
+                    SideCondition::Mist => {
+                        log!("{} is protected by the mist!", pokemon);
+                    }
+                    _ => {
+                        log!("{} is protected by {:?}!", pokemon, condition);
+                    }
+                }
+            }
+        }
+    }
 }
\ No newline at end of file
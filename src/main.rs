@@ -1,7 +1,5 @@
 #![feature(variant_count)]
 #![feature(never_type)]
-#![feature(tuple_trait)]
-#![feature(concat_idents)]
 
 #[macro_use]
 mod bitfield;
@@ -13,12 +11,18 @@ mod sim;
 mod parser;
 mod controller;
 mod logging;
+mod rng;
+#[cfg(test)]
+mod test_utils;
 
 use database::*;
 use battle::*;
 use parser::*;
+use logging::*;
+use controller::*;
+use std::sync::OnceLock;
 
-const MY_TEAM: &'static str = "
+const MY_TEAM: &str = "
 Volcarona @ Miracle Seed
 Level: 65
 Careful Nature
@@ -30,7 +34,7 @@ IVs: 2 HP / 29 Atk / 19 Def / 22 SpA / 19 SpD / 6 Spe
 - Signal Beam
 - Giga Drain";
 
-const IRIS_TEAM: &'static str = "
+const IRIS_TEAM: &str = "
 Hydreigon @ Wise Glasses
 Level: 57
 Modest Nature
@@ -91,27 +95,43 @@ IVs: 30 HP / 30 Atk / 30 Def / 30 SpA / 30 SpD / 30 Spe
 - X-Scissor
 - Dragon Dance";
 
-static mut DATA_HANDLER: Option<DataHandler> = None;
+static DATA_HANDLER: OnceLock<DataHandler> = OnceLock::new();
 
 fn get_data_handler() -> &'static DataHandler {
-    unsafe {
-        DATA_HANDLER.as_ref().unwrap()
-    }
+    DATA_HANDLER.get().unwrap()
 }
 
 fn main() -> std::io::Result<()> {
 
-    unsafe {
-        DATA_HANDLER = Some(DataHandler::new()?);
-    }
+    let _ = DATA_HANDLER.set(DataHandler::new()?);
+
+    let generation = 5;
+
+    let my_team = parse_showdown_team(MY_TEAM, get_data_handler(), generation);
+    let iris_team = parse_showdown_team(IRIS_TEAM, get_data_handler(), generation);
 
-    =
+    let seed = rand::random();
+    println!("Seed: {seed:#018x}");
 
-    let my_team = parse_showdown_team(MY_TEAM, get_data_handler());
-    let iris_team = parse_showdown_team(IRIS_TEAM, get_data_handler());
+    let mut battle = Battle::new_battle(get_data_handler(), my_team, iris_team, generation, seed);
+    battle.set_controllers(Box::new(TextController::new(get_data_handler())), Box::new(Gen5AI::new(get_data_handler(), seed)));
+    battle.add_event_sink(Box::new(TextLogger));
+
+    let result = battle.simulate();
+
+    println!();
+
+    match (result.winner, result.reason) {
+        (BattleWinner::Side1, BattleEndReason::Forfeit) => println!("Iris forfeited!"),
+        (BattleWinner::Side2, BattleEndReason::Forfeit) => println!("You forfeited!"),
+        (BattleWinner::Side1, _) => println!("You defeated Iris!"),
+        (BattleWinner::Side2, _) => println!("You lost to Iris!"),
+        (BattleWinner::Draw, _) => println!("The battle ended in a draw!")
+    }
 
-    let mut battle = Battle::new_battle(get_data_handler(), my_team, iris_team, 5);
-    battle.simulate();
+    println!("The battle lasted {} turns", result.turns);
+    println!("Your team's HP: {:?}", result.side1_hp);
+    println!("Iris's team's HP: {:?}", result.side2_hp);
 
     Ok(())
 
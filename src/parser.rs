@@ -33,6 +33,13 @@ fn parse_stats_line(mut line: &str, default: u8) -> [u8; 6] {
 
 }
 
+// showdown exports assume every move has max pp ups
+const SHOWDOWN_PP_UPS: u8 = 3;
+
+fn calc_max_pp(base_pp: u8, pp_ups: u8) -> u8 {
+    base_pp + base_pp / 5 * pp_ups
+}
+
 fn calc_hp(base: u8, iv: u8, ev: u8, level: u8) -> u16 {
     let level_u16 = level as u16;
     (2 * (base as u16) + (iv as u16) + ((ev as u16) / 4)) * level_u16 / 100 + level_u16 + 10
@@ -42,17 +49,59 @@ fn calc_stat(base: u8, iv: u8, ev: u8, level: u8, nature: u8) -> u16  {
     ((2 * (base as u16) + (iv as u16) + ((ev as u16) / 4)) * (level as u16) / 100 + 5) * (nature as u16) / 10
 }
 
-pub fn parse_showdown(showdown_export_lines: &[&str], data_handler: &DataHandler) -> Pokemon {
+// gen 1 and 2 use dvs from 0 to 15 and up to 65535 stat exp
+fn calc_stat_exp_bonus(stat_exp: u16) -> u16 {
+    ((stat_exp as f64).sqrt().ceil() as u16).min(255) / 4
+}
+
+// the unified special stat by national dex number, the database only has the split stats from gen 2
+const GEN_1_SPECIAL: [u8; 151] = [
+    65, 80, 100, 50, 65, 85, 50, 65, 85, 20, 25, 80, 20, 25, 45,
+    35, 50, 70, 25, 50, 31, 61, 40, 65, 50, 90, 30, 55, 40, 55,
+    75, 40, 55, 75, 60, 85, 65, 100, 25, 50, 40, 75, 75, 85, 100,
+    55, 80, 40, 90, 45, 70, 40, 65, 50, 80, 35, 60, 50, 80, 40,
+    50, 70, 105, 120, 135, 35, 50, 65, 70, 85, 100, 100, 120, 30, 45,
+    55, 65, 80, 40, 80, 95, 120, 58, 35, 60, 70, 95, 40, 65, 45,
+    85, 100, 115, 130, 30, 90, 115, 25, 50, 55, 80, 60, 125, 40, 50,
+    35, 35, 60, 60, 85, 30, 45, 105, 100, 40, 70, 95, 50, 80, 70,
+    100, 100, 55, 95, 85, 85, 55, 40, 20, 100, 95, 48, 65, 110, 110,
+    110, 75, 90, 115, 45, 70, 60, 65, 125, 125, 125, 50, 70, 100, 154,
+    100
+];
+
+fn calc_hp_gen_1_2(base: u8, dv: u8, stat_exp: u16, level: u8) -> u16 {
+    let level_u16 = level as u16;
+    ((base as u16 + dv as u16) * 2 + calc_stat_exp_bonus(stat_exp)) * level_u16 / 100 + level_u16 + 10
+}
+
+fn calc_stat_gen_1_2(base: u8, dv: u8, stat_exp: u16, level: u8) -> u16 {
+    ((base as u16 + dv as u16) * 2 + calc_stat_exp_bonus(stat_exp)) * (level as u16) / 100 + 5
+}
+
+// the hp dv is made from the lowest bit of the others
+fn get_hp_dv(attack_dv: u8, defense_dv: u8, speed_dv: u8, special_dv: u8) -> u8 {
+    (attack_dv & 1) << 3 | (defense_dv & 1) << 2 | (speed_dv & 1) << 1 | special_dv & 1
+}
+
+// showdown exports gen 1 and 2 sets with ivs of twice the dv and evs of the square root of the stat exp
+fn showdown_to_dv(iv: u8) -> u8 {
+    iv / 2
+}
+
+fn showdown_to_stat_exp(ev: u8) -> u16 {
+    ev as u16 * ev as u16
+}
+
+pub fn parse_showdown(showdown_export_lines: &[&str], data_handler: &DataHandler, generation: u8) -> Pokemon {
 
     let mut lines_iter = showdown_export_lines.iter();
     let mut line1 = *lines_iter.next().unwrap();
 
-    let mut gender = Gender::Male;
     let mut held_item = None;
 
     if let Some((display_name, held_item_name)) = line1.split_once('@') {
         let id = data_handler.item_name_table.lookup_id(held_item_name.trim()).unwrap();
-        held_item = Some(Item::from_db_id(id));
+        held_item = Item::from_db_id(id);
         line1 = display_name;
     }
 
@@ -72,7 +121,7 @@ pub fn parse_showdown(showdown_export_lines: &[&str], data_handler: &DataHandler
             (split[0], split[0])
         }
         else {
-            (split[1], split[0])
+            (second, split[0])
         }
     }
     else {
@@ -80,8 +129,6 @@ pub fn parse_showdown(showdown_export_lines: &[&str], data_handler: &DataHandler
         (split[1].trim_end_matches(')'), split[0])
     };
 
-    let form_name = line1.trim();
-
     let form_id = data_handler.form_name_table.lookup_id(form_name).unwrap();
     let pokemon_data = data_handler.get_pokemon_data(form_id);
 
@@ -90,8 +137,10 @@ pub fn parse_showdown(showdown_export_lines: &[&str], data_handler: &DataHandler
 
     let mut level = 100;
     let mut ability = Ability::None;
-    let mut ivs = [0; 6];
-    let mut evs = [0; 6];
+    // showdown maxes out dvs and stat exp by default
+    let mut ivs = if generation <= 2 {[31; 6]} else {[0; 6]};
+    let default_ev = if generation <= 2 {252} else {0};
+    let mut evs = [default_ev; 6];
     let mut nature_id = ID::new(1).unwrap();
     let mut friendship = 255;
 
@@ -99,7 +148,7 @@ pub fn parse_showdown(showdown_export_lines: &[&str], data_handler: &DataHandler
 
         if line.starts_with("Ability:") {
             let id = data_handler.ability_name_table.lookup_id(line.trim_start_matches("Ability:").trim()).unwrap();
-            ability = Ability::from_db_id(id);
+            ability = Ability::from_db_id(id).unwrap_or(Ability::None);
         }
         else if line.starts_with("Level:") {
             level = line.trim_start_matches("Level:").trim().parse().unwrap()
@@ -110,7 +159,7 @@ pub fn parse_showdown(showdown_export_lines: &[&str], data_handler: &DataHandler
             friendship = line.trim_start_matches("Happiness:").trim().parse().unwrap()
         }
         else if line.starts_with("EVs:") {
-            evs = parse_stats_line(line.trim_start_matches("EVs:").trim(), 0);
+            evs = parse_stats_line(line.trim_start_matches("EVs:").trim(), default_ev);
         }
         else if line.starts_with("IVs:") {
             ivs = parse_stats_line(line.trim_start_matches("IVs:").trim(), 31);
@@ -123,7 +172,14 @@ pub fn parse_showdown(showdown_export_lines: &[&str], data_handler: &DataHandler
                 move_name = name;
             }
         
-            moves[move_count] = data_handler.move_name_table.lookup_id(move_name.trim());
+            moves[move_count] = data_handler.move_name_table.lookup_id(move_name.trim()).map(|id| {
+                let max_pp = calc_max_pp(data_handler.get_move_in_generation(id, generation).pp, SHOWDOWN_PP_UPS);
+                MoveSlot {
+                    id,
+                    pp: max_pp,
+                    max_pp
+                }
+            });
             move_count += 1;
         
         }
@@ -133,23 +189,65 @@ pub fn parse_showdown(showdown_export_lines: &[&str], data_handler: &DataHandler
         }
     }
 
-    let mut nature_modifiers = [10; 5];
-    let (increased_stat, decreased_stat) = data_handler.get_nature_changed_stats(nature_id);
-    nature_modifiers[increased_stat as usize - 2] += 1;
-    nature_modifiers[decreased_stat as usize - 2] -= 1;
+    let (ability1, ability2, hidden_ability) = pokemon_data.get_abilities(generation);
+
+    // a missing ability or one the species couldn't have in this generation falls back to its first
+    if ability == Ability::None || ability != ability1 && Some(ability) != ability2 && Some(ability) != hidden_ability {
+        ability = ability1;
+    }
+
+    let (hp, attack, defense, special_attack, special_defense, speed) = if generation <= 2 {
 
-    let hp = if form_id == POKEMON_SHEDINJA {
-        1
+        let dvs = ivs.map(showdown_to_dv);
+        let stat_exp = evs.map(showdown_to_stat_exp);
+
+        // both specials share the special dv and stat exp
+        let hp_dv = get_hp_dv(dvs[1], dvs[2], dvs[5], dvs[3]);
+
+        // gen 1 has a single special stat
+        let (special_attack, special_defense) = if generation == 1 {
+            let special = calc_stat_gen_1_2(GEN_1_SPECIAL[pokemon_data.species_id.get() as usize - 1], dvs[3], stat_exp[3], level);
+            (special, special)
+        }
+        else {
+            (
+                calc_stat_gen_1_2(pokemon_data.special_attack,  dvs[3], stat_exp[3], level),
+                calc_stat_gen_1_2(pokemon_data.special_defense, dvs[3], stat_exp[3], level)
+            )
+        };
+
+        (
+            calc_hp_gen_1_2(pokemon_data.hp, hp_dv, stat_exp[0], level),
+            calc_stat_gen_1_2(pokemon_data.attack,  dvs[1], stat_exp[1], level),
+            calc_stat_gen_1_2(pokemon_data.defense, dvs[2], stat_exp[2], level),
+            special_attack,
+            special_defense,
+            calc_stat_gen_1_2(pokemon_data.speed,   dvs[5], stat_exp[5], level)
+        )
     }
     else {
-        calc_hp(pokemon_data.hp, ivs[0], evs[0], level)
-    };
 
-    let attack              = calc_stat(pokemon_data.attack,             ivs[1], evs[1], level, nature_modifiers[0]);
-    let defense             = calc_stat(pokemon_data.defense,            ivs[2], evs[2], level, nature_modifiers[1]);
-    let special_attack      = calc_stat(pokemon_data.special_attack,     ivs[3], evs[3], level, nature_modifiers[2]);
-    let special_defense     = calc_stat(pokemon_data.special_defense,    ivs[4], evs[4], level, nature_modifiers[3]);
-    let speed               = calc_stat(pokemon_data.speed,              ivs[5], evs[5], level, nature_modifiers[4]);
+        let mut nature_modifiers = [10; 5];
+        let (increased_stat, decreased_stat) = data_handler.get_nature_changed_stats(nature_id);
+        nature_modifiers[increased_stat as usize - 2] += 1;
+        nature_modifiers[decreased_stat as usize - 2] -= 1;
+
+        let hp = if form_id == POKEMON_SHEDINJA {
+            1
+        }
+        else {
+            calc_hp(pokemon_data.hp, ivs[0], evs[0], level)
+        };
+
+        (
+            hp,
+            calc_stat(pokemon_data.attack,             ivs[1], evs[1], level, nature_modifiers[0]),
+            calc_stat(pokemon_data.defense,            ivs[2], evs[2], level, nature_modifiers[1]),
+            calc_stat(pokemon_data.special_attack,     ivs[3], evs[3], level, nature_modifiers[2]),
+            calc_stat(pokemon_data.special_defense,    ivs[4], evs[4], level, nature_modifiers[3]),
+            calc_stat(pokemon_data.speed,              ivs[5], evs[5], level, nature_modifiers[4])
+        )
+    };
     
     Pokemon {
         id: form_id,
@@ -168,11 +266,14 @@ pub fn parse_showdown(showdown_export_lines: &[&str], data_handler: &DataHandler
         non_volatile_status: None,
         volatile_status: VolatileStatus::default(),
         gender,
-        friendship
+        friendship,
+        ability_triggered: false,
+        is_side1: true,
+        team_slot: 0
     }
 }
 
-pub fn parse_showdown_team(block: &str, data_handler: &DataHandler) -> Vec<Pokemon> {
+pub fn parse_showdown_team(block: &str, data_handler: &DataHandler, generation: u8) -> Vec<Pokemon> {
 
     let mut team = Vec::new();
     let mut mon_lines = Vec::new();
@@ -180,7 +281,7 @@ pub fn parse_showdown_team(block: &str, data_handler: &DataHandler) -> Vec<Pokem
     for line in block.trim().split('\n') {
         
         if line.is_empty() {
-            team.push(parse_showdown(&mon_lines, &data_handler));
+            team.push(parse_showdown(&mon_lines, data_handler, generation));
             mon_lines.clear();
         }
         else {
@@ -189,9 +290,66 @@ pub fn parse_showdown_team(block: &str, data_handler: &DataHandler) -> Vec<Pokem
     }
 
     if !mon_lines.is_empty() {
-        team.push(parse_showdown(&mon_lines, &data_handler));
+        team.push(parse_showdown(&mon_lines, data_handler, generation));
     }
     
     team
 
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::test_utils::*;
+
+    fn parse_ability(set: &str, generation: u8) -> Ability {
+        parse_showdown_team(set, get_data_handler(), generation)[0].ability
+    }
+
+    fn parse_stats(set: &str, generation: u8) -> [u16; 6] {
+        let mon = &parse_showdown_team(set, get_data_handler(), generation)[0];
+        [mon.max_hp, mon.attack, mon.defense, mon.special_attack, mon.special_defense, mon.speed]
+    }
+
+    #[test]
+    fn gen_1_2_stats() {
+
+        // chansey's gen 1 special of 105 became 35 sp. atk and 105 sp. def
+        assert_eq!(parse_stats("Chansey\n- Pound", 1), [703, 108, 108, 308, 308, 198]);
+        assert_eq!(parse_stats("Chansey\n- Pound", 2), [703, 108, 108, 168, 308, 198]);
+
+        // an odd attack dv clears a bit of the hp dv
+        assert_eq!(parse_stats("Chansey\nIVs: 0 Atk\n- Pound", 1)[..2], [687, 78]);
+
+        assert_eq!(parse_stats("Mewtwo\nLevel: 50\n- Psychic", 1)[3], 205);
+    }
+
+    #[test]
+    fn abilities_follow_the_generation() {
+
+        // levitate became cursed body in gen 7
+        assert_eq!(parse_ability("Gengar\nAbility: Levitate\n- Curse", 6), Ability::Levitate);
+        assert_eq!(parse_ability("Gengar\nAbility: Levitate\n- Curse", 7), Ability::CursedBody);
+
+        // hidden abilities came in gen 5
+        assert_eq!(parse_ability("Snorlax\nAbility: Gluttony\n- Curse", 4), Ability::Immunity);
+        assert_eq!(parse_ability("Snorlax\nAbility: Gluttony\n- Curse", 5), Ability::Gluttony);
+
+        assert_eq!(parse_ability("Snorlax\n- Curse", 5), Ability::Immunity);
+        assert_eq!(parse_ability("Snorlax\nAbility: Thick Fat\n- Curse", 2), Ability::None);
+    }
+
+    #[test]
+    fn nicknames_and_genders() {
+
+        let team = parse_showdown_team("Bob (Snorlax) (F)\n- Curse\n\nJim (Lapras)\n- Surf\n\nJolteon (M)\n- Growl", get_data_handler(), 5);
+
+        assert_eq!((&*team[0].name, team[0].gender), ("Bob", Gender::Female));
+        assert_eq!(&*team[1].name, "Jim");
+        assert_eq!((&*team[2].name, team[2].gender), ("Jolteon", Gender::Male));
+
+        assert_eq!(team[0].id, parse_team("Snorlax\n- Curse", 5)[0].id);
+        assert_eq!(team[1].id, parse_team("Lapras\n- Surf", 5)[0].id);
+    }
 }
\ No newline at end of file
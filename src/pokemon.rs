@@ -1,7 +1,10 @@
-use crate::{bitfield, db_enums::*, log};
+use crate::db_enums::*;
+use crate::logging::EventPokemon;
 
 pub type ID = std::num::NonZeroU16;
 
+// only built from database ids
+#[allow(dead_code)]
 #[repr(u8)]
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum Type {
@@ -34,13 +37,24 @@ impl Type {
             Self::Unknown
         }
         else {
-            unsafe {std::mem::transmute(id as u8)}
+            unsafe {std::mem::transmute::<u8, Self>(id as u8)}
+        }
+    }
+}
+
+impl Type {
+    // before gen 4 the type decided whether a move was physical or special
+    pub fn get_split_class(&self) -> MoveClass {
+        match self {
+            Self::Fire | Self::Water | Self::Grass | Self::Electric | Self::Psychic | Self::Ice | Self::Dragon | Self::Dark => MoveClass::Special,
+            _ => MoveClass::Physical
         }
     }
 }
 
 const NUM_TYPES: usize = std::mem::variant_count::<Type>();
 
+#[derive(Clone)]
 pub struct TypeChart([[u8; NUM_TYPES]; NUM_TYPES]);
 
 impl TypeChart {
@@ -55,6 +69,13 @@ impl TypeChart {
     pub fn get(&self, attacker: Type, defender: Type) -> u8 {
         self.0[attacker as usize][defender as usize]
     }
+
+    pub fn clear_type(&mut self, cleared_type: Type) {
+        for other in 0..NUM_TYPES {
+            self.0[cleared_type as usize][other] = 100;
+            self.0[other][cleared_type as usize] = 100;
+        }
+    }
 }
 
 pub struct PokemonData {
@@ -71,24 +92,82 @@ pub struct PokemonData {
     pub ability1: Ability,
     pub ability2: Option<Ability>,
     pub hidden_ability: Option<Ability>,
-    pub weight: f32,   
+    pub weight: f32,
+    // (last generation, type1, type2) in ascending order
+    pub past_types: Vec<(u8, Type, Option<Type>)>,
+    // (last generation, slot, ability) in ascending order
+    pub past_abilities: Vec<(u8, u8, Ability)>
 }
 
 impl PokemonData {
 
-    pub fn is_type(&self, check_type: Type) -> bool {
+    pub fn get_types(&self, generation: u8) -> (Type, Option<Type>) {
+
+        for &(last_generation, type1, type2) in &self.past_types {
+            if generation <= last_generation {
+                return (type1, type2);
+            }
+        }
+
+        (self.type1, self.type2)
+
+    }
+
+    // abilities came in gen 3 and hidden abilities in gen 5
+    pub fn get_abilities(&self, generation: u8) -> (Ability, Option<Ability>, Option<Ability>) {
+
+        if generation <= 2 {
+            return (Ability::None, None, None);
+        }
+
+        let mut abilities = (self.ability1, self.ability2, self.hidden_ability);
+        let mut found_slots = [false; 3];
+
+        for &(last_generation, slot, ability) in &self.past_abilities {
+
+            if generation > last_generation || found_slots[slot as usize - 1] {
+                continue;
+            }
+
+            found_slots[slot as usize - 1] = true;
+
+            match slot {
+                1 => abilities.0 = ability,
+                2 => abilities.1 = Some(ability),
+                _ => abilities.2 = Some(ability)
+            }
+        }
+
+        if generation <= 4 {
+            abilities.2 = None;
+        }
+
+        abilities
+
+    }
+
+    pub fn is_type(&self, check_type: Type, generation: u8) -> bool {
+
+        let (type1, type2) = self.get_types(generation);
         
-        if let Some(type2) = self.type2 {
+        if let Some(type2) = type2 {
             if type2 == check_type {
                 return true;
             }
         }
             
-        self.type1 == check_type
+        type1 == check_type
 
     }
 }
 
+#[derive(Debug, Clone, Copy)]
+pub struct MoveSlot {
+    pub id: ID,
+    pub pp: u8,
+    pub max_pp: u8
+}
+
 #[derive(Debug, Clone)]
 pub struct Pokemon {
     pub id: ID,
@@ -102,35 +181,75 @@ pub struct Pokemon {
     pub special_attack: u16,
     pub special_defense: u16,
     pub speed: u16,
-    pub moves: [Option<ID>; 4],
+    pub moves: [Option<MoveSlot>; 4],
     pub non_volatile_status: Option<NonVolatileStatus>,
     pub volatile_status: VolatileStatus,
     pub item: Option<Item>,
     pub gender: Gender,
-    pub friendship: u8
+    pub friendship: u8,
+    // for abilities that only activate once per battle
+    pub ability_triggered: bool,
+    // where the pokemon sits in the battle, set once the battle state is made
+    pub is_side1: bool,
+    pub team_slot: u8
 }
 
 impl Pokemon {
 
-    pub fn apply_stat_changes(&mut self, stat: Stat, stages: i8) {
-        
-        self.volatile_status.stat_stages[stat as usize] = (self.volatile_status.stat_stages[stat as usize] + stages).clamp(-6, 6);
-
-        log!(
-            "{}'s {:?} {}!",
-            self.name,
-            stat,
-            match stages {
-                ..=-3 => "severely fell",
-                -2 => "harshly fell",
-                -1 => "fell",
-                0 => unreachable!(),
-                1 => "rose",
-                2 => "rose sharply",
-                3.. => "rose drastically",
+    pub fn get_event_pokemon(&self) -> EventPokemon {
+        EventPokemon {
+            is_side1: self.is_side1,
+            team_slot: self.team_slot,
+            name: self.name.clone()
+        }
+    }
+
+    // returns the number of stages actually applied after clamping
+    pub fn apply_stat_changes(&mut self, stat: Stat, stages: i8) -> i8 {
+
+        let old_stages = self.volatile_status.stat_stages[stat as usize];
+        let new_stages = (old_stages + stages).clamp(-6, 6);
+
+        self.volatile_status.stat_stages[stat as usize] = new_stages;
+
+        new_stages - old_stages
+
+    }
+
+    pub fn get_move_slot(&self, move_id: ID) -> Option<&MoveSlot> {
+        self.moves.iter().flatten().find(|slot| slot.id == move_id)
+    }
+
+    pub fn get_move_slot_mut(&mut self, move_id: ID) -> Option<&mut MoveSlot> {
+        self.moves.iter_mut().flatten().find(|slot| slot.id == move_id)
+    }
+
+    pub fn has_usable_move(&self) -> bool {
+        self.moves.iter().flatten().any(|slot| slot.pp > 0)
+    }
+
+    // carried over from the last turn, the controller doesn't get to choose
+    pub fn get_locked_move(&self) -> Option<ID> {
+
+        for effect in &self.volatile_status.effects {
+            match effect {
+                VolatileStatusEffect::Charging(move_id) => return Some(*move_id),
+                VolatileStatusEffect::Recharge |
+                VolatileStatusEffect::Rampage(_) |
+                VolatileStatusEffect::Rolling(_) |
+                VolatileStatusEffect::Uproar(_) |
+                VolatileStatusEffect::Bide(..)
+                    => return self.volatile_status.last_move,
+                _ => {}
             }
-        );
+        }
+
+        None
+
+    }
 
+    pub fn get_stat_stage(&self, stat: Stat) -> i8 {
+        self.volatile_status.stat_stages[stat as usize]
     }
 
     pub fn reset_stat_changes(&mut self) {
@@ -145,12 +264,10 @@ impl Pokemon {
         self.hp = self.max_hp.min(self.hp + hp);
     }
     
-    pub fn get_stat(&self, stat: Stat) -> u16 {
-
-        let stat_stages = self.volatile_status.stat_stages[stat as usize];
+    pub fn get_stat_at_stage(&self, stat: Stat, stat_stages: i8) -> u16 {
 
         let mut stat_val = match stat {
-            Stat::Evasion | Stat::Accuracy => todo!(),
+            Stat::Evasion | Stat::Accuracy => return get_accuracy_stage_percent(stat_stages) as u16,
             Stat::Attack => self.attack,
             Stat::Defense => self.defense,
             Stat::SpecialAttack => self.special_attack,
@@ -203,14 +320,20 @@ pub enum NonVolatileStatus {
     Freeze,
     Paralysis,
     Poison,
-    BadlyPoison,
-    Sleep
+    // turns since being badly poisoned, damage ramps with it
+    BadlyPoison(u8),
+    // move attempts left until waking up
+    Sleep(u8)
 }
 
+// not all of these are implemented yet
+#[allow(dead_code)]
 #[derive(Debug, Clone, Copy)]
 pub enum VolatileStatusEffect {
     AbilityChange(Ability),
     AbilitySuppression,
+    // mold breaker and co, only during the attacker's move
+    AbilityIgnored,
     TypeChange(!),
     Mimic,
     Substitute(u16),
@@ -238,38 +361,79 @@ pub enum VolatileStatusEffect {
     DefenseCurl,
     NoRetreat,
     Octolock,
-    Disable(u8),
+    Trapped,
+    JawLock,
+    ParadoxBoost(Stat),
+    // fainted allies when it switched in
+    SupremeOverlord(u8),
+    Disable(ID, u8),
     Embargo(u8),
     HealBlock(u8),
-    Imprison(!),
+    Imprison,
     Taunt(u8),
     ThroatChop(u8),
-    Torment(u8),
+    Torment,
     Confusion(u8),
-    Infatuation(!),
+    Infatuation,
     GettingPumped,
     GuardSplit(!),
     PowerSplit(!),
     SpeedSwap(!),
     PowerTrick,
     Choiced(u8),
-    Encore(u8),
-    Rampage(!),
-    Rolling(!),
+    Encore(ID, u8),
+    Rampage(u8),
+    Rolling(u8),
     Uproar(u8),
     Bide(u16, u8),
     Recharge,
-    Charging(!),
-    SemiInvulernable(!),
+    Charging(ID),
+    SemiInvulernable(SemiInvulnerableKind),
+    SkyDropped,
     Flinch,
     Endure,
     CenterOfAttention,
     MagicCoat,
-    Protect(!)
+    Protect(ProtectKind)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SemiInvulnerableKind {
+    Airborne,
+    Underground,
+    Underwater,
+    Vanished
+}
+
+// wide guard, quick guard, mat block and crafty shield cover the whole side,
+// which in singles is just the active pokemon
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ProtectKind {
+    Protect,
+    KingsShield,
+    SpikyShield,
+    BanefulBunker,
+    SilkTrap,
+    BurningBulwark,
+    Obstruct,
+    WideGuard,
+    QuickGuard,
+    MatBlock,
+    CraftyShield
+}
+
+impl VolatileStatusEffect {
+    pub fn is_baton_passed(&self) -> bool {
+        matches!(self,
+            Self::AbilitySuppression | Self::Substitute(_) | Self::Curse | Self::PerishSong(_) | Self::Seed | Self::MagnetRise |
+            Self::Telekinesis | Self::AquaRing | Self::Ingrain | Self::Embargo(_) | Self::HealBlock(_) | Self::Confusion(_) |
+            Self::GettingPumped | Self::PowerTrick | Self::Trapped
+        )
+    }
 }
 
 #[repr(C)]
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum Stat {
     Attack,
     Defense,
@@ -280,25 +444,47 @@ pub enum Stat {
     Accuracy
 }
 
-const NUM_STATS: usize = std::mem::variant_count::<Stat>();
+pub const NUM_STATS: usize = std::mem::variant_count::<Stat>();
+
+// accuracy and evasion stages move in thirds instead of halves
+pub fn get_accuracy_stage_percent(stages: i8) -> u32 {
+    if stages < 0 {
+        300 / (3 + (-stages as u32))
+    }
+    else {
+        100 * (3 + stages as u32) / 3
+    }
+}
 
 #[derive(Debug, Clone)]
 pub struct VolatileStatus {
     pub stat_stages: [i8; NUM_STATS],
-    pub effects: Vec<VolatileStatusEffect>
+    pub effects: Vec<VolatileStatusEffect>,
+    pub last_move: Option<ID>,
+    pub protect_chain: u8,
+    pub turns_active: u8,
+    pub moved_this_turn: bool
 }
 
 impl VolatileStatus {
     pub fn default() -> Self {
         Self {
             stat_stages: [0; NUM_STATS],
-            effects: Vec::new()
+            effects: Vec::new(),
+            last_move: None,
+            protect_chain: 0,
+            turns_active: 0,
+            moved_this_turn: false
         }
     }
 
     pub fn clear(&mut self) {
         self.stat_stages = [0; NUM_STATS];
         self.effects.clear();
+        self.last_move = None;
+        self.protect_chain = 0;
+        self.turns_active = 0;
+        self.moved_this_turn = false;
     }
 
     pub fn add(&mut self, effect: VolatileStatusEffect) {
@@ -309,7 +495,10 @@ impl VolatileStatus {
 
     }
 
-    pub fn decriment_counters(&mut self) {
+    // returns the effects that ended
+    pub fn decriment_counters(&mut self) -> Vec<VolatileStatusEffect> {
+
+        let mut ended = Vec::new();
 
         for idx in (0..self.effects.len()).rev() {
             
@@ -317,16 +506,13 @@ impl VolatileStatus {
 
             let turns = match effect {
                 VolatileStatusEffect::Bind {health_fraction :_, turns} | 
-                VolatileStatusEffect::PerishSong(turns) |
                 VolatileStatusEffect::LaserFocus(turns) |
-                VolatileStatusEffect::Disable(turns) |
+                VolatileStatusEffect::Disable(_, turns) |
                 VolatileStatusEffect::Embargo(turns) |
                 VolatileStatusEffect::HealBlock(turns) |
                 VolatileStatusEffect::Taunt(turns) |
                 VolatileStatusEffect::ThroatChop(turns) |
-                VolatileStatusEffect::Torment(turns) |
-                VolatileStatusEffect::Confusion(turns) |
-                VolatileStatusEffect::Encore(turns) |
+                VolatileStatusEffect::Encore(_, turns) |
                 VolatileStatusEffect::Uproar(turns) |
                 VolatileStatusEffect::Bide(_, turns)
                     => turns,
@@ -336,9 +522,12 @@ impl VolatileStatus {
             *turns -= 1;
 
             if *turns == 0 {
-                self.effects.remove(idx);
+                ended.push(self.effects.remove(idx));
             }
         }
+
+        ended
+
     }
 }
 
@@ -349,6 +538,7 @@ pub enum Weather {
     Sandstorm,
     Hail,
     Snow,
+    #[allow(dead_code)]
     Fog,
     ExtremeSun,
     HeavyRain,
@@ -402,12 +592,90 @@ bitfield!(
     get_happy_hour set_happy_hour
 );
 
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SideCondition {
+    Reflect,
+    LightScreen,
+    Safeguard,
+    Mist,
+    AuroraVeil,
+    Tailwind,
+    LuckyChant
+}
+
+const SIDE_CONDITIONS: [SideCondition; 7] = [
+    SideCondition::Reflect,
+    SideCondition::LightScreen,
+    SideCondition::Safeguard,
+    SideCondition::Mist,
+    SideCondition::AuroraVeil,
+    SideCondition::Tailwind,
+    SideCondition::LuckyChant
+];
+
 impl SideEffects {
+    pub fn get_turns(&self, condition: SideCondition) -> u8 {
+        match condition {
+            SideCondition::Reflect => self.get_reflect(),
+            SideCondition::LightScreen => self.get_light_screen(),
+            SideCondition::Safeguard => self.get_safeguard(),
+            SideCondition::Mist => self.get_mist(),
+            SideCondition::AuroraVeil => self.get_aurora_veil(),
+            SideCondition::Tailwind => self.get_tailwind(),
+            SideCondition::LuckyChant => self.get_lucky_chant()
+        }
+    }
+    pub fn set_turns(&mut self, condition: SideCondition, turns: u8) {
+        match condition {
+            SideCondition::Reflect => self.set_reflect(turns),
+            SideCondition::LightScreen => self.set_light_screen(turns),
+            SideCondition::Safeguard => self.set_safeguard(turns),
+            SideCondition::Mist => self.set_mist(turns),
+            SideCondition::AuroraVeil => self.set_aurora_veil(turns),
+            SideCondition::Tailwind => self.set_tailwind(turns),
+            SideCondition::LuckyChant => self.set_lucky_chant(turns)
+        }
+    }
+    pub fn is_active(&self, condition: SideCondition) -> bool {
+        self.get_turns(condition) > 0
+    }
+    // returns the conditions that ran out
+    pub fn decriment_counters(&mut self) -> Vec<SideCondition> {
+
+        let mut ended = Vec::new();
+
+        for condition in SIDE_CONDITIONS {
+
+            let turns = self.get_turns(condition);
+
+            if turns == 0 {
+                continue;
+            }
+
+            self.set_turns(condition, turns - 1);
+
+            if turns == 1 {
+                ended.push(condition);
+            }
+        }
+
+        ended
+
+    }
     pub fn add_spikes(&mut self) {
         self.set_spikes((self.get_spikes() + 1).min(3));
     }
     pub fn add_toxic_spikes(&mut self) {
-        self.set_spikes((self.get_spikes() + 1).min(2));
+        self.set_toxic_spikes((self.get_toxic_spikes() + 1).min(2));
+    }
+    pub fn has_hazards(&self) -> bool {
+        self.get_stealth_rock() || self.get_spikes() > 0 || self.get_toxic_spikes() > 0 || self.get_sticky_web()
+    }
+    pub fn clear_hazards(&mut self) {
+        self.set_stealth_rock(false);
+        self.set_spikes(0);
+        self.set_toxic_spikes(0);
+        self.set_sticky_web(false);
     }
 }
 
@@ -455,16 +723,165 @@ bitfield!(
     get_dance set_dance
 );
 
+#[derive(Clone)]
 pub struct Move {
     pub id: ID,
     pub name: Box<str>,
     pub class: MoveClass,
     pub move_type: Type,
     pub priority: i8,
+    pub pp: u8,
     pub power: Option<u8>,
     pub accuracy: Option<u8>,
     pub effect: MoveEffect,
     pub effect_chance: Option<u8>,
+    pub crit_rate: u8,
+    pub min_hits: u8,
+    pub max_hits: u8,
     pub target: MoveTarget,
-    pub flags: MoveFlags
+    pub flags: MoveFlags,
+    pub meta: MoveMeta
+}
+
+impl Move {
+    pub fn targets_user(&self) -> bool {
+        matches!(self.target,
+            MoveTarget::User | MoveTarget::UsersField | MoveTarget::UserOrAlly | MoveTarget::Ally | MoveTarget::UserAndAllies | MoveTarget::AllAllies
+        )
+    }
+
+    // hits the opposing pokemon itself rather than its side of the field
+    pub fn targets_opponent(&self) -> bool {
+        matches!(self.target,
+            MoveTarget::SelectedPokemon | MoveTarget::SelectedPokemonMeFirst | MoveTarget::RandomOpponent | MoveTarget::AllOpponents | MoveTarget::AllOtherPokemon
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MoveMetaCategory {
+    Damage,
+    Ailment,
+    NetGoodStats,
+    Heal,
+    DamageAilment,
+    Swagger,
+    DamageLower,
+    DamageRaise,
+    DamageHeal,
+    Ohko,
+    WholeFieldEffect,
+    FieldEffect,
+    ForceSwitch,
+    Unique
+}
+
+impl MoveMetaCategory {
+    pub fn from_db_id(id: u8) -> Self {
+
+        match id {
+            0 => Self::Damage,
+            1 => Self::Ailment,
+            2 => Self::NetGoodStats,
+            3 => Self::Heal,
+            4 => Self::DamageAilment,
+            5 => Self::Swagger,
+            6 => Self::DamageLower,
+            7 => Self::DamageRaise,
+            8 => Self::DamageHeal,
+            9 => Self::Ohko,
+            10 => Self::WholeFieldEffect,
+            11 => Self::FieldEffect,
+            12 => Self::ForceSwitch,
+            _ => Self::Unique
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MoveAilment {
+    Unknown,
+    None,
+    Paralysis,
+    Sleep,
+    Freeze,
+    Burn,
+    Poison,
+    Confusion,
+    Infatuation,
+    Trap,
+    Nightmare,
+    Torment,
+    Disable,
+    Yawn,
+    HealBlock,
+    NoTypeImmunity,
+    LeechSeed,
+    Embargo,
+    PerishSong,
+    Ingrain,
+    Silence,
+    TarShot
+}
+
+impl MoveAilment {
+    pub fn from_db_id(id: i8) -> Self {
+
+        match id {
+            0 => Self::None,
+            1 => Self::Paralysis,
+            2 => Self::Sleep,
+            3 => Self::Freeze,
+            4 => Self::Burn,
+            5 => Self::Poison,
+            6 => Self::Confusion,
+            7 => Self::Infatuation,
+            8 => Self::Trap,
+            9 => Self::Nightmare,
+            12 => Self::Torment,
+            13 => Self::Disable,
+            14 => Self::Yawn,
+            15 => Self::HealBlock,
+            17 => Self::NoTypeImmunity,
+            18 => Self::LeechSeed,
+            19 => Self::Embargo,
+            20 => Self::PerishSong,
+            21 => Self::Ingrain,
+            24 => Self::Silence,
+            42 => Self::TarShot,
+            _ => Self::Unknown
+        }
+    }
+}
+
+// chances of 0 on non damaging moves mean the effect always happens
+#[derive(Debug, Clone)]
+pub struct MoveMeta {
+    pub category: MoveMetaCategory,
+    pub ailment: MoveAilment,
+    pub min_turns: Option<u8>,
+    pub max_turns: Option<u8>,
+    pub drain: i8,
+    pub healing: i8,
+    pub ailment_chance: u8,
+    pub flinch_chance: u8,
+    pub stat_chance: u8,
+    pub stat_changes: Vec<(Stat, i8)>
+}
+
+impl MoveMeta {
+    pub fn default() -> Self {
+        Self {
+            category: MoveMetaCategory::Unique,
+            ailment: MoveAilment::None,
+            min_turns: None,
+            max_turns: None,
+            drain: 0,
+            healing: 0,
+            ailment_chance: 0,
+            flinch_chance: 0,
+            stat_chance: 0,
+            stat_changes: Vec::new()
+        }
+    }
 }
\ No newline at end of file
@@ -0,0 +1,50 @@
+use rand::RngCore;
+use rand::rand_core::impls;
+
+// same 64 bit lcg the gen 5 games use, the state is a single u64 so it can be saved and restored
+#[derive(Debug, Clone)]
+pub struct BattleRng {
+    state: u64
+}
+
+impl BattleRng {
+
+    const MULTIPLIER: u64 = 0x5D588B656C078965;
+    const INCREMENT: u64 = 0x269EC3;
+
+    pub fn new(seed: u64) -> Self {
+        Self {
+            state: seed
+        }
+    }
+
+    #[allow(dead_code)]
+    pub fn get_state(&self) -> u64 {
+        self.state
+    }
+
+    #[allow(dead_code)]
+    pub fn set_state(&mut self, state: u64) {
+        self.state = state;
+    }
+
+    fn advance(&mut self) -> u32 {
+        self.state = self.state.wrapping_mul(Self::MULTIPLIER).wrapping_add(Self::INCREMENT);
+        (self.state >> 32) as u32
+    }
+}
+
+impl RngCore for BattleRng {
+
+    fn next_u32(&mut self) -> u32 {
+        self.advance()
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        impls::next_u64_via_u32(self)
+    }
+
+    fn fill_bytes(&mut self, dst: &mut [u8]) {
+        impls::fill_bytes_via_next(self, dst)
+    }
+}
\ No newline at end of file
@@ -1,8 +1,12 @@
 use crate::db_enums::*;
 use crate::database::*;
-use crate::log;
 use crate::pokemon::*;
 use crate::battle::*;
+use crate::rng::*;
+use crate::logging::*;
+use crate::controller::*;
+
+use rand::Rng;
 
 macro_rules! find_effect {
     ($mon:expr, $effect:pat => $on_find:block) => {
@@ -14,48 +18,335 @@ macro_rules! find_effect {
     };
 }
 
+macro_rules! has_effect {
+    ($mon:expr, $effect:pat) => {
+        $mon.volatile_status.effects.iter().any(|effect| matches!(effect, $effect))
+    };
+}
+
+macro_rules! remove_effect {
+    ($mon:expr, $effect:pat) => {
+        $mon.volatile_status.effects.retain(|effect| !matches!(effect, $effect))
+    };
+}
+
+#[derive(Clone, Copy)]
+enum ResidualStep {
+    Weather,
+    StatusCure,
+    HeldItem,
+    AquaRing,
+    Ingrain,
+    LeechSeed,
+    StatusDamage,
+    Nightmare,
+    Curse,
+    Bind,
+    Octolock,
+    PerishSong,
+    SpeedBoost,
+    Orb
+}
+
+// gen 1 and 2 deal these right after each pokemon acts instead of at the end of the turn
+const GEN_1_2_AFTER_ACTION_ORDER: [ResidualStep; 4] = [
+    ResidualStep::StatusDamage,
+    ResidualStep::LeechSeed,
+    ResidualStep::Nightmare,
+    ResidualStep::Curse
+];
+
+const GEN_2_RESIDUAL_ORDER: [ResidualStep; 5] = [
+    ResidualStep::Weather,
+    ResidualStep::Bind,
+    ResidualStep::PerishSong,
+    ResidualStep::HeldItem,
+    ResidualStep::StatusCure
+];
+
+const GEN_3_4_RESIDUAL_ORDER: [ResidualStep; 13] = [
+    ResidualStep::Weather,
+    ResidualStep::Ingrain,
+    ResidualStep::AquaRing,
+    ResidualStep::SpeedBoost,
+    ResidualStep::StatusCure,
+    ResidualStep::HeldItem,
+    ResidualStep::LeechSeed,
+    ResidualStep::StatusDamage,
+    ResidualStep::Nightmare,
+    ResidualStep::Curse,
+    ResidualStep::Bind,
+    ResidualStep::PerishSong,
+    ResidualStep::Orb
+];
+
+const RESIDUAL_ORDER: [ResidualStep; 14] = [
+    ResidualStep::Weather,
+    ResidualStep::StatusCure,
+    ResidualStep::HeldItem,
+    ResidualStep::AquaRing,
+    ResidualStep::Ingrain,
+    ResidualStep::LeechSeed,
+    ResidualStep::StatusDamage,
+    ResidualStep::Nightmare,
+    ResidualStep::Curse,
+    ResidualStep::Bind,
+    ResidualStep::Octolock,
+    ResidualStep::PerishSong,
+    ResidualStep::SpeedBoost,
+    ResidualStep::Orb
+];
+
+// how many times a controller is asked again before its first legal choice is taken
+const MAX_PROMPTS: usize = 3;
+
+// stat stage multipliers before gen 3, also used for accuracy
+const GEN_1_2_STAGES: [u32; 13] = [25, 28, 33, 40, 50, 66, 100, 150, 200, 250, 300, 350, 400];
+
 pub struct HitProperties {
     crit: bool,
-    roll_percent: u8,
+    // 217 to 255 out of 255 before gen 3, otherwise a percentage
+    roll: u8,
+    screened: bool,
+    hit: u8,
+    parental_bond_hit: bool
+}
+
+struct QueuedAction {
+    action: BattleAction,
+    is_side1: bool,
+    priority: i8,
+    fractional_priority: i8,
+    speed: i32,
+    // speed ties are decided randomly
+    tiebreak: u32
+}
+
+impl QueuedAction {
+    fn order_key(&self) -> (i8, i8, i32, u32) {
+        (self.priority, self.fractional_priority, self.speed, self.tiebreak)
+    }
 }
 
 pub struct Simulator {
     data_handler: &'static DataHandler,
+    rng: BattleRng,
+    event_sinks: Vec<Box<dyn EventSink>>,
+    turn: u32,
     pub generation: u8
 }
 
 impl Simulator {
 
-    pub fn new(data_handler: &'static DataHandler, generation: u8) -> Self {
+    pub fn new(data_handler: &'static DataHandler, generation: u8, seed: u64) -> Self {
         Self {
             data_handler,
+            rng: BattleRng::new(seed),
+            event_sinks: Vec::new(),
+            turn: 0,
             generation
         }
     }
 
-    fn get_ability(&self, pokemon: &Pokemon, conditions: &Conditions) -> Ability {
-    
-        find_effect!(pokemon, VolatileStatusEffect::AbilitySuppression => {
-            return Ability::None;
-        });
+    pub fn add_event_sink(&mut self, sink: Box<dyn EventSink>) {
+        self.event_sinks.push(sink);
+    }
+
+    fn emit(&mut self, event: BattleEvent) {
+        for sink in &mut self.event_sinks {
+            sink.on_event(&event);
+        }
+    }
+
+    pub fn get_turn(&self) -> u32 {
+        self.turn
+    }
+
+    #[allow(dead_code)]
+    pub fn get_rng_state(&self) -> u64 {
+        self.rng.get_state()
+    }
+
+    #[allow(dead_code)]
+    pub fn set_rng_state(&mut self, state: u64) {
+        self.rng.set_state(state);
+    }
+
+    fn change_stat(&mut self, pokemon: &mut Pokemon, stat: Stat, stages: i8) {
+
+        let applied = pokemon.apply_stat_changes(stat, stages);
+
+        // gen 1 only has one special stat
+        if self.generation == 1 && applied != 0 {
+            match stat {
+                Stat::SpecialAttack => {pokemon.apply_stat_changes(Stat::SpecialDefense, applied);}
+                Stat::SpecialDefense => {pokemon.apply_stat_changes(Stat::SpecialAttack, applied);}
+                _ => {}
+            }
+        }
+
+        if applied == 0 {
+            self.emit(BattleEvent::StatUnchanged {pokemon: pokemon.get_event_pokemon(), stat, raise: stages > 0});
+        }
+        else {
+            self.emit(BattleEvent::StatChange {pokemon: pokemon.get_event_pokemon(), stat, stages: applied});
+        }
+    }
+
+    fn reset_stats(&mut self, pokemon: &mut Pokemon) {
+        pokemon.reset_stat_changes();
+        self.emit(BattleEvent::StatsReset {pokemon: pokemon.get_event_pokemon()});
+    }
+
+    fn try_apply_status(&mut self, pokemon: &mut Pokemon, status: NonVolatileStatus) {
+
+        if pokemon.non_volatile_status.is_some() || pokemon.hp == 0 {
+            return;
+        }
+
+        pokemon.non_volatile_status = Some(status);
+        self.emit(BattleEvent::StatusApplied {pokemon: pokemon.get_event_pokemon(), status});
+
+    }
+
+    fn add_volatile(&mut self, pokemon: &mut Pokemon, effect: VolatileStatusEffect) {
+        pokemon.volatile_status.add(effect);
+        self.emit(BattleEvent::VolatileApplied {pokemon: pokemon.get_event_pokemon(), effect});
+    }
+
+    fn change_ability(&mut self, pokemon: &mut Pokemon, ability: Ability) {
+        pokemon.volatile_status.add(VolatileStatusEffect::AbilityChange(ability));
+        self.emit(BattleEvent::AbilityChanged {pokemon: pokemon.get_event_pokemon(), ability});
+    }
+
+    fn heal(&mut self, pokemon: &mut Pokemon, hp: u16) {
+
+        let old_hp = pokemon.hp;
+        pokemon.heal(hp);
+
+        if pokemon.hp != old_hp {
+            self.emit(BattleEvent::Heal {pokemon: pokemon.get_event_pokemon(), amount: pokemon.hp - old_hp});
+        }
+    }
+
+    // the ability before anything suppressing it is considered
+    fn get_base_ability(&self, pokemon: &Pokemon) -> Ability {
 
         find_effect!(pokemon, VolatileStatusEffect::AbilityChange(ability) => {
             return *ability;
         });
-    
+
         pokemon.ability
-    
+
+    }
+
+    fn get_ability(&self, pokemon: &Pokemon, conditions: &Conditions) -> Ability {
+
+        let ability = self.get_base_ability(pokemon);
+
+        if self.is_unsuppressable(ability) || pokemon.item == Some(Item::AbilityShield) {
+            return ability;
+        }
+
+        if has_effect!(pokemon, VolatileStatusEffect::AbilitySuppression) ||
+            conditions.neutralizing_gas && ability != Ability::NeutralizingGas ||
+            has_effect!(pokemon, VolatileStatusEffect::AbilityIgnored) && self.is_breakable(ability)
+        {
+            return Ability::None;
+        }
+
+        ability
+
+    }
+
+    fn is_unsuppressable(&self, ability: Ability) -> bool {
+        matches!(ability,
+            Ability::Multitype | Ability::ZenMode | Ability::StanceChange | Ability::Schooling | Ability::Comatose |
+            Ability::ShieldsDown | Ability::Disguise | Ability::RksSystem | Ability::BattleBond | Ability::PowerConstruct |
+            Ability::IceFace | Ability::GulpMissile | Ability::AsOneGlastrier | Ability::AsOneSpectrier | Ability::ZeroToHero |
+            Ability::Commander | Ability::TeraShift
+        )
+    }
+
+    // defensive abilities that mold breaker and co ignore
+    fn is_breakable(&self, ability: Ability) -> bool {
+        matches!(ability,
+            Ability::AromaVeil | Ability::BattleArmor | Ability::BigPecks | Ability::Bulletproof | Ability::ClearBody |
+            Ability::Contrary | Ability::Damp | Ability::Dazzling | Ability::Disguise | Ability::DrySkin | Ability::Filter |
+            Ability::FlashFire | Ability::FlowerGift | Ability::FlowerVeil | Ability::Fluffy | Ability::FriendGuard |
+            Ability::FurCoat | Ability::GrassPelt | Ability::Heatproof | Ability::HeavyMetal | Ability::HyperCutter |
+            Ability::IceFace | Ability::IceScales | Ability::Immunity | Ability::InnerFocus | Ability::Insomnia |
+            Ability::KeenEye | Ability::LeafGuard | Ability::Levitate | Ability::LightMetal | Ability::LightningRod |
+            Ability::Limber | Ability::MagicBounce | Ability::MagmaArmor | Ability::MarvelScale | Ability::MirrorArmor |
+            Ability::MotorDrive | Ability::Multiscale | Ability::Oblivious | Ability::Overcoat | Ability::OwnTempo |
+            Ability::PastelVeil | Ability::PunkRock | Ability::QueenlyMajesty | Ability::SandVeil | Ability::SapSipper |
+            Ability::ShellArmor | Ability::ShieldDust | Ability::Simple | Ability::SnowCloak | Ability::SolidRock |
+            Ability::Soundproof | Ability::StickyHold | Ability::StormDrain | Ability::Sturdy | Ability::SuctionCups |
+            Ability::SweetVeil | Ability::TangledFeet | Ability::Telepathy | Ability::ThickFat | Ability::Unaware |
+            Ability::VitalSpirit | Ability::VoltAbsorb | Ability::WaterAbsorb | Ability::WaterBubble | Ability::WaterVeil |
+            Ability::WhiteSmoke | Ability::WonderGuard | Ability::WonderSkin | Ability::ArmorTail | Ability::EarthEater |
+            Ability::GoodAsGold | Ability::PurifyingSalt | Ability::WellBakedBody | Ability::WindRider | Ability::GuardDog |
+            Ability::TeraShell | Ability::ThermalExchange
+        )
+    }
+
+    fn ignores_abilities(&self, ability: Ability) -> bool {
+        matches!(ability, Ability::MoldBreaker | Ability::Teravolt | Ability::Turboblaze)
+    }
+
+    // neutralizing gas and air lock last as long as their user is on the field
+    fn update_field_abilities(&mut self, side: &Side, other_side: &Side, conditions: &mut Conditions) {
+
+        let had_neutralizing_gas = conditions.neutralizing_gas;
+        conditions.neutralizing_gas = false;
+
+        let actives = [side.get_active(), other_side.get_active()];
+
+        conditions.neutralizing_gas = actives.iter().any(|mon| mon.hp > 0 && self.get_ability(mon, conditions) == Ability::NeutralizingGas);
+        conditions.weather_negated = actives.iter().any(|mon| mon.hp > 0 && matches!(self.get_ability(mon, conditions), Ability::AirLock | Ability::CloudNine));
+
+        if had_neutralizing_gas && !conditions.neutralizing_gas {
+            self.emit(BattleEvent::NeutralizingGasEnded);
+        }
     }
 
     fn pokemon_has_type(&self, pokemon: &Pokemon, check_type: Type) -> bool {
-        todo!()
+        self.data_handler.get_pokemon_data(pokemon.id).is_type(check_type, self.generation)
     }
 
     // protosynthesis/quark drive??????
 
-    fn get_attack_stat(&self, pokemon: &Pokemon, conditions: &Conditions) -> u16 {
+    fn get_staged_stat(&self, pokemon: &Pokemon, stat: Stat, stages: i8) -> u16 {
+
+        if self.generation <= 2 {
+            let stat_val = pokemon.get_stat_at_stage(stat, 0) as u32;
+            return ((stat_val * GEN_1_2_STAGES[(stages + 6) as usize] / 100) as u16).clamp(1, 999);
+        }
+
+        pokemon.get_stat_at_stage(stat, stages)
+
+    }
+
+    // crits ignore stat stages that would hurt the attacker, or every stage in gen 1
+    fn get_attacking_stat_stage(&self, pokemon: &Pokemon, stat: Stat, crit: bool) -> i8 {
+        match (crit, self.generation) {
+            (false, _) => pokemon.get_stat_stage(stat),
+            (true, 1) => 0,
+            (true, _) => pokemon.get_stat_stage(stat).max(0)
+        }
+    }
+
+    fn get_defending_stat_stage(&self, pokemon: &Pokemon, stat: Stat, crit: bool) -> i8 {
+        match (crit, self.generation) {
+            (false, _) => pokemon.get_stat_stage(stat),
+            (true, 1) => 0,
+            (true, _) => pokemon.get_stat_stage(stat).min(0)
+        }
+    }
+
+    fn get_attack_stat(&self, pokemon: &Pokemon, conditions: &Conditions, crit: bool) -> u16 {
 
-        let mut attack = pokemon.get_stat(Stat::Attack);
+        let mut attack = self.get_staged_stat(pokemon, Stat::Attack, self.get_attacking_stat_stage(pokemon, Stat::Attack, crit));
 
         let ability = self.get_ability(pokemon, conditions);
 
@@ -91,30 +382,34 @@ impl Simulator {
                     attack *= 3;
                     attack /= 2;
                 }
+                Item::ThickClub if pokemon.id == POKEMON_MAROWAK => attack *= 2,
                 Item::LightBall if pokemon.id == POKEMON_PIKACHU && self.generation >= 5 => attack *= 2,
                 _ => {}
             }
         }
 
-        if pokemon.non_volatile_status == Some(NonVolatileStatus::Burn) && ability != Ability::Guts {
+        // gen 1 crits ignore burns too
+        if pokemon.non_volatile_status == Some(NonVolatileStatus::Burn) && ability != Ability::Guts && !(crit && self.generation == 1) {
             attack /= 2;
         }
 
+        self.apply_paradox_boost(&mut attack, pokemon, Stat::Attack, conditions);
+
         attack
 
     }
 
-    fn get_defense_stat(&self, pokemon: &Pokemon, conditions: &Conditions) -> u16 {
+    fn get_defense_stat(&self, pokemon: &Pokemon, conditions: &Conditions, crit: bool) -> u16 {
 
-        let mut defense = pokemon.get_stat(Stat::Defense);
+        let mut defense = self.get_staged_stat(pokemon, Stat::Defense, self.get_defending_stat_stage(pokemon, Stat::Defense, crit));
 
         match self.get_ability(pokemon, conditions) {
             Ability::FurCoat => defense *= 2,
-            Ability::GrassPelt => if conditions.is_terrain(Terrain::Grassy) {
+            Ability::GrassPelt if conditions.is_terrain(Terrain::Grassy) => {
                 defense *= 3;
                 defense /= 2;
             }
-            Ability::MarvelScale => if pokemon.non_volatile_status.is_some() {
+            Ability::MarvelScale if pokemon.non_volatile_status.is_some() => {
                 defense *= 3;
                 defense /= 2;
             }
@@ -139,22 +434,24 @@ impl Simulator {
             }
         }
 
+        self.apply_paradox_boost(&mut defense, pokemon, Stat::Defense, conditions);
+
         defense
 
     }
 
-    fn get_special_attack_stat(&self, pokemon: &Pokemon, conditions: &Conditions) -> u16 {
+    fn get_special_attack_stat(&self, pokemon: &Pokemon, conditions: &Conditions, crit: bool) -> u16 {
 
-        let mut special_attack = pokemon.get_stat(Stat::SpecialAttack);
+        let mut special_attack = self.get_staged_stat(pokemon, Stat::SpecialAttack, self.get_attacking_stat_stage(pokemon, Stat::SpecialAttack, crit));
 
         // plus/minus
 
         match self.get_ability(pokemon, conditions) {
-            Ability::HadronEngine => if conditions.is_terrain(Terrain::Electric) {
+            Ability::HadronEngine if conditions.is_terrain(Terrain::Electric) => {
                 special_attack *= 5461;
                 special_attack /= 4096;
             }
-            Ability::SolarPower => if conditions.is_sunny() {
+            Ability::SolarPower if conditions.is_sunny() => {
                 special_attack *= 3;
                 special_attack /= 2;
             }
@@ -174,20 +471,19 @@ impl Simulator {
             }
         }
 
+        self.apply_paradox_boost(&mut special_attack, pokemon, Stat::SpecialAttack, conditions);
+
         special_attack
 
     }
 
-    fn get_special_defense_stat(&self, pokemon: &Pokemon, conditions: &Conditions) -> u16 {
+    fn get_special_defense_stat(&self, pokemon: &Pokemon, conditions: &Conditions, crit: bool) -> u16 {
 
-        let mut special_defense = pokemon.get_stat(Stat::SpecialDefense);
+        let mut special_defense = self.get_staged_stat(pokemon, Stat::SpecialDefense, self.get_defending_stat_stage(pokemon, Stat::SpecialDefense, crit));
 
-        match self.get_ability(pokemon, conditions) {
-            Ability::FlowerGift => if conditions.is_sunny() {
-                special_defense *= 3;
-                special_defense /= 2;
-            }
-            _ => {}
+        if self.get_ability(pokemon, conditions) == Ability::FlowerGift && conditions.is_sunny() {
+            special_defense *= 3;
+            special_defense /= 2;
         }
 
         // eviolite
@@ -212,13 +508,27 @@ impl Simulator {
             special_defense /= 2;
         }
 
+        self.apply_paradox_boost(&mut special_defense, pokemon, Stat::SpecialDefense, conditions);
+
         special_defense
 
     }
 
+    fn get_side_speed(&self, side: &Side, conditions: &Conditions) -> u16 {
+
+        let speed = self.get_speed_stat(side.get_active(), conditions);
+
+        if side.effects.is_active(SideCondition::Tailwind) {
+            speed.saturating_mul(2)
+        }
+        else {
+            speed
+        }
+    }
+
     fn get_speed_stat(&self, pokemon: &Pokemon, conditions: &Conditions) -> u16 {
 
-        let mut speed = pokemon.get_stat(Stat::Speed);
+        let mut speed = self.get_staged_stat(pokemon, Stat::Speed, pokemon.get_stat_stage(Stat::Speed));
 
         let ability = self.get_ability(pokemon, conditions);
 
@@ -253,48 +563,178 @@ impl Simulator {
             }
         }
 
+        self.apply_paradox_boost(&mut speed, pokemon, Stat::Speed, conditions);
+
         speed
 
     }
     
-    fn get_priority(&self, action: &BattleAction) -> i8 {
-        match action {
-            BattleAction::Move(move_id) => self.data_handler.get_move(*move_id).priority,
-            BattleAction::Switch(_) => 8,
-            BattleAction::Item(_) => 8
+    fn get_action_priority(&self, action: &BattleAction, side: &Side, conditions: &Conditions) -> i8 {
+
+        let BattleAction::Move(move_id) = action
+        else {
+            // switches, items and running all go before moves
+            return 8;
+        };
+
+        let used_move = self.data_handler.get_move_in_generation(*move_id, self.generation);
+        let user = side.get_active();
+
+        let mut priority = used_move.priority;
+
+        match self.get_ability(user, conditions) {
+            Ability::Prankster if used_move.class == MoveClass::Status => priority += 1,
+            Ability::GaleWings if used_move.move_type == Type::Flying && (self.generation <= 6 || user.hp == user.max_hp) => priority += 1,
+            Ability::Triage if used_move.flags.get_heal() => priority += 3,
+            _ => {}
+        }
+
+        priority
+
+    }
+
+    // moves first or last within its priority bracket
+    fn roll_fractional_priority(&mut self, action: &BattleAction, side: &mut Side, conditions: &Conditions) -> i8 {
+
+        let BattleAction::Move(move_id) = action
+        else {
+            return 0;
+        };
+
+        let used_move = self.data_handler.get_move_in_generation(*move_id, self.generation);
+        let user = side.get_active_mut();
+        let ability = self.get_ability(user, conditions);
+
+        match user.item {
+            Some(Item::QuickClaw) => {
+
+                let activated = if self.generation == 2 {
+                    self.rng.random_ratio(60, 256)
+                }
+                else {
+                    self.rng.random_ratio(1, 5)
+                };
+
+                if activated {
+                    self.emit(BattleEvent::ItemActivated {pokemon: user.get_event_pokemon(), item: Item::QuickClaw});
+                    return 1;
+                }
+            }
+            Some(Item::CustapBerry) => {
+
+                let threshold = if ability == Ability::Gluttony {2} else {4};
+
+                if user.hp > 0 && user.hp <= user.max_hp / threshold {
+                    user.item = None;
+                    self.emit(BattleEvent::ItemActivated {pokemon: user.get_event_pokemon(), item: Item::CustapBerry});
+                    return 1;
+                }
+            }
+            _ => {}
+        }
+
+        if ability == Ability::QuickDraw && used_move.class != MoveClass::Status && self.rng.random_ratio(3, 10) {
+            self.emit(BattleEvent::AbilityActivated {pokemon: user.get_event_pokemon(), ability});
+            return 1;
+        }
+
+        if matches!(user.item, Some(Item::LaggingTail | Item::FullIncense)) || ability == Ability::Stall && self.generation >= 4 {
+            return -1;
+        }
+
+        0
+
+    }
+
+    // trick room flips the order of every speed comparison
+    fn get_order_speed(&self, side: &Side, conditions: &Conditions) -> i32 {
+
+        let speed = self.get_side_speed(side, conditions) as i32;
+
+        if conditions.is_trick_room() {
+            -speed
+        }
+        else {
+            speed
         }
     }
 
-    fn get_attacking_stat(&self, used_move: &Move, attacker: &Pokemon, defender: &Pokemon, conditions: &Conditions) -> u16 {
+    fn is_side1_faster(&mut self, state: &BattleState) -> bool {
+
+        let side1_speed = self.get_order_speed(&state.side1, &state.conditions);
+        let side2_speed = self.get_order_speed(&state.side2, &state.conditions);
+
+        if side1_speed == side2_speed {
+            self.rng.random()
+        }
+        else {
+            side1_speed > side2_speed
+        }
+    }
+
+    fn queue_action(&mut self, action: BattleAction, is_side1: bool, state: &mut BattleState) -> QueuedAction {
+
+        let (side, _, conditions) = state.split_mut(is_side1);
+
+        let priority = self.get_action_priority(&action, side, conditions);
+        let fractional_priority = self.roll_fractional_priority(&action, side, conditions);
+        let speed = self.get_order_speed(side, conditions);
+
+        QueuedAction {
+            action,
+            is_side1,
+            priority,
+            fractional_priority,
+            speed,
+            tiebreak: self.rng.random()
+        }
+    }
+
+    fn get_attacking_stat(&self, used_move: &Move, attacker: &Pokemon, defender: &Pokemon, conditions: &Conditions, crit: bool) -> u16 {
 
         // if used_move.has_effect(MoveEffect::BodyPress) {
         //     attacker.defense
         // } else
         if used_move.effect == MoveEffect::FoulPlay {
-            self.get_attack_stat(defender, &conditions)
+            self.get_attack_stat(defender, conditions, crit)
         }
         else if used_move.class == MoveClass::Physical {
-            self.get_attack_stat(attacker, &conditions)
+            self.get_attack_stat(attacker, conditions, crit)
         }
         else {
-            self.get_special_attack_stat(attacker, conditions)
+            self.get_special_attack_stat(attacker, conditions, crit)
         }
     }
 
-    fn get_defending_stat(&self, used_move: &Move, attacker: &Pokemon, defender: &Pokemon, conditions: &Conditions) -> u16 {
+    fn get_defending_stat(&self, used_move: &Move, defender: &Pokemon, conditions: &Conditions, crit: bool) -> u16 {
 
         if used_move.class == MoveClass::Physical || used_move.effect == MoveEffect::Psyshock {
-            self.get_defense_stat(defender, conditions)
+            self.get_defense_stat(defender, conditions, crit)
         }
         else {
-            self.get_special_defense_stat(defender, conditions)
+            self.get_special_defense_stat(defender, conditions, crit)
         }
     }
 
-    fn get_move_power(&self, used_move: &Move, attacker: &Pokemon, defender: &Pokemon, conditions: &Conditions) -> u32 {
+    fn get_move_power(&self, used_move: &Move, attacker: &Pokemon, defender: &Pokemon, conditions: &Conditions, hit: u8) -> u32 {
 
         let mut power = used_move.power.unwrap_or(0) as u32;
 
+        if used_move.effect == MoveEffect::Hit3TimesIncreasing || used_move.id == MOVE_TRIPLE_AXEL {
+            power *= hit as u32 + 1;
+        }
+
+        if used_move.effect == MoveEffect::Rollout {
+
+            find_effect!(attacker, VolatileStatusEffect::Rolling(rolls) => {
+                power <<= *rolls;
+            });
+
+            if has_effect!(attacker, VolatileStatusEffect::DefenseCurl) {
+                power *= 2;
+            }
+        }
+
         let attacker_ability = self.get_ability(attacker, conditions);
 
         if used_move.effect == MoveEffect::Return {
@@ -304,22 +744,71 @@ impl Simulator {
             power = (255 - attacker.friendship as u32) * 5 / 2;
         }
 
+        // power that depends on the battle rather than the move data
+        match used_move.effect {
+            MoveEffect::PowerBasedOnUserHP => power = (power * attacker.hp as u32 / attacker.max_hp as u32).max(1),
+            MoveEffect::MorePowerWhenLessHP => {
+                let fraction = 48 * attacker.hp as u32 / attacker.max_hp as u32;
+                power = [(1, 200), (4, 150), (9, 100), (16, 80), (32, 40)].iter().find(|&&(limit, _)| fraction <= limit).map_or(20, |&(_, power)| power);
+            }
+            MoveEffect::PowerBasedOnWeight => {
+                let weight = self.get_weight(defender);
+                power = [(100.0, 20), (250.0, 40), (500.0, 60), (1000.0, 80), (2000.0, 100)].iter().find(|&&(limit, _)| weight < limit).map_or(120, |&(_, power)| power);
+            }
+            MoveEffect::HeavySlam => {
+                let ratio = self.get_weight(attacker) / self.get_weight(defender);
+                power = [(5.0, 120), (4.0, 100), (3.0, 80), (2.0, 60)].iter().find(|&&(limit, _)| ratio >= limit).map_or(40, |&(_, power)| power);
+            }
+            MoveEffect::GyroBall => {
+                let user_speed = self.get_speed_stat(attacker, conditions).max(1) as u32;
+                power = (25 * self.get_speed_stat(defender, conditions) as u32 / user_speed + 1).min(150);
+            }
+            MoveEffect::PowerBasedOnSpeedDifference => {
+                let ratio = self.get_speed_stat(attacker, conditions) as u32 / self.get_speed_stat(defender, conditions).max(1) as u32;
+                power = [(4, 150), (3, 120), (2, 80), (1, 60)].iter().find(|&&(limit, _)| ratio >= limit).map_or(40, |&(_, power)| power);
+            }
+            MoveEffect::PowerTrip => {
+                let boosts: u32 = attacker.volatile_status.stat_stages.iter().map(|&stages| stages.max(0) as u32).sum();
+                power += 20 * boosts;
+            }
+            MoveEffect::Punishment => {
+                let boosts: u32 = defender.volatile_status.stat_stages.iter().map(|&stages| stages.max(0) as u32).sum();
+                power = (60 + 20 * boosts).min(200);
+            }
+            MoveEffect::WeatherBall if conditions.get_weather().is_some() => power *= 2,
+            _ => {}
+        }
+
         let mut modifier = 4096;
 
         if
             used_move.effect == MoveEffect::Acrobatics && attacker.item.is_none() ||
             used_move.effect == MoveEffect::Brine && defender.hp <= defender.max_hp / 2 ||
             used_move.effect == MoveEffect::Facade && attacker.non_volatile_status.is_some() ||
-            used_move.effect == MoveEffect::Venoshock && matches!(defender.non_volatile_status, Some(NonVolatileStatus::Poison | NonVolatileStatus::BadlyPoison))
+            used_move.effect == MoveEffect::Venoshock && matches!(defender.non_volatile_status, Some(NonVolatileStatus::Poison | NonVolatileStatus::BadlyPoison(_))) ||
+            used_move.effect == MoveEffect::Stomp && self.generation >= 3 && has_effect!(defender, VolatileStatusEffect::Minimize) ||
+            used_move.effect == MoveEffect::DoublePowerIfTargetAlreadyMoved && defender.volatile_status.moved_this_turn ||
+            self.hits_semi_invulnerable_twice(used_move, defender)
             // retaliate
             // fusion moves
         {
             modifier *= 2;
         }
 
-        // solarbeam in weather
+        if used_move.effect == MoveEffect::Solarbeam && conditions.get_weather().is_some_and(|weather|
+            matches!(weather, Weather::Rain | Weather::HeavyRain | Weather::Sandstorm | Weather::Hail | Weather::Snow)
+        ) {
+            modifier /= 2;
+        }
+
+        if used_move.move_type == Type::Electric && has_effect!(attacker, VolatileStatusEffect::Charge) {
+            modifier *= 2;
+        }
 
-        // charge
+        if used_move.effect == MoveEffect::KnockOff && self.generation >= 6 && defender.item.is_some() {
+            modifier *= 3;
+            modifier /= 2;
+        }
 
         // helping hand
 
@@ -345,6 +834,13 @@ impl Simulator {
             modifier /= 4096;
         }
 
+        if attacker_ability == Ability::SupremeOverlord {
+            find_effect!(attacker, VolatileStatusEffect::SupremeOverlord(fainted) => {
+                modifier *= [4096, 4506, 4915, 5325, 5734, 6144][*fainted as usize];
+                modifier /= 4096;
+            });
+        }
+
         // analytic
 
         // sand force
@@ -420,7 +916,7 @@ impl Simulator {
 
     }
 
-    fn apply_item_boosts(&self, damage: &mut u32, used_move: &Move, attacker: &Pokemon) {
+    fn apply_item_boosts(&self, damage: &mut u32, attacker: &Pokemon) {
 
         let Some(item) = attacker.item
         else {
@@ -433,28 +929,61 @@ impl Simulator {
         }
     }
 
-    fn do_contact(&self, attacker: &mut Pokemon, defender: &mut Pokemon, conditions: &Conditions) {
-
-        // baneful bunker, beak blast, burning bulwark, kings shield, obstruct, silk trap, spiky shield
-
-        match self.get_ability(defender, conditions) {
-            // Ability::CuteCharm if rand::random_ratio(3, 10) => attacker.volatile_status.add(VolatileStatusEffect::Infatuation),
-            Ability::EffectSpore if rand::random_ratio(3, 10) && attacker.non_volatile_status.is_none() =>
-                attacker.non_volatile_status = Some([NonVolatileStatus::Poison, NonVolatileStatus::Paralysis, NonVolatileStatus::Sleep][rand::random_range(0..3)]),
-            Ability::FlameBody if rand::random_ratio(3, 10) && attacker.non_volatile_status.is_none() =>
-                attacker.non_volatile_status = Some(NonVolatileStatus::Burn),
-            Ability::Gooey | Ability::TanglingHair => attacker.apply_stat_changes(Stat::Speed, -1),
-            Ability::IronBarbs | Ability::RoughSkin => defender.deal_damage(defender.max_hp / 8),
-            Ability::Mummy => attacker.volatile_status.add(VolatileStatusEffect::AbilityChange(Ability::Mummy)),
-            Ability::PerishBody => todo!(),
-            Ability::Pickpocket => todo!(),
-            Ability::PoisonPoint if rand::random_ratio(3, 10) && attacker.non_volatile_status.is_none() =>
-                attacker.non_volatile_status = Some(NonVolatileStatus::Poison),
-            Ability::PoisonTouch if rand::random_ratio(3, 10) && defender.non_volatile_status.is_none() =>
-                defender.non_volatile_status = Some(NonVolatileStatus::Poison),
-            Ability::Static if rand::random_ratio(3, 10) && defender.non_volatile_status.is_none() =>
-                defender.non_volatile_status = Some(NonVolatileStatus::Paralysis),
-            Ability::WanderingSpirit => todo!(),
+    fn do_contact(&mut self, attacker: &mut Pokemon, defender: &mut Pokemon, conditions: &Conditions) {
+
+        // beak blast
+
+        if self.get_ability(attacker, conditions) == Ability::PoisonTouch && self.rng.random_ratio(3, 10) {
+            self.try_apply_status(defender, NonVolatileStatus::Poison);
+        }
+
+        let ability = self.get_ability(defender, conditions);
+
+        match ability {
+            Ability::CuteCharm if self.rng.random_ratio(3, 10) => {
+                self.try_infatuate(attacker, defender, conditions);
+            }
+            Ability::EffectSpore if self.rng.random_ratio(3, 10) => {
+                let status = match self.rng.random_range(0..3) {
+                    0 => NonVolatileStatus::Poison,
+                    1 => NonVolatileStatus::Paralysis,
+                    _ => NonVolatileStatus::Sleep(self.roll_sleep_turns())
+                };
+                self.try_apply_status(attacker, status);
+            }
+            Ability::FlameBody if self.rng.random_ratio(3, 10) => self.try_apply_status(attacker, NonVolatileStatus::Burn),
+            Ability::Gooey | Ability::TanglingHair => self.change_stat(attacker, Stat::Speed, -1),
+            Ability::IronBarbs | Ability::RoughSkin => self.deal_residual_damage(attacker, attacker.max_hp / 8, DamageCause::Ability(ability), conditions),
+            Ability::Mummy => self.change_ability(attacker, Ability::Mummy),
+            // nothing happens if the attacker is already counting down
+            Ability::PerishBody => if !has_effect!(attacker, VolatileStatusEffect::PerishSong(_)) {
+
+                self.emit(BattleEvent::AbilityActivated {pokemon: defender.get_event_pokemon(), ability: Ability::PerishBody});
+
+                for mon in [&mut *attacker, &mut *defender] {
+                    if !has_effect!(mon, VolatileStatusEffect::PerishSong(_)) {
+                        self.add_volatile(mon, VolatileStatusEffect::PerishSong(3));
+                    }
+                }
+            }
+            Ability::Pickpocket => if defender.hp > 0 && defender.item.is_none() && self.get_ability(attacker, conditions) != Ability::StickyHold {
+                if let Some(item) = attacker.item.take() {
+                    defender.item = Some(item);
+                    self.emit(BattleEvent::ItemStolen {pokemon: defender.get_event_pokemon(), target: attacker.get_event_pokemon(), item});
+                }
+            }
+            Ability::PoisonPoint if self.rng.random_ratio(3, 10) => self.try_apply_status(attacker, NonVolatileStatus::Poison),
+            Ability::Static if self.rng.random_ratio(3, 10) => self.try_apply_status(attacker, NonVolatileStatus::Paralysis),
+            // the abilities trace can't copy can't be swapped either
+            Ability::WanderingSpirit => {
+
+                let attacker_ability = self.get_ability(attacker, conditions);
+
+                if self.can_be_traced(attacker_ability) && attacker_ability != Ability::WonderGuard {
+                    self.change_ability(attacker, Ability::WanderingSpirit);
+                    self.change_ability(defender, attacker_ability);
+                }
+            }
             _ => {}
         }
 
@@ -462,79 +991,266 @@ impl Simulator {
 
     }
 
-    //    Damage=((2×Level5+2)×Power×AD50+2)×Targets×PB×Weather×GlaiveRush×Critical×random×STAB×Type×Burn×other×ZMove×TeraShield
-    pub fn calc_damage_inner(&self, used_move: &Move, attacker: &Pokemon, defender: &Pokemon, conditions: &Conditions, hit_properties: HitProperties) -> u16 {
+    fn check_hit(&mut self, used_move: &Move, attacker: &Pokemon, defender: &Pokemon, conditions: &Conditions) -> bool {
 
-        let attack_stat = self.get_attacking_stat(used_move, attacker, defender, conditions) as u32;
-        let defense_stat = self.get_defending_stat(used_move, attacker, defender, conditions) as u32;
+        let Some(chance) = self.get_hit_chance(used_move, attacker, defender, conditions)
+        else {
+            return true;
+        };
 
-        let attacker_data = self.data_handler.get_pokemon_data(attacker.id);
-        let defender_data = self.data_handler.get_pokemon_data(defender.id);
+        // gen 1 rolls out of 256 against a threshold out of 255, so even 100% moves miss 1/256 of the time
+        if self.generation == 1 {
+            let threshold = (chance * 255 / 100).min(255);
+            return self.rng.random_range(0..256) < threshold;
+        }
 
-        let power = self.get_move_power(used_move, attacker, defender, conditions);
+        self.rng.random_range(0..100) < chance
 
-        let mut damage = (2 * (attacker.level as u32) / 5 + 2) * power * attack_stat / defense_stat / 50 + 2;
+    }
 
-        // targets
-        if false {
-            damage *= 3;
-            damage /= 4;
-        }
+    // percentage chance to hit, none if the move can't miss
+    fn get_hit_chance(&self, used_move: &Move, attacker: &Pokemon, defender: &Pokemon, conditions: &Conditions) -> Option<u32> {
 
-        // parental bond
-        if false {
-            damage /= 4;
-        }
+        const GEN_3_4_STAGES: [u32; 13] = [33, 36, 43, 50, 60, 75, 100, 133, 166, 200, 233, 266, 300];
 
-        // weather
-        if false {
-        }
+        // moves without an accuracy value never check
+        let mut accuracy = used_move.accuracy? as u32;
 
-        // glaive rush
-        if false {
-            damage *= 2;
-        }
+        let attacker_ability = self.get_ability(attacker, conditions);
+        let defender_ability = self.get_ability(defender, conditions);
 
-        if hit_properties.crit {
-            damage *= 2;
+        if used_move.effect == MoveEffect::NeverMiss || attacker_ability == Ability::NoGuard || defender_ability == Ability::NoGuard {
+            return None;
         }
 
-        damage *= hit_properties.roll_percent as u32;
-        damage /= 100;
-
-        // stab
-        if attacker_data.is_type(used_move.move_type) {
-            damage *= 3;
-            damage /= 2;
+        match used_move.effect {
+            MoveEffect::Thunder | MoveEffect::Hurricane if self.generation >= 2 => {
+                if conditions.is_rain() {
+                    return None;
+                }
+                if conditions.is_sunny() {
+                    accuracy = 50;
+                }
+            }
+            MoveEffect::Blizzard if self.generation >= 4 && conditions.is_weather(Weather::Hail) => return None,
+            MoveEffect::Stomp if self.generation >= 6 && has_effect!(defender, VolatileStatusEffect::Minimize) => return None,
+            _ => {}
         }
 
-        let mut type_effectiveness = self.data_handler.type_chart.get(used_move.move_type, defender_data.type1) as u32;
+        let mut accuracy_stage = attacker.get_stat_stage(Stat::Accuracy);
+        let mut evasion_stage = defender.get_stat_stage(Stat::Evasion);
 
-        if let Some(type2) = defender_data.type2 {
-            type_effectiveness *= self.data_handler.type_chart.get(used_move.move_type, type2) as u32;
-            type_effectiveness /= 100;
+        if defender_ability == Ability::Unaware {
+            accuracy_stage = 0;
         }
 
-        if type_effectiveness > 100 {
-            log!("It's super effective!");
+        if attacker_ability == Ability::Unaware {
+            evasion_stage = 0;
         }
-        else if type_effectiveness == 0 {
-            log!("It doesn't affect...");
-        }
-        else if type_effectiveness < 100 {
-            log!("It's not very effective...");
+
+        if self.generation >= 6 && matches!(attacker_ability, Ability::KeenEye | Ability::MindsEye) {
+            evasion_stage = evasion_stage.min(0);
         }
 
+        find_effect!(defender, VolatileStatusEffect::Identified => {
+            evasion_stage = evasion_stage.min(0);
+        });
+
+        let mut chance = if self.generation <= 2 {
+            accuracy * GEN_1_2_STAGES[(accuracy_stage + 6) as usize] / 100 * GEN_1_2_STAGES[(6 - evasion_stage) as usize] / 100
+        }
+        else {
+
+            let stage = (accuracy_stage - evasion_stage).clamp(-6, 6);
+
+            if self.generation <= 4 {
+                accuracy * GEN_3_4_STAGES[(stage + 6) as usize] / 100
+            }
+            else {
+                accuracy * get_accuracy_stage_percent(stage) / 100
+            }
+        };
+
+        let mut modifier = 4096;
+
+        match attacker_ability {
+            Ability::CompoundEyes => {
+                modifier *= 5325;
+                modifier /= 4096;
+            }
+            Ability::Hustle if used_move.class == MoveClass::Physical => {
+                modifier *= 3277;
+                modifier /= 4096;
+            }
+            Ability::VictoryStar => {
+                modifier *= 4506;
+                modifier /= 4096;
+            }
+            _ => {}
+        }
+
+        let mut confused = false;
+        find_effect!(defender, VolatileStatusEffect::Confusion(_) => {
+            confused = true;
+        });
+
+        match defender_ability {
+            Ability::SandVeil if conditions.is_weather(Weather::Sandstorm) => {
+                modifier *= 3277;
+                modifier /= 4096;
+            }
+            Ability::SnowCloak if conditions.is_weather(Weather::Hail) => {
+                modifier *= 3277;
+                modifier /= 4096;
+            }
+            Ability::TangledFeet if confused => modifier /= 2,
+            _ => {}
+        }
+
+        if conditions.is_gravity() {
+            modifier *= 6840;
+            modifier /= 4096;
+        }
+
+        if attacker.item == Some(Item::WideLens) {
+            modifier *= 4505;
+            modifier /= 4096;
+        }
+
+        if matches!(defender.item, Some(Item::BrightPowder | Item::LaxIncense)) {
+            modifier *= 3686;
+            modifier /= 4096;
+        }
+
+        chance *= modifier;
+        chance /= 4096;
+
+        Some(chance)
+
+    }
+
+    // struggle stopped having a type in gen 2
+    fn is_typeless(&self, used_move: &Move) -> bool {
+        used_move.effect == MoveEffect::Struggle && self.generation >= 2
+    }
+
+    // percentage, 100 is neutral
+    fn get_type_effectiveness(&self, used_move: &Move, defender: &Pokemon) -> u32 {
+
+        if self.is_typeless(used_move) {
+            return 100;
+        }
+
+        self.get_type_effectiveness_against(used_move.move_type, defender)
+
+    }
+
+    fn get_type_effectiveness_against(&self, attack_type: Type, defender: &Pokemon) -> u32 {
+
+        let type_chart = self.data_handler.get_type_chart(self.generation);
+        let (type1, type2) = self.data_handler.get_pokemon_data(defender.id).get_types(self.generation);
+
+        let mut type_effectiveness = type_chart.get(attack_type, type1) as u32;
+
+        if let Some(type2) = type2 {
+            type_effectiveness *= type_chart.get(attack_type, type2) as u32;
+            type_effectiveness /= 100;
+        }
+
+        type_effectiveness
+
+    }
+
+    //    Damage=((2×Level5+2)×Power×AD50+2)×Targets×PB×Weather×GlaiveRush×Critical×random×STAB×Type×Burn×other×ZMove×TeraShield
+    pub fn calc_damage_inner(&self, used_move: &Move, attacker: &Pokemon, defender: &Pokemon, conditions: &Conditions, hit_properties: HitProperties) -> u16 {
+
+        if self.generation <= 2 {
+            return self.calc_damage_gen_1_2(used_move, attacker, defender, conditions, hit_properties);
+        }
+
+        let attack_stat = self.get_attacking_stat(used_move, attacker, defender, conditions, hit_properties.crit) as u32;
+        let defense_stat = self.get_defending_stat(used_move, defender, conditions, hit_properties.crit) as u32;
+
+        let attacker_data = self.data_handler.get_pokemon_data(attacker.id);
+        let power = self.get_move_power(used_move, attacker, defender, conditions, hit_properties.hit);
+
+        let mut damage = (2 * (attacker.level as u32) / 5 + 2) * power * attack_stat / defense_stat / 50 + 2;
+
+        // targets
+        if false {
+            damage *= 3;
+            damage /= 4;
+        }
+
+        if hit_properties.parental_bond_hit {
+            if self.generation >= 7 {
+                damage /= 4;
+            }
+            else {
+                damage /= 2;
+            }
+        }
+
+        // weather
+        match used_move.move_type {
+            Type::Fire if conditions.is_sunny() => {
+                damage *= 3;
+                damage /= 2;
+            }
+            Type::Water if conditions.is_rain() => {
+                damage *= 3;
+                damage /= 2;
+            }
+            Type::Fire if conditions.is_rain() => damage /= 2,
+            Type::Water if conditions.is_sunny() => damage /= 2,
+            _ => {}
+        }
+
+        // glaive rush
+        if false {
+            damage *= 2;
+        }
+
+        if hit_properties.crit {
+            if self.generation >= 6 {
+                damage *= 3;
+                damage /= 2;
+            }
+            else {
+                damage *= 2;
+            }
+
+            if self.get_ability(attacker, conditions) == Ability::Sniper {
+                damage *= 3;
+                damage /= 2;
+            }
+        }
+
+        damage *= hit_properties.roll as u32;
+        damage /= 100;
+
+        // stab
+        if attacker_data.is_type(used_move.move_type, self.generation) && !self.is_typeless(used_move) {
+            damage *= 3;
+            damage /= 2;
+        }
+
+        let type_effectiveness = self.get_type_effectiveness(used_move, defender);
+
         damage *= type_effectiveness;
         damage /= 100;
 
+        if hit_properties.screened && self.generation >= 3 {
+            damage /= 2;
+        }
+
         // burn
         if used_move.class == MoveClass::Physical && attacker.non_volatile_status == Some(NonVolatileStatus::Burn) {
             damage /= 2;
         }
 
         // tons of random stuff
-        self.apply_item_boosts(&mut damage, used_move, attacker);
+        self.apply_item_boosts(&mut damage, attacker);
 
         let mut damage_u16 = damage as u16;
 
@@ -546,619 +1262,5066 @@ impl Simulator {
 
     }
 
-    fn calc_damage(&self, used_move: &Move, attacker: &Pokemon, defender: &Pokemon, conditions: &Conditions) -> u16 {
+    // truncates in the same order as the cartridges
+    fn calc_damage_gen_1_2(&self, used_move: &Move, attacker: &Pokemon, defender: &Pokemon, conditions: &Conditions, hit_properties: HitProperties) -> u16 {
 
-        let crit = rand::random_ratio(1, 16);
+        let mut attack_stat = self.get_attacking_stat(used_move, attacker, defender, conditions, hit_properties.crit) as u32;
+        let mut defense_stat = self.get_defending_stat(used_move, defender, conditions, hit_properties.crit) as u32;
 
-        if crit {
-            log!("A critical hit!");
+        if hit_properties.screened {
+            defense_stat *= 2;
         }
 
-        let roll_percent = rand::random_range(85..=100);
-        
-        let hit_properties = HitProperties {
-            crit,
-            roll_percent
+        // stats are squeezed into a byte
+        if attack_stat > 255 || defense_stat > 255 {
+            attack_stat /= 4;
+            defense_stat /= 4;
+        }
+
+        attack_stat = attack_stat.max(1);
+        defense_stat = defense_stat.max(1);
+
+        // gen 1 crits double the level instead of the damage
+        let level = if hit_properties.crit && self.generation == 1 {
+            attacker.level as u32 * 2
+        }
+        else {
+            attacker.level as u32
         };
 
-        self.calc_damage_inner(used_move, attacker, defender, conditions, hit_properties)
-        
-    }
+        let power = self.get_move_power(used_move, attacker, defender, conditions, hit_properties.hit);
 
-    fn do_move_hit(&self, used_side: &mut Side, other_side: &mut Side, mut damage: u16, used_move: &Move, conditions: &Conditions) {
+        let mut damage = (2 * level / 5 + 2) * power * attack_stat / defense_stat / 50;
 
-        let attacker = used_side.get_active_mut();
-        let defender = other_side.get_active_mut();
+        if hit_properties.crit && self.generation == 2 {
+            damage *= 2;
+        }
 
-        let defender_ability = self.get_ability(&defender, conditions);
+        damage = damage.min(997) + 2;
 
-        if damage >= defender.hp {
+        if self.data_handler.get_pokemon_data(attacker.id).is_type(used_move.move_type, self.generation) && !self.is_typeless(used_move) {
+            damage += damage / 2;
+        }
 
-            if defender.hp == defender.max_hp {
-                if defender_ability == Ability::Sturdy {
-                    damage = defender.hp - 1;
-                }
-                else if defender.item == Some(Item::FocusSash) {
-                    log!("{} held on using their Focus Sash!", defender.name);
-                    damage = defender.hp - 1;
-                    defender.item = None;
-                }
+        // each type is applied separately
+        if !self.is_typeless(used_move) {
+
+            let type_chart = self.data_handler.get_type_chart(self.generation);
+            let (type1, type2) = self.data_handler.get_pokemon_data(defender.id).get_types(self.generation);
+
+            damage = damage * type_chart.get(used_move.move_type, type1) as u32 / 100;
+
+            if let Some(type2) = type2 {
+                damage = damage * type_chart.get(used_move.move_type, type2) as u32 / 100;
             }
 
-            if defender.item == Some(Item::FocusBand) && rand::random_ratio(1, 10) {
-                log!("{} held on using their Focus Band!", defender.name);
-                damage = defender.hp - 1;
+            if damage == 0 {
+                return 0;
             }
         }
 
-        defender.deal_damage(damage);
+        if damage > 1 {
+            damage = damage * hit_properties.roll as u32 / 255;
+        }
 
-        if defender.hp == 0 {
-            log!("{} fainted!", defender.name);
+        let mut damage_u16 = damage.max(1) as u16;
+
+        if used_move.effect == MoveEffect::FalseSwipe && damage_u16 >= defender.hp {
+            damage_u16 = defender.hp - 1;
         }
 
-        if used_move.move_type == Type::Dark && defender_ability == Ability::Justified {
-            defender.apply_stat_changes(Stat::Attack, 1);
+        damage_u16
+
+    }
+
+    // in hectograms, autotomize takes 100 kg off each time
+    fn get_weight(&self, pokemon: &Pokemon) -> f32 {
+
+        let mut weight = self.data_handler.get_pokemon_data(pokemon.id).weight;
+
+        find_effect!(pokemon, VolatileStatusEffect::Autotomize(uses) => {
+            weight -= 1000.0 * *uses as f32;
+        });
+
+        weight.max(1.0)
+
+    }
+
+    fn get_crit_stage(&self, used_move: &Move, attacker: &Pokemon, conditions: &Conditions) -> u8 {
+
+        let mut stage = used_move.crit_rate;
+
+        if has_effect!(attacker, VolatileStatusEffect::GettingPumped) {
+            stage += if self.generation >= 3 {2} else {1};
         }
 
-        if used_move.flags.get_contact() && attacker.item == Some(Item::ProtectivePads) && self.get_ability(attacker, conditions) != Ability::LongReach {
-            self.do_contact(attacker, defender, conditions);
+        if self.get_ability(attacker, conditions) == Ability::SuperLuck {
+            stage += 1;
         }
+
+        match attacker.item {
+            Some(Item::ScopeLens | Item::RazorClaw) => stage += 1,
+            Some(Item::Stick) if matches!(attacker.id, POKEMON_FARFETCHD | POKEMON_FARFETCHD_GALAR | POKEMON_SIRFETCHD) => stage += 2,
+            Some(Item::LuckyPunch) if attacker.id == POKEMON_CHANSEY => stage += 2,
+            _ => {}
+        }
+
+        stage
+
     }
 
-    fn use_move(&self, used_move: &Move, using_side: &mut Side, other_side: &mut Side, conditions: &mut Conditions) {
-        
-        log!("{} used {}!", using_side.get_active().name, used_move.name);
+    fn roll_crit(&mut self, used_move: &Move, attacker: &Pokemon, defending_side: &Side, conditions: &Conditions) -> bool {
 
-        let mut damage = 0;
+        let defender = defending_side.get_active();
 
-        if used_move.class != MoveClass::Status {
-            damage = self.calc_damage(used_move, &using_side.get_active_mut(), &other_side.get_active_mut(), conditions);
-            self.do_move_hit(using_side, other_side, damage, used_move, conditions);
+        if matches!(self.get_ability(defender, conditions), Ability::BattleArmor | Ability::ShellArmor) || defending_side.effects.is_active(SideCondition::LuckyChant) {
+            return false;
         }
 
-        let mut do_effect = true;
+        // move_meta gives guaranteed crits a crit rate of 6
+        if used_move.crit_rate >= 6 || has_effect!(attacker, VolatileStatusEffect::LaserFocus(_)) {
+            return true;
+        }
 
-        if let Some(chance) = used_move.effect_chance {
-            do_effect = rand::random_ratio(chance as u32, 100);
+        if self.get_ability(attacker, conditions) == Ability::Merciless && matches!(defender.non_volatile_status, Some(NonVolatileStatus::Poison | NonVolatileStatus::BadlyPoison(_))) {
+            return true;
         }
 
-        if do_effect {
-            self.apply_effect_after_use(used_move.effect, using_side, other_side, conditions, damage);
+        if self.generation == 1 {
+            return self.roll_crit_gen_1(used_move, attacker);
         }
+
+        let stage = self.get_crit_stage(used_move, attacker, conditions);
+        let (numerator, denominator) = self.get_crit_chance(stage);
+
+        self.rng.random_ratio(numerator, denominator)
+
     }
 
-    fn set_weather(&self, conditions: &mut Conditions, weather: Weather, held_item: Option<Item>, from_ability: bool) {
-        
-        if let Some((current_weather, _)) = conditions.weather {
-            if weather == current_weather || current_weather.is_strong() && !weather.is_strong() {
-                return;
-            }
+    fn get_crit_chance(&self, stage: u8) -> (u32, u32) {
+
+        let stage = stage as usize;
+
+        match self.generation {
+            2 => [(17, 256), (1, 8), (1, 4), (85, 256), (1, 2)][stage.min(4)],
+            ..=5 => [(1, 16), (1, 8), (1, 4), (1, 3), (1, 2)][stage.min(4)],
+            6 => [(1, 16), (1, 8), (1, 2), (1, 1)][stage.min(3)],
+            _ => [(1, 24), (1, 8), (1, 2), (1, 1)][stage.min(3)]
         }
 
-        let duration = if self.generation <= 5 && from_ability {
-            Weather::PERMANENT
+    }
+
+    fn roll_crit_gen_1(&mut self, used_move: &Move, attacker: &Pokemon) -> bool {
+        let threshold = self.get_crit_threshold_gen_1(used_move, attacker);
+        self.rng.random_range(0..256) < threshold
+    }
+
+    // out of 256 based on base speed, focus energy was meant to quadruple the chance but quarters it instead
+    fn get_crit_threshold_gen_1(&self, used_move: &Move, attacker: &Pokemon) -> u32 {
+
+        let mut threshold = self.data_handler.get_pokemon_data(attacker.id).speed as u32 / 2;
+
+        if has_effect!(attacker, VolatileStatusEffect::GettingPumped) {
+            threshold /= 2;
         }
-        else if matches!(weather, Weather::ExtremeSun | Weather::HeavyRain | Weather::StrongWind) {
-            Weather::PERMANENT
+        else {
+            threshold = (threshold * 2).min(255);
         }
-        else if held_item.is_some_and(|item| matches!((weather, item), 
-            (Weather::Rain, Item::DampRock) |
-            (Weather::Sun, Item::HeatRock) |
-            (Weather::Hail, Item::IcyRock) |
-            (Weather::Sandstorm, Item::SmoothRock)
-        )) {
-            8
+
+        if used_move.crit_rate > 0 {
+            threshold = (threshold * 4).min(255);
         }
         else {
-            5
+            threshold /= 2;
+        }
+
+        threshold
+
+    }
+
+    // damage that doesn't come from the formula, gen 1 ignores type immunities for these
+    fn get_fixed_damage(&mut self, used_move: &Move, attacker: &Pokemon, defender: &Pokemon) -> Option<u16> {
+
+        let damage = match used_move.effect {
+            MoveEffect::SuperFang => (defender.hp / 2).max(1),
+            MoveEffect::DragonRage => 40,
+            MoveEffect::SonicBoom => 20,
+            MoveEffect::DamageByLevel => attacker.level as u16,
+            MoveEffect::RangeDamageByLevel => (attacker.level as u32 * self.rng.random_range(50..=150) / 100).max(1) as u16,
+            _ => return None
         };
 
-        conditions.weather = Some((weather, duration));
+        if self.generation >= 2 && self.get_type_effectiveness(used_move, defender) == 0 {
+            return Some(0);
+        }
+
+        Some(damage)
 
     }
 
-    fn set_terrain(&self, conditions: &mut Conditions, terrain: Terrain, held_item: Option<Item>) {
+    #[allow(clippy::too_many_arguments)]
+    fn calc_damage(&mut self, used_move: &Move, attacker: &Pokemon, defending_side: &Side, conditions: &Conditions, crit: bool, hit: u8, parental_bond_hit: bool) -> u16 {
 
-        let duration = if held_item == Some(Item::TerrainExtender) {
-            8
+        let defender = defending_side.get_active();
+
+        if let Some(damage) = self.get_fixed_damage(used_move, attacker, defender) {
+            return damage;
+        }
+
+        let roll = if self.generation <= 2 {
+            self.rng.random_range(217..=255)
         }
         else {
-            5
+            self.rng.random_range(85..=100)
         };
 
-        conditions.terrain = Some((terrain, duration));
+        let screen = match used_move.class {
+            MoveClass::Physical => SideCondition::Reflect,
+            _ => SideCondition::LightScreen
+        };
+
+        // crits go through screens
+        let screened = !crit && !self.bypasses_screens(attacker, conditions) && (
+            defending_side.effects.is_active(screen) || defending_side.effects.is_active(SideCondition::AuroraVeil)
+        );
+        
+        let hit_properties = HitProperties {
+            crit,
+            roll,
+            screened,
+            hit,
+            parental_bond_hit
+        };
 
+        self.calc_damage_inner(used_move, attacker, defender, conditions, hit_properties)
+        
     }
 
-    fn activate_ability(&self, side: &mut Side, other_side: &mut Side, conditions: &mut Conditions) {
+    // returns whether a substitute took the hit
+    fn do_move_hit(&mut self, used_side: &mut Side, other_side: &mut Side, mut damage: u16, crit: bool, used_move: &Move, conditions: &Conditions) -> bool {
 
-        let mon = side.get_active_mut();
-        let other_mon = other_side.get_active_mut();
+        let attacker = used_side.get_active_mut();
+        let defender = other_side.get_active_mut();
 
-        match self.get_ability(mon, &conditions) {
-            Ability::AirLock | Ability::CloudNine => todo!(),
-            Ability::Anticipation => todo!(),
-            // Ability::AsOne => todo!(),
-            Ability::CuriousMedicine => todo!(),
-            Ability::DauntlessShield => mon.apply_stat_changes(Stat::Defense, 1), // gen 9, only once per battle
-            Ability::DeltaStream => self.set_weather(conditions, Weather::StrongWind, mon.item, true),
-            Ability::DesolateLand => conditions.weather = Some((Weather::ExtremeSun, Weather::PERMANENT)),
-            Ability::Download => {
-                // need to account for gen 4 weirdness
-                let other_mon = other_side.get_active();
-                if self.get_defense_stat(other_mon, &conditions) < self.get_special_defense_stat(other_mon, &conditions) {
-                    mon.apply_stat_changes(Stat::Attack, 1);
+        if has_effect!(defender, VolatileStatusEffect::Substitute(_)) && !self.bypasses_substitute(used_move, attacker, conditions) {
+            self.damage_substitute(defender, damage);
+            return true;
+        }
+
+        let defender_ability = self.get_ability(defender, conditions);
+
+        if damage >= defender.hp && has_effect!(defender, VolatileStatusEffect::Endure) {
+            self.emit(BattleEvent::VolatileActivated {pokemon: defender.get_event_pokemon(), effect: VolatileStatusEffect::Endure});
+            damage = defender.hp - 1;
+        }
+
+        if damage >= defender.hp {
+
+            if defender.hp == defender.max_hp {
+                if defender_ability == Ability::Sturdy {
+                    damage = defender.hp - 1;
                 }
-                else {
-                    mon.apply_stat_changes(Stat::SpecialAttack, 1);
+                else if defender.item == Some(Item::FocusSash) {
+                    self.emit(BattleEvent::ItemActivated {pokemon: defender.get_event_pokemon(), item: Item::FocusSash});
+                    damage = defender.hp - 1;
+                    defender.item = None;
                 }
             }
-            Ability::Drizzle => self.set_weather(conditions, Weather::Rain, mon.item, true),
-            Ability::Drought | Ability::OrichalcumPulse => self.set_weather(conditions, Weather::Sun, mon.item, true),
-            Ability::ElectricSurge | Ability::HadronEngine => self.set_terrain(conditions, Terrain::Electric, mon.item),
-            Ability::Forewarn => todo!(),
-            Ability::Frisk => todo!(),
-            Ability::GrassySurge => self.set_terrain(conditions, Terrain::Grassy, mon.item),
-            Ability::Hospitality => todo!(),
-            Ability::Imposter => todo!(),
-            Ability::Intimidate => other_mon.apply_stat_changes(Stat::Attack, -1),
-            Ability::IntrepidSword => mon.apply_stat_changes(Stat::Attack, 1), // gen 9, only once per battle
-            Ability::MistySurge => self.set_terrain(conditions, Terrain::Misty, mon.item),
-            Ability::MoldBreaker => todo!(),
-            Ability::NeutralizingGas => todo!(),
-            Ability::Pressure => todo!(),
-            Ability::PrimordialSea => self.set_weather(conditions, Weather::HeavyRain, mon.item, true),
-            Ability::Protosynthesis => todo!(),
-            Ability::PsychicSurge => self.set_terrain(conditions, Terrain::Psychic, mon.item),
-            Ability::QuarkDrive => todo!(),
-            Ability::SandStream => self.set_weather(conditions, Weather::Sandstorm, mon.item, true),
-            Ability::Schooling => todo!(),
-            Ability::ScreenCleaner => todo!(),
-            Ability::ShieldsDown => todo!(),
-            Ability::SnowWarning => self.set_weather(conditions, Weather::Hail, mon.item, true),
-            Ability::SupersweetSyrup => other_mon.apply_stat_changes(Stat::Evasion, -1),
-            Ability::SupremeOverlord => todo!(),
-            Ability::Trace => {
-                mon.volatile_status.add(VolatileStatusEffect::AbilityChange(self.get_ability(other_side.get_active(), &conditions)));
-                self.activate_ability(side, other_side, conditions);
+
+            if defender.item == Some(Item::FocusBand) && self.rng.random_ratio(1, 10) {
+                self.emit(BattleEvent::ItemActivated {pokemon: defender.get_event_pokemon(), item: Item::FocusBand});
+                damage = defender.hp - 1;
             }
-            Ability::Unnerve => todo!(),
-            _ => {}
         }
-    }
 
-    fn apply_effect_after_use(&self, effect: MoveEffect, user_side: &mut Side, target_side: &mut Side, conditions: &mut Conditions, move_damage: u16) {
+        let hp_before = defender.hp;
+        defender.deal_damage(damage);
 
-        let using_mon = user_side.get_active_mut();
-        let target_mon = target_side.get_active_mut();
-
-        use MoveEffect as ME;
-        match effect {
-            ME::Sleep => target_side.try_apply_status(NonVolatileStatus::Sleep),
-            ME::PoisonChance => target_side.try_apply_status(NonVolatileStatus::Poison),
-            ME::DrainHalf => using_mon.heal(move_damage / 2),
-            ME::Burn | ME::BurnChance => target_side.try_apply_status(NonVolatileStatus::Burn),
-            ME::FreezeChance => target_side.try_apply_status(NonVolatileStatus::Freeze),
-            ME::Paralyze | ME::ParalyzeChance => target_side.try_apply_status(NonVolatileStatus::Paralysis),
-            ME::FaintUser => using_mon.hp = 0,
-            ME::DreamEater => todo!(),
-            ME::UseTargetsLastMove => todo!(),
-            ME::RaiseUserAttack1 => using_mon.apply_stat_changes(Stat::Attack, 1),
-            ME::RaiseUserDefense1 => using_mon.apply_stat_changes(Stat::Defense, 1),
-            ME::RaiseUserSpecialAttack1 => using_mon.apply_stat_changes(Stat::SpecialAttack, 1),
-            //ME::RaiseUserSpecialDefense1 => using_mon.apply_stat_changes(Stat::SpecialDefense, 1),
-            ME::RaiseUserSpeed1 => using_mon.apply_stat_changes(Stat::Speed, 1),
-            ME::NeverMiss => todo!(),
-            ME::LowerTargetAttack1 => target_mon.apply_stat_changes(Stat::Attack, -1),
-            ME::LowerTargetDefense1 => target_mon.apply_stat_changes(Stat::Defense, -1),
-            // ME::LowerTargetSpecialAttack1 => using_mon.apply_stat_changes(Stat::SpecialAttack, 1),
-            // ME::LowerTargetSpecialDefense1 => using_mon.apply_stat_changes(Stat::SpecialDefense, 1),
-            ME::LowerTargetSpeed1 => target_mon.apply_stat_changes(Stat::Speed, -1),
-            ME::LowerTargetAccuracy1 => target_mon.apply_stat_changes(Stat::Accuracy, -1),
-            ME::LowerTargetEvasion1 => target_mon.apply_stat_changes(Stat::Evasion, -1),
-            ME::ResetTargetStats => target_mon.reset_stat_changes(),
-            ME::Bide => todo!(),
-            ME::ForceSwitch => todo!(),
-            ME::Hit2To5Times => todo!(),
-            ME::Conversion => todo!(),
-            ME::FlinchChance => todo!(),
-            ME::HealUserHalf => todo!(),
-            ME::BadlyPoison | ME::BadlyPoisonChance => target_side.try_apply_status(NonVolatileStatus::BadlyPoison),
-            ME::ScatterMoney => todo!(),
-            ME::LightScreen => target_side.effects.set_light_screen(if using_mon.item == Some(Item::LightClay) {8} else {5}),
-            ME::TriAttack => todo!(),
-            ME::Rest => todo!(),
-            ME::RazorWind => todo!(),
-            ME::SuperFang => todo!(),
-            ME::DragonRage => todo!(),
-            ME::Trapping => todo!(),
-            ME::IncreasedCrit => todo!(),
-            ME::HitTwice => todo!(),
-            ME::RecoilOnMiss => todo!(),
-            ME::ProtectStats => todo!(),
-            ME::FocusEnergy => todo!(),
-            ME::RecoilQuarter => todo!(),
-            ME::Confuse | ME::ConfuseAllTargets | ME::ConfuseChance => target_mon.volatile_status.add(VolatileStatusEffect::Confusion(rand::random_range(2..=5))),
-            ME::RaiseUserAttack2 => using_mon.apply_stat_changes(Stat::Attack, 2),
-            ME::RaiseUserDefense2 => using_mon.apply_stat_changes(Stat::Defense, 2),
-            ME::RaiseUserSpeed2 => using_mon.apply_stat_changes(Stat::Speed, 2),
-            ME::RaiseUserSpecialAttack2 => using_mon.apply_stat_changes(Stat::SpecialAttack, 2),
-            ME::RaiseUserSpecialDefense2 => using_mon.apply_stat_changes(Stat::SpecialDefense, 2),
-            ME::Transform => todo!(),
-            ME::LowerTargetAttack2 => target_mon.apply_stat_changes(Stat::Attack, 2),
-            ME::LowerTargetDefense2 => target_mon.apply_stat_changes(Stat::Defense, 2),
-            ME::LowerTargetSpeed2 => target_mon.apply_stat_changes(Stat::Speed, 2),
-            ME::LowerTargetSpecialAttack2 => target_mon.apply_stat_changes(Stat::SpecialAttack, 2),
-            ME::LowerTargetSpecialDefense2 => target_mon.apply_stat_changes(Stat::SpecialDefense, 2),
-            ME::Reflect => target_side.effects.set_reflect(if using_mon.item == Some(Item::LightClay) {8} else {5}),
-            ME::Poison => target_side.try_apply_status(NonVolatileStatus::Poison),
-            ME::LowerTargetAttack1Chance => target_mon.apply_stat_changes(Stat::Attack, -1),
-            ME::LowerTargetDefense1Chance => target_mon.apply_stat_changes(Stat::Defense, -1),
-            ME::LowerTargetSpeed1Chance => target_mon.apply_stat_changes(Stat::Speed, -1),
-            ME::LowerTargetSpecialAttack1Chance => target_mon.apply_stat_changes(Stat::SpecialAttack, -1),
-            ME::LowerTargetSpecialDefense1Chance => target_mon.apply_stat_changes(Stat::SpecialDefense, -1),
-            ME::LowerTargetAccuracy1Chance => target_mon.apply_stat_changes(Stat::Accuracy, -1),
-            ME::MysticalFire => todo!(),
-            ME::ChargeAndFlinchChance => todo!(),
-            ME::HitTwiceAndPoisonChance => todo!(),
-            ME::Substitute => todo!(),
-            ME::Recharge => todo!(),
-            ME::RaiseAttack1IfHit => todo!(),
-            ME::UseTargetsLastMove2 => todo!(),
-            ME::Metronome => todo!(),
-            ME::Seed => todo!(),
-            ME::Splash => todo!(),
-            ME::Disable => todo!(),
-            ME::DamageByLevel => todo!(),
-            ME::RangeDamageByLevel => todo!(),
-            ME::Counter => todo!(),
-            ME::Encore => todo!(),
-            ME::PainSplit => {
-                let total = using_mon.hp + target_mon.hp;
-                using_mon.hp = using_mon.max_hp.min(total / 2);
-                target_mon.hp = target_mon.max_hp.min(total / 2);
-            },
-            ME::FlinchChanceWorksIfSleeping => todo!(),
-            ME::Transform2 => todo!(),
-            ME::NextMoveHits => todo!(),
-            ME::Sketch => todo!(),
-            ME::SleepTalk => todo!(),
-            ME::DestinyBond => todo!(),
-            ME::MorePowerWhenLessHP => todo!(),
-            ME::Spite => todo!(),
-            ME::CurePartyStatus => todo!(),
-            ME::NoOtherEffect2 => todo!(),
-            ME::Hit3TimesIncreasing => todo!(),
-            ME::StealItem => todo!(),
-            ME::PreventEscape => todo!(),
-            ME::Nightmare => todo!(),
-            ME::Minimize => todo!(),
-            ME::Curse => todo!(),
-            ME::Protect => todo!(),
-            ME::Spikes => target_side.effects.add_spikes(),
-            ME::Identify => todo!(),
-            ME::PerishSong => todo!(),
-            ME::Sandstorm => self.set_weather(conditions, Weather::Sandstorm, using_mon.item, false),
-            ME::Endure => todo!(),
-            ME::Rollout => todo!(),
-            ME::Swagger => todo!(),
-            ME::IceBall => todo!(),
-            ME::Attract => todo!(),
-            ME::Return => todo!(),
-            ME::Present => todo!(),
-            ME::Frustration => todo!(),
-            ME::Safeguard => todo!(),
-            ME::Magnitude => todo!(),
-            ME::BatonPass => todo!(),
-            ME::Pursuit => todo!(),
-            ME::RapidSpin => todo!(),
-            ME::SonicBoom => todo!(),
-            ME::Moonlight => todo!(),
-            ME::HiddenPower => todo!(),
-            ME::RainDance => self.set_weather(conditions, Weather::Rain, using_mon.item, false),
-            ME::SunnyDay => self.set_weather(conditions, Weather::Sun, using_mon.item, false),
-            ME::RaiseUserDefense1Chance => todo!(),
-            ME::RaiseUserAttack1Chance => todo!(),
-            ME::RaiseAllUserStats1Chance => todo!(),
-            ME::BellyDrum => todo!(),
-            ME::PsychUp => todo!(),
-            ME::MirrorCoat => todo!(),
-            ME::SkullBash => todo!(),
-            ME::Twister => todo!(),
-            ME::Earthquake => todo!(),
-            ME::Hits2TurnsLater => todo!(),
-            ME::Gust => todo!(),
-            ME::Stomp => todo!(),
-            ME::Solarbeam => todo!(),
-            ME::Thunder => todo!(),
-            ME::Teleport => todo!(),
-            ME::BeatUp => todo!(),
-            ME::Fly => todo!(),
-            ME::DefenseCurl => todo!(),
-            ME::FakeOut => todo!(),
-            ME::Uproar => todo!(),
-            ME::Stockpile => todo!(),
-            ME::SpitUp => todo!(),
-            ME::Swallow => todo!(),
-            ME::Hail => self.set_weather(conditions, Weather::Hail, using_mon.item, false),
-            ME::Torment => todo!(),
-            ME::Flatter => todo!(),
-            ME::Memento => todo!(),
-            ME::Facade => todo!(),
-            ME::FocusPunch => todo!(),
-            ME::SmellingSalts => todo!(),
-            ME::FollowMe => todo!(),
-            ME::NaturePower => todo!(),
-            ME::Charge => todo!(),
-            ME::Taunt => todo!(),
-            ME::HelpingHand => todo!(),
-            ME::Trick => todo!(),
-            ME::RolePlay => todo!(),
-            ME::Wish => todo!(),
-            ME::RandomlySwitchOutTarget => todo!(),
-            ME::Ingrain => todo!(),
-            ME::LowerUserAttackDefense1 => todo!(),
-            ME::MagicCoat => todo!(),
-            ME::Recycle => todo!(),
-            ME::DoubleDamageIfHitBeforeAttacking => todo!(),
-            ME::DestroyScreens => todo!(),
-            ME::Yawn => todo!(),
-            ME::KnockOff => todo!(),
-            ME::PowerBasedOnUserHP => todo!(),
-            ME::SkillSwap => todo!(),
-            ME::Imprison => todo!(),
-            ME::HealUserStatus => todo!(),
-            ME::Grudge => todo!(),
-            ME::Snatch => todo!(),
-            ME::PowerBasedOnWeight => todo!(),
-            ME::SecretPower => todo!(),
-            ME::RecoilThird => todo!(),
-            ME::IncreasedCritAndBurnChance => todo!(),
-            ME::MudSport => todo!(),
-            ME::WeatherBall => todo!(),
-            ME::LowerUserSpecialAttack => todo!(),
-            ME::LowerTargetAttackDefense1 => todo!(),
-            ME::RaiseUserDefenseSpecialDefense1 => todo!(),
-            ME::HitBounceFly => todo!(),
-            ME::RaiseUserAttackDefense1 => todo!(),
-            ME::IncreasedCritAndPoisonChance => todo!(),
-            ME::WaterSport => todo!(),
-            ME::RaiseUserSpecialAttackSpecialDefense1 => todo!(),
-            ME::DragonDance => {
-                using_mon.apply_stat_changes(Stat::Attack, 1);
-                using_mon.apply_stat_changes(Stat::Speed, 1);
-            }
-            ME::Camouflage => todo!(),
-            ME::Roost => todo!(),
-            ME::Gravity => todo!(),
-            ME::MiracleEye => todo!(),
-            ME::WakeUpSlap => todo!(),
-            ME::LowerUserSpeed1 => using_mon.apply_stat_changes(Stat::Speed, -1),
-            ME::GyroBall => todo!(),
-            ME::HealingWish => todo!(),
-            ME::NaturalGift => todo!(),
-            ME::Feint => todo!(),
-            ME::Pluck => todo!(),
-            ME::Tailwind => user_side.effects.set_tailwind(5),
-            ME::Acupressure => todo!(),
-            ME::MetalBurst => todo!(),
-            ME::SwitchAfterAttacking => todo!(),
-            ME::LowerUserDefenseSpecialDefense1 => todo!(),
-            ME::DoublePowerIfTargetAlreadyMoved => todo!(),
-            ME::DoublePowerIfTargetAlreadyTookDamage => todo!(),
-            ME::Embargo => todo!(),
-            ME::Fling => todo!(),
-            ME::PsychoShift => todo!(),
-            ME::TrumpCard => todo!(),
-            ME::HealBlock => todo!(),
-            ME::PowerTrick => todo!(),
-            ME::GastroAcid => target_mon.volatile_status.add(VolatileStatusEffect::AbilitySuppression),
-            ME::LuckyChant => todo!(),
-            ME::MeFirst => todo!(),
-            ME::Copycat => todo!(),
-            ME::PowerSwap => todo!(),
-            ME::GuardSwap => todo!(),
-            ME::Punishment => todo!(),
-            ME::LastResort => todo!(),
-            ME::WorrySeed => todo!(),
-            ME::SuckerPunch => todo!(),
-            ME::ToxicSpikes => target_side.effects.add_toxic_spikes(),
-            ME::HeartSwap => todo!(),
-            ME::AquaRing => todo!(),
-            ME::MagnetRise => todo!(),
-            ME::FlareBlitz => todo!(),
-            ME::Struggle => todo!(),
-            ME::Dive => todo!(),
-            ME::Dig => todo!(),
-            ME::Defog => todo!(),
-            ME::TrickRoom => todo!(),
-            ME::Blizzard => todo!(),
-            ME::Whirlpool => todo!(),
-            ME::VoltTackle => todo!(),
-            ME::Bounce => todo!(),
-            ME::Captivate => todo!(),
-            ME::StealthRock => target_side.effects.set_stealth_rock(true),
-            ME::Chatter => todo!(),
-            ME::RecoilHalf => todo!(),
-            ME::LunarDance => todo!(),
-            ME::LowerTargetSpecialDefense2Chance => todo!(),
-            ME::Disappear1TurnIgnoreProtect => todo!(),
-            ME::FireFang => todo!(),
-            ME::IceFang => todo!(),
-            ME::ThunderFang => todo!(),
-            ME::RaiseUserSpecialAttack1Chance => todo!(),
-            ME::HoneClaws => todo!(),
-            ME::WideGuard => todo!(),
-            ME::GuardSplit => todo!(),
-            ME::PowerSplit => todo!(),
-            ME::WonderRoom => todo!(),
-            ME::Psyshock => todo!(),
-            ME::Venoshock => todo!(),
-            ME::Autotomize => todo!(),
-            ME::Telekinesis => todo!(),
-            ME::MagicRoom => todo!(),
-            ME::SmackDown => todo!(),
-            ME::AlwaysCrits => todo!(),
-            ME::SplashDamage => todo!(),
-            ME::QuiverDance => {
-                using_mon.apply_stat_changes(Stat::SpecialAttack, 1);
-                using_mon.apply_stat_changes(Stat::SpecialDefense, 1);
-                using_mon.apply_stat_changes(Stat::Speed, 1);
+        for effect in &mut defender.volatile_status.effects {
+            if let VolatileStatusEffect::Bide(stored, _) = effect {
+                *stored = stored.saturating_add(damage);
             }
-            ME::HeavySlam => todo!(),
-            ME::HitIfTypesShared => todo!(),
-            ME::PowerBasedOnSpeedDifference => todo!(),
-            ME::Soak => todo!(),
-            ME::AcidSpray => todo!(),
-            ME::SimpleBeam => todo!(),
-            ME::Entrainment => todo!(),
-            ME::AfterYou => todo!(),
-            ME::Round => todo!(),
-            ME::EchoedVoice => todo!(),
-            ME::DarkestLariat => todo!(),
-            ME::ClearSmog => todo!(),
-            ME::PowerTrip => todo!(),
-            ME::QuickGuard => todo!(),
-            ME::AllySwitch => todo!(),
-            ME::ShellSmash => {
-                using_mon.apply_stat_changes(Stat::Attack, 2);
-                using_mon.apply_stat_changes(Stat::SpecialAttack, 2);
-                using_mon.apply_stat_changes(Stat::Speed, 2);
-                using_mon.apply_stat_changes(Stat::Defense, -2);
-                using_mon.apply_stat_changes(Stat::SpecialDefense, -2);
-            },
-            ME::HealPulse => todo!(),
-            ME::SkyDrop => todo!(),
-            ME::ShiftGear => todo!(),
-            ME::Roar => todo!(),
-            ME::Incinerate => todo!(),
-            ME::Quash => todo!(),
-            ME::Growth => todo!(),
-            ME::ReflectType => todo!(),
-            ME::Retaliate => todo!(),
-            ME::FinalGambit => todo!(),
-            ME::TailGlow => using_mon.apply_stat_changes(Stat::SpecialAttack, 3),
-            ME::Coil => {
-                using_mon.apply_stat_changes(Stat::Attack, 1);
-                using_mon.apply_stat_changes(Stat::Defense, 1);
-                using_mon.apply_stat_changes(Stat::Accuracy, 1);
-            },
-            ME::Thief => todo!(),
-            ME::WaterPledge => todo!(),
-            ME::FirePledge => todo!(),
-            ME::GrassPledge => todo!(),
-            ME::WorkUp => {
-                using_mon.apply_stat_changes(Stat::Attack, 1);
-                using_mon.apply_stat_changes(Stat::SpecialAttack, 1);
-            },
-            ME::CottonGuard => using_mon.apply_stat_changes(Stat::Defense, 3),
-            ME::RelicSong => todo!(),
-            ME::RockTomb => target_mon.apply_stat_changes(Stat::Speed, -1),
-            ME::FreezeShock => todo!(),
-            ME::IceBurn => todo!(),
-            ME::Hurricane => todo!(),
-            ME::VCreate => {
-                using_mon.apply_stat_changes(Stat::Defense, -1);
-                using_mon.apply_stat_changes(Stat::SpecialDefense, -1);
-                using_mon.apply_stat_changes(Stat::Speed, -1);
-            },
-            ME::FlyingPress => todo!(),
-            ME::Belch => todo!(),
-            ME::Rototiller => todo!(),
-            ME::StickyWeb => target_side.effects.set_sticky_web(true),
-            ME::FellStinger => todo!(),
-            _ => {}
         }
+
+        if used_move.move_type == Type::Fire && defender.non_volatile_status == Some(NonVolatileStatus::Freeze) && defender.hp > 0 {
+            defender.non_volatile_status = None;
+            self.emit(BattleEvent::StatusCured {pokemon: defender.get_event_pokemon(), status: NonVolatileStatus::Freeze});
+        }
+
+        let effectiveness = self.get_type_effectiveness(used_move, defender);
+        self.emit(BattleEvent::Damage {pokemon: defender.get_event_pokemon(), amount: damage, crit, effectiveness});
+
+        if defender.hp == 0 {
+            self.emit(BattleEvent::Faint {pokemon: defender.get_event_pokemon()});
+        }
+
+        if used_move.move_type == Type::Dark && defender_ability == Ability::Justified {
+            self.change_stat(defender, Stat::Attack, 1);
+        }
+
+        if used_move.flags.get_contact() && attacker.item != Some(Item::ProtectivePads) && self.get_ability(attacker, conditions) != Ability::LongReach {
+            self.do_contact(attacker, defender, conditions);
+        }
+
+        self.check_hit_switch(used_side, other_side, hp_before, conditions);
+
+        false
+
     }
 
-    fn do_switch(&self, idx: usize, side: &mut Side, other_side: &mut Side, conditions: &mut Conditions) {
+    // items and abilities that switch someone out after a hit lands
+    fn check_hit_switch(&mut self, used_side: &mut Side, other_side: &mut Side, hp_before: u16, conditions: &Conditions) {
 
-        let mon = side.get_active_mut();
+        let defender = other_side.get_active();
 
-        match self.get_ability(mon, &conditions) {
-            Ability::NaturalCure => mon.non_volatile_status = None,
-            Ability::Regenerator => mon.heal(mon.max_hp / 3),
-            _ => {}
+        if defender.hp == 0 || other_side.pending_switch.is_some() {
+            return;
         }
 
-        mon.volatile_status.clear();
+        let half_hp = defender.max_hp / 2;
 
-        side.active_pokemon = idx;
+        if defender.item == Some(Item::EjectButton) && other_side.has_healthy_reserve() {
+            self.emit(BattleEvent::ItemActivated {pokemon: defender.get_event_pokemon(), item: Item::EjectButton});
+            other_side.get_active_mut().item = None;
+            other_side.pending_switch = Some(SwitchOut::Pivot);
+        }
+        else if defender.item == Some(Item::RedCard) && self.can_force_out(used_side, conditions) {
+            self.emit(BattleEvent::ItemActivated {pokemon: defender.get_event_pokemon(), item: Item::RedCard});
+            other_side.get_active_mut().item = None;
+            used_side.pending_switch = Some(SwitchOut::Forced);
+        }
+        else if self.generation >= 7 && hp_before > half_hp && defender.hp <= half_hp && other_side.has_healthy_reserve() {
 
-        self.activate_ability(side, other_side, conditions);
+            let ability = self.get_ability(defender, conditions);
 
+            if matches!(ability, Ability::EmergencyExit | Ability::WimpOut) {
+                self.emit(BattleEvent::AbilityActivated {pokemon: defender.get_event_pokemon(), ability});
+                other_side.pending_switch = Some(SwitchOut::Pivot);
+            }
+        }
     }
 
-    fn perform_action(&self, action: BattleAction, used_by_side1: bool, state: &mut BattleState) {
+    fn can_force_out(&self, side: &Side, conditions: &Conditions) -> bool {
 
-        let (using_side, other_side) = if used_by_side1 {
-            (&mut state.side1, &mut state.side2)
+        let mon = side.get_active();
+
+        mon.hp > 0 && side.has_healthy_reserve() && self.get_ability(mon, conditions) != Ability::SuctionCups && !has_effect!(mon, VolatileStatusEffect::Ingrain)
+
+    }
+
+    // sound moves and infiltrator only get through from gen 6
+    fn bypasses_substitute(&self, used_move: &Move, attacker: &Pokemon, conditions: &Conditions) -> bool {
+        // the data marks sound moves as authentic, which only holds from gen 6
+        used_move.flags.get_authentic() && !used_move.flags.get_sound() ||
+            self.generation >= 6 && (used_move.flags.get_sound() || self.get_ability(attacker, conditions) == Ability::Infiltrator)
+    }
+
+    fn damage_substitute(&mut self, pokemon: &mut Pokemon, damage: u16) {
+
+        let Some(idx) = pokemon.volatile_status.effects.iter().position(|effect| matches!(effect, VolatileStatusEffect::Substitute(_)))
+        else {
+            return;
+        };
+
+        let VolatileStatusEffect::Substitute(hp) = &mut pokemon.volatile_status.effects[idx]
+        else {
+            unreachable!()
+        };
+
+        *hp = hp.saturating_sub(damage);
+        let effect = pokemon.volatile_status.effects[idx];
+
+        self.emit(BattleEvent::VolatileActivated {pokemon: pokemon.get_event_pokemon(), effect});
+
+        if let VolatileStatusEffect::Substitute(0) = effect {
+            self.remove_substitute(pokemon);
+        }
+    }
+
+    fn remove_substitute(&mut self, pokemon: &mut Pokemon) {
+        find_effect!(pokemon, VolatileStatusEffect::Substitute(hp) => {
+            self.emit(BattleEvent::VolatileEnded {pokemon: pokemon.get_event_pokemon(), effect: VolatileStatusEffect::Substitute(*hp)});
+        });
+        remove_effect!(pokemon, VolatileStatusEffect::Substitute(_));
+    }
+
+    fn create_substitute(&mut self, pokemon: &mut Pokemon) {
+
+        if has_effect!(pokemon, VolatileStatusEffect::Substitute(_)) {
+            self.emit(BattleEvent::Failed);
+            return;
+        }
+
+        let cost = pokemon.max_hp / 4;
+
+        // gen 1 lets the user go down to 0 hp, and its substitute has an extra hp
+        let (fails, substitute_hp) = if self.generation == 1 {
+            (pokemon.hp < cost, cost + 1)
         }
         else {
-            (&mut state.side2, &mut state.side1)
+            (pokemon.hp <= cost || cost == 0, cost)
         };
 
-        if using_side.get_active().hp == 0 {
+        if fails {
+            self.emit(BattleEvent::Failed);
             return;
         }
 
-        match action {
-            BattleAction::Move(move_id) => {
-                let used_move = self.data_handler.get_move(move_id);
-                self.use_move(used_move, using_side, other_side, &mut state.conditions);
-            }
-            BattleAction::Switch(mon_idx) =>  {
-                self.do_switch(mon_idx as usize, using_side, other_side, &mut state.conditions);
+        pokemon.deal_damage(cost);
+        self.add_volatile(pokemon, VolatileStatusEffect::Substitute(substitute_hp));
+
+        if pokemon.hp == 0 {
+            self.emit(BattleEvent::Faint {pokemon: pokemon.get_event_pokemon()});
+        }
+    }
+
+    fn get_passed_volatiles(&self, pokemon: &Pokemon, switch_out: SwitchOut) -> VolatileStatus {
+
+        let mut passed = VolatileStatus::default();
+
+        match switch_out {
+            SwitchOut::BatonPass => {
+                passed.stat_stages = pokemon.volatile_status.stat_stages;
+                passed.effects = pokemon.volatile_status.effects.iter().filter(|effect| effect.is_baton_passed()).cloned().collect();
             }
-            BattleAction::Item(_) => todo!()
+            SwitchOut::ShedTail(substitute_hp) => passed.effects.push(VolatileStatusEffect::Substitute(substitute_hp)),
+            SwitchOut::Pivot | SwitchOut::Forced => {}
         }
+
+        passed
+
     }
 
-    fn on_turn_end(&self, state: &mut BattleState) {
-        
-        state.side1.get_active_mut().volatile_status.decriment_counters();
-        state.side2.get_active_mut().volatile_status.decriment_counters();
+    // returns true if the move has to wait a turn
+    fn start_charging(&mut self, used_move: &Move, user: &mut Pokemon, conditions: &Conditions) -> bool {
 
-        state.conditions.decriment_counters();
+        if used_move.effect == MoveEffect::Solarbeam && conditions.is_sunny() {
+            return false;
+        }
+
+        if used_move.effect == MoveEffect::SkullBash && self.generation >= 2 {
+            self.change_stat(user, Stat::Defense, 1);
+        }
+        else if used_move.id == MOVE_METEOR_BEAM {
+            self.change_stat(user, Stat::SpecialAttack, 1);
+        }
+
+        if user.item == Some(Item::PowerHerb) {
+            user.item = None;
+            self.emit(BattleEvent::ItemActivated {pokemon: user.get_event_pokemon(), item: Item::PowerHerb});
+            return false;
+        }
+
+        let semi_invulnerable = match used_move.effect {
+            MoveEffect::Fly | MoveEffect::Bounce => Some(SemiInvulnerableKind::Airborne),
+            MoveEffect::Dig => Some(SemiInvulnerableKind::Underground),
+            MoveEffect::Dive => Some(SemiInvulnerableKind::Underwater),
+            MoveEffect::Disappear1TurnIgnoreProtect => Some(SemiInvulnerableKind::Vanished),
+            _ => None
+        };
+
+        user.volatile_status.add(VolatileStatusEffect::Charging(used_move.id));
+
+        match semi_invulnerable {
+            Some(kind) => self.add_volatile(user, VolatileStatusEffect::SemiInvulernable(kind)),
+            None => self.emit(BattleEvent::VolatileApplied {pokemon: user.get_event_pokemon(), effect: VolatileStatusEffect::Charging(used_move.id)})
+        }
+
+        true
 
     }
 
-    pub fn simulate_turn(&self, side1_action: BattleAction, side2_action: BattleAction, state: &mut BattleState) {
-        
-        log!("");
+    fn stop_charging(&mut self, using_side: &mut Side, other_side: &mut Side) {
+
+        let user = using_side.get_active_mut();
+
+        if !has_effect!(user, VolatileStatusEffect::Charging(_)) {
+            return;
+        }
+
+        remove_effect!(user, VolatileStatusEffect::Charging(_) | VolatileStatusEffect::SemiInvulernable(_));
+
+        // only the user could be holding the target in the sky
+        remove_effect!(other_side.get_active_mut(), VolatileStatusEffect::SkyDropped);
+
+    }
+
+    // stores damage for two turns, then hits back with double
+    fn use_bide(&mut self, used_move: &Move, using_side: &mut Side, other_side: &mut Side, conditions: &Conditions) -> bool {
+
+        let user = using_side.get_active_mut();
 
-        let side1_pokemon = &state.side1.team[state.side1.active_pokemon];
-        let side2_pokemon = &state.side2.team[state.side2.active_pokemon];
+        let mut stored = None;
 
-        let side1_priority = self.get_priority(&side1_action);
-        let side2_priority = self.get_priority(&side2_action);
+        find_effect!(user, VolatileStatusEffect::Bide(damage, turns) => {
+            stored = Some((*damage, *turns));
+        });
+
+        match stored {
+            None => {
+                // counted down at the end of each turn, released when it reaches the last one
+                let turns = if self.generation == 1 {self.rng.random_range(3..=4)} else {3};
+                self.add_volatile(user, VolatileStatusEffect::Bide(0, turns));
+                true
+            }
+            Some((_, turns)) if turns > 1 => {
+                self.emit(BattleEvent::VolatileActivated {pokemon: user.get_event_pokemon(), effect: VolatileStatusEffect::Bide(0, turns)});
+                true
+            }
+            Some((damage, _)) => {
 
-        let mut side1_first = rand::random::<bool>();
+                remove_effect!(user, VolatileStatusEffect::Bide(..));
+                self.emit(BattleEvent::VolatileEnded {pokemon: user.get_event_pokemon(), effect: VolatileStatusEffect::Bide(damage, 0)});
 
-        let side1_speed = self.get_speed_stat(side1_pokemon, &state.conditions);
-        let side2_speed = self.get_speed_stat(side2_pokemon, &state.conditions);
+                // ghosts are immune from gen 2
+                if damage == 0 || self.generation >= 2 && self.get_type_effectiveness(used_move, other_side.get_active()) == 0 {
+                    self.emit(BattleEvent::Failed);
+                    return false;
+                }
+
+                self.do_move_hit(using_side, other_side, damage.saturating_mul(2), false, used_move, conditions);
+                true
 
-        if side1_priority != side2_priority {
-            side1_first = side1_priority > side2_priority;
+            }
         }
-        else if side1_speed != side2_speed {
-            side1_first = side1_speed != side2_speed
+    }
+
+    // starts, continues or ends moves that repeat over several turns
+    fn update_locked_move(&mut self, used_move: &Move, user: &mut Pokemon, continuing: bool, succeeded: bool) {
+
+        if used_move.effect == MoveEffect::Outrage || used_move.id == MOVE_RAGING_FURY {
+
+            if !continuing {
+                if succeeded {
+                    let turns = if self.generation == 1 {self.rng.random_range(2..=3)} else {self.rng.random_range(1..=2)};
+                    user.volatile_status.add(VolatileStatusEffect::Rampage(turns));
+                }
+            }
+            // being disrupted ends the rampage without confusion from gen 5
+            else if !succeeded && self.generation >= 5 {
+                remove_effect!(user, VolatileStatusEffect::Rampage(_));
+            }
+            else {
+
+                let mut finished = false;
+
+                for effect in &mut user.volatile_status.effects {
+                    if let VolatileStatusEffect::Rampage(turns) = effect {
+                        *turns -= 1;
+                        finished = *turns == 0;
+                    }
+                }
+
+                if finished {
+
+                    remove_effect!(user, VolatileStatusEffect::Rampage(_));
+
+                    // fatigue
+                    if !has_effect!(user, VolatileStatusEffect::Confusion(_)) {
+                        let turns = self.rng.random_range(2..=5);
+                        self.add_volatile(user, VolatileStatusEffect::Confusion(turns));
+                    }
+                }
+            }
         }
+        else if used_move.effect == MoveEffect::Rollout {
+
+            if !succeeded {
+                remove_effect!(user, VolatileStatusEffect::Rolling(_));
+            }
+            else {
 
-        if side1_first {
-            self.perform_action(side1_action, true, state);
-            self.perform_action(side2_action, false, state);
+                let mut rolls = 1;
+
+                find_effect!(user, VolatileStatusEffect::Rolling(previous) => {
+                    rolls = previous + 1;
+                });
+
+                remove_effect!(user, VolatileStatusEffect::Rolling(_));
+
+                if rolls < 5 {
+                    user.volatile_status.add(VolatileStatusEffect::Rolling(rolls));
+                }
+            }
         }
-        else {
-            self.perform_action(side2_action, false, state);
-            self.perform_action(side1_action, true, state);
+        else if used_move.effect == MoveEffect::Uproar && !continuing && succeeded {
+            let turns = if self.generation >= 5 {3} else {self.rng.random_range(2..=5)};
+            self.add_volatile(user, VolatileStatusEffect::Uproar(turns));
         }
 
-        self.on_turn_end(state);
+        // choice items lock in whatever was picked, even if it failed
+        if self.is_choice_item(user.item) && !has_effect!(user, VolatileStatusEffect::Choiced(_)) {
+            if let Some(slot) = user.moves.iter().position(|slot| slot.is_some_and(|slot| slot.id == used_move.id)) {
+                user.volatile_status.add(VolatileStatusEffect::Choiced(slot as u8));
+            }
+        }
+    }
 
-        // println!("{:#?}", state.side1);
-        // println!("{:#?}", state.side2);
-        
+    fn is_choice_item(&self, item: Option<Item>) -> bool {
+        matches!(item, Some(Item::ChoiceBand | Item::ChoiceScarf | Item::ChoiceSpecs))
+    }
+
+    fn is_uproar(&self, pokemon: &Pokemon, other: &Pokemon) -> bool {
+        has_effect!(pokemon, VolatileStatusEffect::Uproar(_)) || has_effect!(other, VolatileStatusEffect::Uproar(_))
+    }
+
+    fn can_reach_semi_invulnerable(&self, used_move: &Move, attacker: &Pokemon, defender: &Pokemon, conditions: &Conditions) -> bool {
+
+        let mut hidden = None;
+
+        find_effect!(defender, VolatileStatusEffect::SemiInvulernable(kind) => {
+            hidden = Some(*kind);
+        });
+
+        let Some(kind) = hidden
+        else {
+            return true;
+        };
+
+        if self.get_ability(attacker, conditions) == Ability::NoGuard || self.get_ability(defender, conditions) == Ability::NoGuard {
+            return true;
+        }
+
+        // nothing gets through in gen 1
+        self.generation >= 2 && match kind {
+            SemiInvulnerableKind::Airborne => matches!(used_move.effect,
+                MoveEffect::Gust | MoveEffect::Twister | MoveEffect::Thunder | MoveEffect::HitBounceFly | MoveEffect::Hurricane | MoveEffect::SmackDown
+            ),
+            SemiInvulnerableKind::Underground => matches!(used_move.effect, MoveEffect::Earthquake | MoveEffect::Magnitude),
+            SemiInvulnerableKind::Underwater => matches!(used_move.effect, MoveEffect::Surf | MoveEffect::Whirlpool),
+            SemiInvulnerableKind::Vanished => false
+        }
+    }
+
+    fn hits_semi_invulnerable_twice(&self, used_move: &Move, defender: &Pokemon) -> bool {
+
+        let mut doubled = false;
+
+        find_effect!(defender, VolatileStatusEffect::SemiInvulernable(kind) => {
+            doubled = matches!((used_move.effect, kind),
+                (MoveEffect::Gust | MoveEffect::Twister, SemiInvulnerableKind::Airborne) |
+                (MoveEffect::Earthquake | MoveEffect::Magnitude, SemiInvulnerableKind::Underground) |
+                (MoveEffect::Surf | MoveEffect::Whirlpool, SemiInvulnerableKind::Underwater)
+            );
+        });
+
+        doubled
+
+    }
+
+    fn uses_protect_chain(&self, used_move: &Move) -> bool {
+        matches!(used_move.effect,
+            MoveEffect::Protect | MoveEffect::Endure | MoveEffect::KingsShield | MoveEffect::SpikyShield | MoveEffect::BanefulBunker | MoveEffect::Obstruct
+        ) ||
+            matches!(used_move.id, MOVE_SILK_TRAP | MOVE_BURNING_BULWARK) ||
+            self.generation <= 5 && matches!(used_move.effect, MoveEffect::WideGuard | MoveEffect::QuickGuard)
+    }
+
+    fn try_protect(&mut self, pokemon: &mut Pokemon, effect: VolatileStatusEffect) {
+
+        // guarding the whole side only shares the counter with protect in gen 5
+        let uses_chain = match effect {
+            VolatileStatusEffect::Protect(ProtectKind::MatBlock | ProtectKind::CraftyShield) => false,
+            VolatileStatusEffect::Protect(ProtectKind::WideGuard | ProtectKind::QuickGuard) => self.generation <= 5,
+            _ => true
+        };
+
+        if uses_chain {
+
+            let chain = pokemon.volatile_status.protect_chain as u32;
+
+            // each consecutive use is less likely to work
+            let denominator = match self.generation {
+                3 | 4 => 1 << chain.min(3),
+                ..=5 => 1 << chain.min(8),
+                _ => 3u32.pow(chain.min(6))
+            };
+
+            if !self.rng.random_ratio(1, denominator) {
+                pokemon.volatile_status.protect_chain = 0;
+                self.emit(BattleEvent::Failed);
+                return;
+            }
+
+            pokemon.volatile_status.protect_chain = pokemon.volatile_status.protect_chain.saturating_add(1);
+        }
+
+        self.add_volatile(pokemon, effect);
+
+    }
+
+    fn breaks_protection(&self, used_move: &Move) -> bool {
+        matches!(used_move.effect, MoveEffect::Feint | MoveEffect::DestroyProtect | MoveEffect::Disappear1TurnIgnoreProtect)
+    }
+
+    fn blocks_move(&self, kind: ProtectKind, used_move: &Move, using_side: &Side, conditions: &Conditions) -> bool {
+
+        // unseen fist makes contact moves ignore protection entirely
+        if !used_move.flags.get_protect() || used_move.flags.get_contact() && self.get_ability(using_side.get_active(), conditions) == Ability::UnseenFist {
+            return false;
+        }
+
+        match kind {
+            ProtectKind::KingsShield | ProtectKind::SilkTrap | ProtectKind::BurningBulwark | ProtectKind::Obstruct | ProtectKind::MatBlock => used_move.class != MoveClass::Status,
+            ProtectKind::WideGuard => {
+                matches!(used_move.target, MoveTarget::AllOpponents | MoveTarget::AllOtherPokemon) && (self.generation >= 6 || used_move.class != MoveClass::Status)
+            }
+            ProtectKind::QuickGuard => self.get_action_priority(&BattleAction::Move(used_move.id), using_side, conditions) > 0,
+            ProtectKind::CraftyShield => used_move.class == MoveClass::Status,
+            ProtectKind::Protect | ProtectKind::SpikyShield | ProtectKind::BanefulBunker => true
+        }
+    }
+
+    // returns true if the move was stopped by the target's protection
+    fn check_protection(&mut self, used_move: &Move, using_side: &mut Side, other_side: &mut Side, conditions: &Conditions) -> bool {
+
+        let mut protection = None;
+
+        // moves that break protection don't have the protect flag, so they never count as blocked
+        let breaks_protection = self.breaks_protection(used_move);
+
+        find_effect!(other_side.get_active(), VolatileStatusEffect::Protect(kind) => {
+            if protection.is_none() && (breaks_protection || self.blocks_move(*kind, used_move, using_side, conditions)) {
+                protection = Some(*kind);
+            }
+        });
+
+        let Some(kind) = protection
+        else {
+            return false;
+        };
+
+        let target = other_side.get_active_mut();
+
+        if breaks_protection {
+            remove_effect!(target, VolatileStatusEffect::Protect(_));
+            self.emit(BattleEvent::VolatileEnded {pokemon: target.get_event_pokemon(), effect: VolatileStatusEffect::Protect(kind)});
+            return false;
+        }
+
+        self.emit(BattleEvent::VolatileActivated {pokemon: target.get_event_pokemon(), effect: VolatileStatusEffect::Protect(kind)});
+
+        let attacker = using_side.get_active_mut();
+
+        if !used_move.flags.get_contact() || self.generation >= 7 && attacker.item == Some(Item::ProtectivePads) {
+            return true;
+        }
+
+        match kind {
+            ProtectKind::KingsShield => self.change_stat(attacker, Stat::Attack, if self.generation >= 8 {-1} else {-2}),
+            ProtectKind::SpikyShield => self.deal_residual_damage(attacker, attacker.max_hp / 8, DamageCause::SpikyShield, conditions),
+            ProtectKind::BanefulBunker => self.try_apply_status(attacker, NonVolatileStatus::Poison),
+            ProtectKind::SilkTrap => self.change_stat(attacker, Stat::Speed, -1),
+            ProtectKind::BurningBulwark => self.try_apply_status(attacker, NonVolatileStatus::Burn),
+            ProtectKind::Obstruct => self.change_stat(attacker, Stat::Defense, -2),
+            _ => {}
+        }
+
+        true
+
+    }
+
+    fn bypasses_screens(&self, attacker: &Pokemon, conditions: &Conditions) -> bool {
+        self.generation >= 6 && self.get_ability(attacker, conditions) == Ability::Infiltrator
+    }
+
+    fn get_screen_turns(&self, pokemon: &Pokemon) -> u8 {
+        if pokemon.item == Some(Item::LightClay) {8} else {5}
+    }
+
+    fn start_side_condition(&mut self, side: &mut Side, condition: SideCondition, turns: u8) {
+
+        if side.effects.is_active(condition) {
+            self.emit(BattleEvent::Failed);
+            return;
+        }
+
+        side.effects.set_turns(condition, turns);
+        self.emit(BattleEvent::SideConditionStarted {pokemon: side.get_active().get_event_pokemon(), condition});
+
+    }
+
+    fn try_inflict_status(&mut self, pokemon: &mut Pokemon, safeguarded: bool, status: NonVolatileStatus) {
+
+        if safeguarded {
+            self.emit(BattleEvent::SideConditionBlocked {pokemon: pokemon.get_event_pokemon(), condition: SideCondition::Safeguard});
+            return;
+        }
+
+        self.try_apply_status(pokemon, status);
+
+    }
+
+    fn change_target_stat(&mut self, pokemon: &mut Pokemon, misted: bool, stat: Stat, stages: i8) {
+
+        if misted && stages < 0 {
+            self.emit(BattleEvent::SideConditionBlocked {pokemon: pokemon.get_event_pokemon(), condition: SideCondition::Mist});
+            return;
+        }
+
+        self.change_stat(pokemon, stat, stages);
+
+    }
+
+    fn roll_sleep_turns(&mut self) -> u8 {
+        match self.generation {
+            1 => self.rng.random_range(1..=7),
+            2 => self.rng.random_range(2..=7),
+            3 | 4 => self.rng.random_range(2..=5),
+            _ => self.rng.random_range(2..=4)
+        }
+    }
+
+    fn try_infatuate(&mut self, pokemon: &mut Pokemon, source: &mut Pokemon, conditions: &Conditions) -> bool {
+
+        if !pokemon.gender.is_opposite(source.gender) || has_effect!(pokemon, VolatileStatusEffect::Infatuation) || self.get_ability(pokemon, conditions) == Ability::Oblivious {
+            return false;
+        }
+
+        self.add_volatile(pokemon, VolatileStatusEffect::Infatuation);
+
+        if pokemon.item == Some(Item::DestinyKnot) && !has_effect!(source, VolatileStatusEffect::Infatuation) && self.get_ability(source, conditions) != Ability::Oblivious {
+            self.emit(BattleEvent::ItemActivated {pokemon: pokemon.get_event_pokemon(), item: Item::DestinyKnot});
+            self.add_volatile(source, VolatileStatusEffect::Infatuation);
+        }
+
+        true
+
+    }
+
+    // typeless 40 power physical hit against itself that can't crit
+    fn calc_confusion_damage(&mut self, pokemon: &Pokemon, conditions: &Conditions) -> u16 {
+
+        let attack_stat = self.get_attack_stat(pokemon, conditions, false) as u32;
+        let defense_stat = self.get_defense_stat(pokemon, conditions, false) as u32;
+
+        let mut damage = (2 * (pokemon.level as u32) / 5 + 2) * 40 * attack_stat / defense_stat.max(1) / 50 + 2;
+
+        damage *= self.rng.random_range(85..=100);
+        damage /= 100;
+
+        damage as u16
+
+    }
+
+    // recharge, sleep, freeze, flinch, confusion, infatuation, paralysis
+    // encore and choice items hold the pokemon to a single move
+    fn get_forced_move(&self, pokemon: &Pokemon) -> Option<ID> {
+
+        let mut forced = None;
+
+        for effect in &pokemon.volatile_status.effects {
+            match effect {
+                VolatileStatusEffect::Encore(move_id, _) => forced = Some(*move_id),
+                VolatileStatusEffect::Choiced(slot) if self.is_choice_item(pokemon.item) => {
+                    forced = forced.or(pokemon.moves[*slot as usize].map(|slot| slot.id));
+                }
+                _ => {}
+            }
+        }
+
+        forced
+
+    }
+
+    fn get_move_restriction(&self, used_move: &Move, pokemon: &Pokemon, opponent: &Pokemon, conditions: &Conditions) -> Option<CantMoveReason> {
+
+        // struggle and moves already underway can't be stopped
+        if used_move.id == MOVE_STRUGGLE || pokemon.get_locked_move() == Some(used_move.id) {
+            return None;
+        }
+
+        for effect in &pokemon.volatile_status.effects {
+            match effect {
+                VolatileStatusEffect::Disable(move_id, _) if *move_id == used_move.id => return Some(CantMoveReason::Disable),
+                VolatileStatusEffect::Taunt(_) if used_move.class == MoveClass::Status => return Some(CantMoveReason::Taunt),
+                VolatileStatusEffect::Torment if pokemon.volatile_status.last_move == Some(used_move.id) => return Some(CantMoveReason::Torment),
+                VolatileStatusEffect::HealBlock(_) if used_move.flags.get_heal() => return Some(CantMoveReason::HealBlock),
+                VolatileStatusEffect::ThroatChop(_) if used_move.flags.get_sound() => return Some(CantMoveReason::ThroatChop),
+                _ => {}
+            }
+        }
+
+        if has_effect!(opponent, VolatileStatusEffect::Imprison) && opponent.get_move_slot(used_move.id).is_some() {
+            return Some(CantMoveReason::Imprison);
+        }
+
+        if conditions.is_gravity() && used_move.flags.get_gravity() {
+            return Some(CantMoveReason::Gravity);
+        }
+
+        if pokemon.item == Some(Item::AssaultVest) && used_move.class == MoveClass::Status {
+            return Some(CantMoveReason::AssaultVest);
+        }
+
+        None
+
+    }
+
+    fn is_trapped(&self, pokemon: &Pokemon, opponent: &Pokemon, conditions: &Conditions) -> bool {
+
+        if self.generation >= 4 && pokemon.item == Some(Item::ShedShell) || self.generation >= 6 && self.pokemon_has_type(pokemon, Type::Ghost) {
+            return false;
+        }
+
+        if has_effect!(pokemon,
+            VolatileStatusEffect::Bind {..} | VolatileStatusEffect::Trapped | VolatileStatusEffect::JawLock |
+            VolatileStatusEffect::Octolock | VolatileStatusEffect::NoRetreat | VolatileStatusEffect::Ingrain
+        ) {
+            return true;
+        }
+
+        if opponent.hp == 0 {
+            return false;
+        }
+
+        match self.get_ability(opponent, conditions) {
+            // two shadow tags cancel out from gen 4
+            Ability::ShadowTag => self.generation <= 3 || self.get_ability(pokemon, conditions) != Ability::ShadowTag,
+            Ability::ArenaTrap => self.is_grounded(pokemon, conditions),
+            Ability::MagnetPull => self.pokemon_has_type(pokemon, Type::Steel),
+            _ => false
+        }
+
+    }
+
+    // every move and switch the side can choose, forfeiting is always allowed and isn't listed
+    pub fn legal_actions(&self, state: &BattleState, is_side1: bool) -> Vec<BattleAction> {
+
+        let (side, other_side) = if is_side1 {
+            (&state.side1, &state.side2)
+        }
+        else {
+            (&state.side2, &state.side1)
+        };
+
+        let mon = side.get_active();
+
+        if let Some(move_id) = mon.get_locked_move() {
+            return vec![BattleAction::Move(move_id)];
+        }
+
+        let forced = self.get_forced_move(mon);
+
+        let mut actions = mon.moves.iter().flatten().filter(|slot| {
+            let move_data = self.data_handler.get_move_in_generation(slot.id, self.generation);
+            slot.pp > 0 && forced.is_none_or(|forced| forced == slot.id) && self.get_move_restriction(move_data, mon, other_side.get_active(), &state.conditions).is_none()
+        }).map(|slot| BattleAction::Move(slot.id)).collect::<Vec<_>>();
+
+        if actions.is_empty() {
+            actions.push(BattleAction::Move(MOVE_STRUGGLE));
+        }
+
+        if self.is_trapped(mon, other_side.get_active(), &state.conditions) {
+            return actions;
+        }
+
+        for idx in 0..side.team.len() {
+            if side.is_valid_switch_in(idx) {
+                actions.push(BattleAction::Switch(idx as u8));
+            }
+        }
+
+        actions
+
+    }
+
+    // anything outside legal_actions goes back to the controller
+    fn check_action(&self, action: BattleAction, is_side1: bool, state: &BattleState, controller: &dyn Controller) -> BattleAction {
+
+        let legal_actions = self.legal_actions(state, is_side1);
+        let is_legal = |action: &BattleAction| *action == BattleAction::Forfeit || legal_actions.contains(action);
+
+        let mut action = action;
+        let mut prompts = 0;
+
+        while !is_legal(&action) {
+
+            if prompts == MAX_PROMPTS {
+                return legal_actions[0];
+            }
+
+            action = controller.get_action(state, is_side1, &legal_actions);
+            prompts += 1;
+        }
+
+        action
+
+    }
+
+    fn can_move(&mut self, used_move: &Move, mon: &mut Pokemon, conditions: &Conditions) -> bool {
+
+        let ability = self.get_ability(mon, conditions);
+
+        if has_effect!(mon, VolatileStatusEffect::SkyDropped) {
+            return false;
+        }
+
+        if has_effect!(mon, VolatileStatusEffect::Recharge) {
+            remove_effect!(mon, VolatileStatusEffect::Recharge);
+            self.emit(BattleEvent::CantMove {pokemon: mon.get_event_pokemon(), reason: CantMoveReason::Recharge});
+            return false;
+        }
+
+        match mon.non_volatile_status {
+            Some(NonVolatileStatus::Sleep(turns)) => {
+
+                let turns = turns.saturating_sub(if ability == Ability::EarlyBird {2} else {1});
+
+                if turns == 0 {
+
+                    mon.non_volatile_status = None;
+                    self.emit(BattleEvent::StatusCured {pokemon: mon.get_event_pokemon(), status: NonVolatileStatus::Sleep(0)});
+
+                    // waking up used up the turn in gen 1
+                    if self.generation == 1 {
+                        return false;
+                    }
+                }
+                else {
+
+                    mon.non_volatile_status = Some(NonVolatileStatus::Sleep(turns));
+
+                    if !matches!(used_move.effect, MoveEffect::SleepTalk | MoveEffect::FlinchChanceWorksIfSleeping) {
+                        self.emit(BattleEvent::CantMove {pokemon: mon.get_event_pokemon(), reason: CantMoveReason::Sleep});
+                        return false;
+                    }
+                }
+            }
+            Some(NonVolatileStatus::Freeze) => {
+
+                // gen 2 thaws at the end of the turn instead
+                let thawed = used_move.flags.get_defrost() && self.generation >= 2 ||
+                    self.generation >= 3 && self.rng.random_ratio(1, 5);
+
+                if thawed {
+                    mon.non_volatile_status = None;
+                    self.emit(BattleEvent::StatusCured {pokemon: mon.get_event_pokemon(), status: NonVolatileStatus::Freeze});
+                }
+                else {
+                    self.emit(BattleEvent::CantMove {pokemon: mon.get_event_pokemon(), reason: CantMoveReason::Freeze});
+                    return false;
+                }
+            }
+            _ => {}
+        }
+
+        if has_effect!(mon, VolatileStatusEffect::Flinch) {
+
+            self.emit(BattleEvent::CantMove {pokemon: mon.get_event_pokemon(), reason: CantMoveReason::Flinch});
+
+            if ability == Ability::Steadfast && self.generation >= 4 {
+                self.change_stat(mon, Stat::Speed, 1);
+            }
+
+            return false;
+
+        }
+
+        let mut confusion_turns = None;
+
+        for effect in &mut mon.volatile_status.effects {
+            if let VolatileStatusEffect::Confusion(turns) = effect {
+                *turns -= 1;
+                confusion_turns = Some(*turns);
+            }
+        }
+
+        match confusion_turns {
+            Some(0) => {
+                remove_effect!(mon, VolatileStatusEffect::Confusion(_));
+                self.emit(BattleEvent::VolatileEnded {pokemon: mon.get_event_pokemon(), effect: VolatileStatusEffect::Confusion(0)});
+            }
+            Some(turns) => {
+
+                self.emit(BattleEvent::VolatileActivated {pokemon: mon.get_event_pokemon(), effect: VolatileStatusEffect::Confusion(turns)});
+
+                let hit_self = if self.generation >= 7 {
+                    self.rng.random_ratio(1, 3)
+                }
+                else {
+                    self.rng.random_ratio(1, 2)
+                };
+
+                if hit_self {
+
+                    let damage = self.calc_confusion_damage(mon, conditions).min(mon.hp);
+
+                    mon.deal_damage(damage);
+                    self.emit(BattleEvent::IndirectDamage {pokemon: mon.get_event_pokemon(), amount: damage, cause: DamageCause::Confusion});
+
+                    if mon.hp == 0 {
+                        self.emit(BattleEvent::Faint {pokemon: mon.get_event_pokemon()});
+                    }
+
+                    return false;
+
+                }
+            }
+            None => {}
+        }
+
+        if has_effect!(mon, VolatileStatusEffect::Infatuation) {
+
+            self.emit(BattleEvent::VolatileActivated {pokemon: mon.get_event_pokemon(), effect: VolatileStatusEffect::Infatuation});
+
+            if self.rng.random::<bool>() {
+                self.emit(BattleEvent::CantMove {pokemon: mon.get_event_pokemon(), reason: CantMoveReason::Infatuation});
+                return false;
+            }
+        }
+
+        if mon.non_volatile_status == Some(NonVolatileStatus::Paralysis) && self.rng.random_ratio(1, 4) {
+            self.emit(BattleEvent::CantMove {pokemon: mon.get_event_pokemon(), reason: CantMoveReason::Paralysis});
+            return false;
+        }
+
+        true
+
+    }
+
+    // returns false if the move failed, missed or was blocked
+    fn use_move(&mut self, used_move: &Move, using_side: &mut Side, other_side: &mut Side, conditions: &mut Conditions) -> bool {
+        
+        self.emit(BattleEvent::MoveUsed {pokemon: using_side.get_active().get_event_pokemon(), move_id: used_move.id, move_name: used_move.name.clone()});
+
+        // moves carried over from the last turn were paid for when they started
+        let continuing = using_side.get_active().get_locked_move() == Some(used_move.id);
+        self.stop_charging(using_side, other_side);
+
+        if !continuing {
+            self.deduct_pp(used_move, using_side.get_active_mut(), other_side.get_active(), conditions);
+            using_side.get_active_mut().volatile_status.last_move = Some(used_move.id);
+        }
+
+        if !self.uses_protect_chain(used_move) {
+            using_side.get_active_mut().volatile_status.protect_chain = 0;
+        }
+
+        // sky drop has to connect before it can lift the target
+        if !continuing && used_move.flags.get_charge() && used_move.effect != MoveEffect::SkyDrop && self.start_charging(used_move, using_side.get_active_mut(), conditions) {
+            return true;
+        }
+
+        if used_move.effect == MoveEffect::Bide {
+            return self.use_bide(used_move, using_side, other_side, conditions);
+        }
+
+        if self.fails_to_start(used_move, using_side.get_active(), other_side.get_active()) {
+            self.emit(BattleEvent::Failed);
+            return false;
+        }
+
+        if used_move.targets_opponent() && !self.can_reach_semi_invulnerable(used_move, using_side.get_active(), other_side.get_active(), conditions) {
+            self.emit(BattleEvent::Miss {pokemon: using_side.get_active().get_event_pokemon()});
+            return false;
+        }
+
+        if used_move.targets_opponent() && self.check_protection(used_move, using_side, other_side, conditions) {
+            return false;
+        }
+
+        if !self.check_hit(used_move, using_side.get_active(), other_side.get_active(), conditions) {
+            self.emit(BattleEvent::Miss {pokemon: using_side.get_active().get_event_pokemon()});
+            return false;
+        }
+
+        if used_move.effect == MoveEffect::SkyDrop && !continuing {
+
+            let target = other_side.get_active_mut();
+
+            // too heavy to lift from gen 6
+            if has_effect!(target, VolatileStatusEffect::Substitute(_)) || self.generation >= 6 && self.get_weight(target) >= 2000.0 {
+                self.emit(BattleEvent::Failed);
+                return false;
+            }
+
+            let user = using_side.get_active_mut();
+            user.volatile_status.add(VolatileStatusEffect::Charging(used_move.id));
+            self.add_volatile(user, VolatileStatusEffect::SemiInvulernable(SemiInvulnerableKind::Airborne));
+            self.add_volatile(other_side.get_active_mut(), VolatileStatusEffect::SkyDropped);
+
+            return true;
+        }
+
+        if used_move.class == MoveClass::Status {
+
+            if used_move.targets_opponent() && has_effect!(other_side.get_active(), VolatileStatusEffect::Substitute(_)) && !self.bypasses_substitute(used_move, using_side.get_active(), conditions) {
+                self.emit(BattleEvent::Failed);
+                return false;
+            }
+
+            self.roll_move_effect(used_move, using_side, other_side, conditions, 0, false);
+        }
+        else {
+
+            let hit_count = self.roll_hit_count(used_move, using_side.get_active(), conditions);
+
+            let parental_bond = hit_count == 1 && self.generation >= 6 && self.get_ability(using_side.get_active(), conditions) == Ability::ParentalBond;
+            let total_hits = if parental_bond {2} else {hit_count};
+
+            let mut hits = 0;
+            let mut total_damage = 0u16;
+            let mut hit_substitute = false;
+
+            for hit in 0..total_hits {
+
+                if hit > 0 {
+
+                    if using_side.get_active().hp == 0 || other_side.get_active().hp == 0 {
+                        break;
+                    }
+
+                    if self.checks_accuracy_each_hit(used_move, using_side.get_active(), conditions) && !self.check_hit(used_move, using_side.get_active(), other_side.get_active(), conditions) {
+                        break;
+                    }
+                }
+
+                let had_substitute = has_effect!(other_side.get_active(), VolatileStatusEffect::Substitute(_));
+
+                let crit = self.roll_crit(used_move, using_side.get_active(), other_side, conditions);
+                let damage = self.calc_damage(used_move, using_side.get_active(), other_side, conditions, crit, hit, parental_bond && hit == 1);
+                hit_substitute = self.do_move_hit(using_side, other_side, damage, crit, used_move, conditions);
+
+                hits += 1;
+                total_damage = total_damage.saturating_add(damage);
+
+                // gen 1 stops hitting once a substitute breaks
+                if self.generation == 1 && had_substitute && !has_effect!(other_side.get_active(), VolatileStatusEffect::Substitute(_)) {
+                    break;
+                }
+            }
+
+            if total_hits > 1 {
+                self.emit(BattleEvent::HitCount {pokemon: other_side.get_active().get_event_pokemon(), count: hits});
+            }
+
+            // secondary effects and stat changes only roll once, after the last hit
+            self.roll_move_effect(used_move, using_side, other_side, conditions, total_damage, hit_substitute);
+        }
+
+        // charge wears off after the next move, or after the next electric move from gen 9
+        if used_move.effect != MoveEffect::Charge && (self.generation < 9 || used_move.move_type == Type::Electric) {
+            remove_effect!(using_side.get_active_mut(), VolatileStatusEffect::Charge);
+        }
+
+        // newer moves don't have an effect id in the database
+        match used_move.id {
+            MOVE_MORTAL_SPIN => {
+                self.rapid_spin(using_side);
+                self.try_apply_status(other_side.get_active_mut(), NonVolatileStatus::Poison);
+            }
+            MOVE_SHED_TAIL => {
+
+                let has_reserve = using_side.has_healthy_reserve();
+                let user = using_side.get_active_mut();
+                let cost = user.max_hp.div_ceil(2);
+
+                if user.hp <= cost || !has_reserve {
+                    self.emit(BattleEvent::Failed);
+                    return false;
+                }
+
+                user.deal_damage(cost);
+                using_side.pending_switch = Some(SwitchOut::ShedTail(user.max_hp / 4));
+
+            }
+            MOVE_FLIP_TURN => if using_side.get_active().hp > 0 && using_side.has_healthy_reserve() && using_side.pending_switch.is_none() {
+                using_side.pending_switch = Some(SwitchOut::Pivot);
+            }
+            MOVE_SILK_TRAP => self.try_protect(using_side.get_active_mut(), VolatileStatusEffect::Protect(ProtectKind::SilkTrap)),
+            MOVE_BURNING_BULWARK => self.try_protect(using_side.get_active_mut(), VolatileStatusEffect::Protect(ProtectKind::BurningBulwark)),
+            MOVE_PSYCHIC_NOISE => {
+
+                let target = other_side.get_active_mut();
+
+                if target.hp > 0 && !has_effect!(target, VolatileStatusEffect::HealBlock(_)) {
+                    self.add_volatile(target, VolatileStatusEffect::HealBlock(2));
+                }
+            }
+            MOVE_TIDY_UP => {
+
+                for side in [&mut *using_side, &mut *other_side] {
+
+                    if side.effects.has_hazards() {
+                        side.effects.clear_hazards();
+                        self.emit(BattleEvent::HazardsCleared {pokemon: side.get_active().get_event_pokemon()});
+                    }
+
+                    self.remove_substitute(side.get_active_mut());
+
+                }
+
+                self.change_stat(using_side.get_active_mut(), Stat::Attack, 1);
+                self.change_stat(using_side.get_active_mut(), Stat::Speed, 1);
+
+            }
+            _ => {}
+        }
+
+        true
+
+    }
+
+    // moves that fail on their own before accuracy or protection come into it
+    fn fails_to_start(&self, used_move: &Move, user: &Pokemon, target: &Pokemon) -> bool {
+        match used_move.effect {
+            // feint only works against protection in gen 4
+            MoveEffect::Feint => self.generation == 4 && !has_effect!(target, VolatileStatusEffect::Protect(_)),
+            // no retreat can only raise the user's stats once
+            MoveEffect::NoRetreat => has_effect!(user, VolatileStatusEffect::NoRetreat),
+            // only on the user's first turn out
+            MoveEffect::FakeOut => user.volatile_status.turns_active > 0,
+            MoveEffect::Stockpile => has_effect!(user, VolatileStatusEffect::Stockpile(3)),
+            MoveEffect::Captivate => !user.gender.is_opposite(target.gender),
+            MoveEffect::DreamEater => !matches!(target.non_volatile_status, Some(NonVolatileStatus::Sleep(_))),
+            _ => false
+        }
+    }
+
+    fn roll_move_effect(&mut self, used_move: &Move, using_side: &mut Side, other_side: &mut Side, conditions: &mut Conditions, damage: u16, hit_substitute: bool) {
+
+        self.apply_move_meta(used_move, using_side, other_side, conditions, damage, hit_substitute);
+
+        let mut do_effect = true;
+
+        if let Some(chance) = used_move.effect_chance {
+            do_effect = self.rng.random_ratio(chance as u32, 100);
+        }
+
+        if do_effect {
+            self.apply_effect_after_use(used_move.effect, using_side, other_side, conditions, damage);
+        }
+    }
+
+    fn roll_meta_chance(&mut self, chance: u8) -> bool {
+        chance == 0 || self.rng.random_ratio(chance.min(100) as u32, 100)
+    }
+
+    // drain, healing, ailments, flinching and stat changes all come from move_meta
+    fn apply_move_meta(&mut self, used_move: &Move, user_side: &mut Side, target_side: &mut Side, conditions: &Conditions, move_damage: u16, hit_substitute: bool) {
+
+        let meta = &used_move.meta;
+
+        let bypasses_screens = self.bypasses_screens(user_side.get_active(), conditions);
+        let safeguarded = target_side.effects.is_active(SideCondition::Safeguard) && !bypasses_screens;
+        let misted = target_side.effects.is_active(SideCondition::Mist) && !bypasses_screens;
+
+        let using_mon = user_side.get_active_mut();
+        let target_mon = target_side.get_active_mut();
+
+        if meta.drain > 0 && move_damage > 0 {
+
+            let mut drained = ((move_damage as u32 * meta.drain as u32) / 100).max(1) as u16;
+
+            if using_mon.item == Some(Item::BigRoot) {
+                drained = (drained * 13) / 10;
+            }
+
+            if self.get_ability(target_mon, conditions) == Ability::LiquidOoze {
+                self.deal_residual_damage(using_mon, drained, DamageCause::Ability(Ability::LiquidOoze), conditions);
+            }
+            else {
+                self.heal(using_mon, drained);
+            }
+        }
+        else if meta.drain < 0 && move_damage > 0 && using_mon.hp > 0 {
+
+            let ability = self.get_ability(using_mon, conditions);
+
+            if ability != Ability::RockHead && ability != Ability::MagicGuard {
+                let recoil = ((move_damage as u32 * meta.drain.unsigned_abs() as u32) / 100).max(1) as u16;
+                self.recoil(using_mon, recoil);
+            }
+        }
+
+        // negative healing is struggle and clangorous soul which have their own arms
+        if meta.category == MoveMetaCategory::Heal && meta.healing > 0 && !matches!(used_move.effect, MoveEffect::Moonlight | MoveEffect::Swallow) {
+
+            let healed_mon = if used_move.targets_user() {&mut *using_mon} else {&mut *target_mon};
+            let amount = ((healed_mon.max_hp as u32 * meta.healing as u32) / 100) as u16;
+
+            if healed_mon.hp == healed_mon.max_hp {
+                self.emit(BattleEvent::Failed);
+            }
+            else {
+                self.heal(healed_mon, amount);
+            }
+        }
+
+        // a substitute soaks up everything aimed at the target
+        if hit_substitute {
+            if meta.category == MoveMetaCategory::DamageRaise && self.roll_meta_chance(meta.stat_chance) {
+                for &(stat, stages) in &meta.stat_changes {
+                    self.change_stat(using_mon, stat, stages);
+                }
+            }
+            return;
+        }
+
+        if meta.ailment != MoveAilment::None && target_mon.hp > 0 && self.roll_meta_chance(meta.ailment_chance) {
+            self.apply_move_ailment(used_move, using_mon, target_mon, safeguarded, conditions);
+        }
+
+        if meta.flinch_chance > 0 && self.rng.random_ratio(meta.flinch_chance as u32, 100) {
+            self.try_flinch(target_mon, conditions);
+        }
+
+        if !meta.stat_changes.is_empty() && self.roll_meta_chance(meta.stat_chance) {
+
+            // unique moves like shell smash and defog list their changes too, the ones aimed at the whole field are left to their effect
+            let affects_user = match meta.category {
+                MoveMetaCategory::DamageRaise => Some(true),
+                MoveMetaCategory::NetGoodStats | MoveMetaCategory::Unique if used_move.targets_user() => Some(true),
+                _ if used_move.targets_opponent() => Some(false),
+                _ => None
+            };
+
+            for &(stat, stages) in &meta.stat_changes {
+                match affects_user {
+                    Some(true) => self.change_stat(using_mon, stat, stages),
+                    Some(false) if target_mon.hp > 0 => self.change_target_stat(target_mon, misted, stat, stages),
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    fn apply_move_ailment(&mut self, used_move: &Move, using_mon: &mut Pokemon, target_mon: &mut Pokemon, safeguarded: bool, conditions: &Conditions) {
+
+        let meta = &used_move.meta;
+
+        match meta.ailment {
+            MoveAilment::Paralysis => self.try_inflict_status(target_mon, safeguarded, NonVolatileStatus::Paralysis),
+            MoveAilment::Sleep => if self.is_uproar(using_mon, target_mon) {
+                if used_move.class == MoveClass::Status {
+                    self.emit(BattleEvent::Failed);
+                }
+            }
+            else {
+                let turns = self.roll_sleep_turns();
+                self.try_inflict_status(target_mon, safeguarded, NonVolatileStatus::Sleep(turns));
+            }
+            MoveAilment::Freeze => self.try_inflict_status(target_mon, safeguarded, NonVolatileStatus::Freeze),
+            MoveAilment::Burn => self.try_inflict_status(target_mon, safeguarded, NonVolatileStatus::Burn),
+            MoveAilment::Poison => if matches!(used_move.effect, MoveEffect::BadlyPoison | MoveEffect::BadlyPoisonChance) {
+                self.try_inflict_status(target_mon, safeguarded, NonVolatileStatus::BadlyPoison(1));
+            }
+            else {
+                self.try_inflict_status(target_mon, safeguarded, NonVolatileStatus::Poison);
+            }
+            MoveAilment::Confusion => if safeguarded {
+                self.emit(BattleEvent::SideConditionBlocked {pokemon: target_mon.get_event_pokemon(), condition: SideCondition::Safeguard});
+            }
+            else if !has_effect!(target_mon, VolatileStatusEffect::Confusion(_)) {
+                let turns = self.rng.random_range(meta.min_turns.unwrap_or(2)..=meta.max_turns.unwrap_or(5));
+                self.add_volatile(target_mon, VolatileStatusEffect::Confusion(turns));
+            }
+            else if used_move.class == MoveClass::Status {
+                self.emit(BattleEvent::Failed);
+            }
+            MoveAilment::Infatuation => {
+                if !self.try_infatuate(target_mon, using_mon, conditions) && used_move.class == MoveClass::Status {
+                    self.emit(BattleEvent::Failed);
+                }
+            }
+            MoveAilment::Trap if !has_effect!(target_mon, VolatileStatusEffect::Bind {..}) => {
+
+                let health_fraction = if using_mon.item == Some(Item::BindingBand) {
+                    6
+                }
+                else if self.generation >= 6 {
+                    8
+                }
+                else {
+                    16
+                };
+
+                let max_turns = meta.max_turns.unwrap_or(5);
+                let turns = if using_mon.item == Some(Item::GripClaw) {
+                    max_turns
+                }
+                else {
+                    self.rng.random_range(meta.min_turns.unwrap_or(2)..=max_turns)
+                };
+
+                self.add_volatile(target_mon, VolatileStatusEffect::Bind {health_fraction, turns});
+
+            }
+            // the rest are unique enough to be handled by their effect
+            _ => {}
+        }
+    }
+
+    fn try_flinch(&mut self, pokemon: &mut Pokemon, conditions: &Conditions) {
+        if self.get_ability(pokemon, conditions) != Ability::InnerFocus && pokemon.hp > 0 {
+            pokemon.volatile_status.add(VolatileStatusEffect::Flinch);
+        }
+    }
+
+    fn roll_hit_count(&mut self, used_move: &Move, attacker: &Pokemon, conditions: &Conditions) -> u8 {
+
+        // not in move_meta yet
+        let (min_hits, max_hits) = if used_move.id == MOVE_POPULATION_BOMB {
+            (10, 10)
+        }
+        else {
+            (used_move.min_hits, used_move.max_hits)
+        };
+
+        if attacker.item == Some(Item::LoadedDice) {
+            if max_hits == 10 {
+                return self.rng.random_range(4..=10);
+            }
+            if (min_hits, max_hits) == (2, 5) {
+                return self.rng.random_range(4..=5);
+            }
+        }
+
+        if min_hits == max_hits {
+            return min_hits;
+        }
+
+        if self.get_ability(attacker, conditions) == Ability::SkillLink {
+            return max_hits;
+        }
+
+        if (min_hits, max_hits) == (2, 5) {
+            return if self.generation >= 5 {
+                [2, 2, 2, 2, 2, 2, 2, 3, 3, 3, 3, 3, 3, 3, 4, 4, 4, 5, 5, 5][self.rng.random_range(0..20)]
+            }
+            else {
+                [2, 2, 2, 3, 3, 3, 4, 5][self.rng.random_range(0..8)]
+            };
+        }
+
+        self.rng.random_range(min_hits..=max_hits)
+
+    }
+
+    fn checks_accuracy_each_hit(&self, used_move: &Move, attacker: &Pokemon, conditions: &Conditions) -> bool {
+
+        if used_move.effect != MoveEffect::Hit3TimesIncreasing && !matches!(used_move.id, MOVE_TRIPLE_AXEL | MOVE_POPULATION_BOMB) {
+            return false;
+        }
+
+        self.get_ability(attacker, conditions) != Ability::SkillLink && attacker.item != Some(Item::LoadedDice)
+
+    }
+
+    fn rapid_spin(&mut self, side: &mut Side) {
+
+        let mon = side.get_active_mut();
+
+        if mon.hp == 0 {
+            return;
+        }
+
+        remove_effect!(mon, VolatileStatusEffect::Bind {..} | VolatileStatusEffect::Seed);
+
+        if side.effects.has_hazards() {
+            side.effects.clear_hazards();
+            self.emit(BattleEvent::HazardsCleared {pokemon: side.get_active().get_event_pokemon()});
+        }
+    }
+
+    fn deduct_pp(&mut self, used_move: &Move, user: &mut Pokemon, target: &Pokemon, conditions: &Conditions) {
+
+        let amount = if self.generation >= 3 && !used_move.targets_user() && self.get_ability(target, conditions) == Ability::Pressure {
+            2
+        }
+        else {
+            1
+        };
+
+        let Some(slot) = user.get_move_slot_mut(used_move.id)
+        else {
+            return;
+        };
+
+        slot.pp = slot.pp.saturating_sub(amount);
+
+        if slot.pp == 0 {
+            self.try_restore_pp_with_berry(user, used_move.id);
+        }
+    }
+
+    fn try_restore_pp_with_berry(&mut self, pokemon: &mut Pokemon, move_id: ID) {
+
+        if pokemon.item != Some(Item::LeppaBerry) {
+            return;
+        }
+
+        let Some(slot) = pokemon.get_move_slot_mut(move_id)
+        else {
+            return;
+        };
+
+        slot.pp = slot.max_pp.min(10);
+        pokemon.item = None;
+
+        self.emit(BattleEvent::ItemActivated {pokemon: pokemon.get_event_pokemon(), item: Item::LeppaBerry});
+
+    }
+
+    fn recoil(&mut self, pokemon: &mut Pokemon, damage: u16) {
+
+        pokemon.deal_damage(damage);
+        self.emit(BattleEvent::Recoil {pokemon: pokemon.get_event_pokemon(), amount: damage});
+
+        if pokemon.hp == 0 {
+            self.emit(BattleEvent::Faint {pokemon: pokemon.get_event_pokemon()});
+        }
+    }
+
+    fn set_weather(&mut self, conditions: &mut Conditions, weather: Weather, held_item: Option<Item>, from_ability: bool) {
+        
+        if let Some((current_weather, _)) = conditions.weather {
+            if weather == current_weather || current_weather.is_strong() && !weather.is_strong() {
+                return;
+            }
+        }
+
+        let duration = if self.generation <= 5 && from_ability || weather.is_strong() {
+            Weather::PERMANENT
+        }
+        else if held_item.is_some_and(|item| matches!((weather, item), 
+            (Weather::Rain, Item::DampRock) |
+            (Weather::Sun, Item::HeatRock) |
+            (Weather::Hail, Item::IcyRock) |
+            (Weather::Sandstorm, Item::SmoothRock)
+        )) {
+            8
+        }
+        else {
+            5
+        };
+
+        conditions.weather = Some((weather, duration));
+        self.emit(BattleEvent::WeatherSet {weather});
+
+    }
+
+    fn set_terrain(&mut self, conditions: &mut Conditions, terrain: Terrain, held_item: Option<Item>) {
+
+        let duration = if held_item == Some(Item::TerrainExtender) {
+            8
+        }
+        else {
+            5
+        };
+
+        conditions.terrain = Some((terrain, duration));
+        self.emit(BattleEvent::TerrainSet {terrain});
+
+    }
+
+    fn can_be_traced(&self, ability: Ability) -> bool {
+        !matches!(ability,
+            Ability::None | Ability::Trace | Ability::Multitype | Ability::Illusion | Ability::FlowerGift | Ability::Imposter |
+            Ability::StanceChange | Ability::ZenMode | Ability::PowerOfAlchemy | Ability::Receiver | Ability::RksSystem |
+            Ability::Schooling | Ability::Comatose | Ability::ShieldsDown | Ability::Disguise | Ability::BattleBond |
+            Ability::PowerConstruct | Ability::IceFace | Ability::GulpMissile | Ability::NeutralizingGas | Ability::HungerSwitch |
+            Ability::AsOneGlastrier | Ability::AsOneSpectrier | Ability::ZeroToHero | Ability::Commander | Ability::Protosynthesis |
+            Ability::QuarkDrive | Ability::TeraShift | Ability::PoisonPuppeteer
+        ) && !(self.generation <= 3 && ability == Ability::Forecast)
+    }
+
+    // the move forewarn treats as the most threatening
+    fn get_forewarn_power(&self, used_move: &Move) -> u8 {
+        match used_move.effect {
+            MoveEffect::Ohko => 150,
+            MoveEffect::Counter | MoveEffect::MirrorCoat | MoveEffect::MetalBurst => 120,
+            _ if used_move.class == MoveClass::Status => 0,
+            _ => used_move.power.unwrap_or(80)
+        }
+    }
+
+    // the highest stat, ties go to the earlier stat
+    fn get_paradox_stat(&self, pokemon: &Pokemon) -> Stat {
+
+        let mut best = (Stat::Attack, 0);
+
+        for stat in [Stat::Attack, Stat::Defense, Stat::SpecialAttack, Stat::SpecialDefense, Stat::Speed] {
+
+            let value = self.get_staged_stat(pokemon, stat, pokemon.get_stat_stage(stat));
+
+            if value > best.1 {
+                best = (stat, value);
+            }
+        }
+
+        best.0
+
+    }
+
+    fn apply_paradox_boost(&self, value: &mut u16, pokemon: &Pokemon, stat: Stat, conditions: &Conditions) {
+
+        if !matches!(self.get_ability(pokemon, conditions), Ability::Protosynthesis | Ability::QuarkDrive) {
+            return;
+        }
+
+        find_effect!(pokemon, VolatileStatusEffect::ParadoxBoost(boosted) => {
+            if *boosted == stat {
+                let modifier = if stat == Stat::Speed {6144} else {5325};
+                *value = (*value as u32 * modifier / 4096) as u16;
+            }
+        });
+    }
+
+    fn activate_ability(&mut self, side: &mut Side, other_side: &mut Side, conditions: &mut Conditions) {
+
+        let mon = side.get_active_mut();
+        let other_mon = other_side.get_active_mut();
+        let ability = self.get_ability(mon, conditions);
+
+        match ability {
+            // the effects themselves are handled wherever the ability matters
+            Ability::AirLock | Ability::CloudNine | Ability::Pressure if self.generation >= 5 => {
+                self.emit(BattleEvent::AbilityActivated {pokemon: mon.get_event_pokemon(), ability});
+            }
+            Ability::MoldBreaker | Ability::Teravolt | Ability::Turboblaze | Ability::NeutralizingGas |
+            Ability::Unnerve | Ability::AsOneGlastrier | Ability::AsOneSpectrier => {
+                self.emit(BattleEvent::AbilityActivated {pokemon: mon.get_event_pokemon(), ability});
+            }
+            Ability::Anticipation => {
+
+                let threatened = other_mon.hp > 0 && other_mon.moves.iter().flatten().any(|slot| {
+                    let move_data = self.data_handler.get_move_in_generation(slot.id, self.generation);
+                    move_data.class != MoveClass::Status && self.get_type_effectiveness(move_data, mon) > 100 ||
+                        move_data.effect == MoveEffect::Ohko ||
+                        self.generation == 4 && move_data.effect == MoveEffect::FaintUser
+                });
+
+                if threatened {
+                    self.emit(BattleEvent::AbilityActivated {pokemon: mon.get_event_pokemon(), ability});
+                }
+            }
+            // only matter in double battles
+            Ability::CuriousMedicine | Ability::Hospitality => {}
+            Ability::DauntlessShield | Ability::IntrepidSword => if !(self.generation >= 9 && mon.ability_triggered) {
+
+                mon.ability_triggered = true;
+                self.emit(BattleEvent::AbilityActivated {pokemon: mon.get_event_pokemon(), ability});
+
+                let stat = if ability == Ability::DauntlessShield {Stat::Defense} else {Stat::Attack};
+                self.change_stat(mon, stat, 1);
+
+            }
+            Ability::DeltaStream => self.set_weather(conditions, Weather::StrongWind, mon.item, true),
+            Ability::DesolateLand => self.set_weather(conditions, Weather::ExtremeSun, mon.item, true),
+            // compares the target's defenses with only stat stages applied
+            Ability::Download => if other_mon.hp > 0 {
+
+                let defense = self.get_staged_stat(other_mon, Stat::Defense, other_mon.get_stat_stage(Stat::Defense));
+                let special_defense = self.get_staged_stat(other_mon, Stat::SpecialDefense, other_mon.get_stat_stage(Stat::SpecialDefense));
+
+                self.emit(BattleEvent::AbilityActivated {pokemon: mon.get_event_pokemon(), ability});
+
+                if defense < special_defense {
+                    self.change_stat(mon, Stat::Attack, 1);
+                }
+                else {
+                    self.change_stat(mon, Stat::SpecialAttack, 1);
+                }
+            }
+            Ability::Drizzle => self.set_weather(conditions, Weather::Rain, mon.item, true),
+            Ability::Drought | Ability::OrichalcumPulse => self.set_weather(conditions, Weather::Sun, mon.item, true),
+            Ability::ElectricSurge | Ability::HadronEngine => self.set_terrain(conditions, Terrain::Electric, mon.item),
+            Ability::Forewarn => if other_mon.hp > 0 {
+
+                let mut strongest = Vec::new();
+                let mut max_power = 0;
+
+                for slot in other_mon.moves.iter().flatten() {
+
+                    let move_data = self.data_handler.get_move_in_generation(slot.id, self.generation);
+                    let power = self.get_forewarn_power(move_data);
+
+                    if power > max_power {
+                        strongest.clear();
+                        max_power = power;
+                    }
+
+                    if power == max_power {
+                        strongest.push(move_data.name.clone());
+                    }
+                }
+
+                if !strongest.is_empty() {
+                    let move_name = strongest.swap_remove(self.rng.random_range(0..strongest.len()));
+                    self.emit(BattleEvent::MoveForewarned {pokemon: mon.get_event_pokemon(), move_name});
+                }
+            }
+            Ability::Frisk => if let Some(item) = other_mon.item {
+                self.emit(BattleEvent::ItemFrisked {pokemon: mon.get_event_pokemon(), target: other_mon.get_event_pokemon(), item});
+            }
+            Ability::GrassySurge => self.set_terrain(conditions, Terrain::Grassy, mon.item),
+            // transform isn't supported yet
+            Ability::Imposter => {}
+            Ability::Intimidate => if other_mon.hp > 0 {
+
+                self.emit(BattleEvent::AbilityActivated {pokemon: mon.get_event_pokemon(), ability});
+
+                let other_ability = self.get_ability(other_mon, conditions);
+
+                if has_effect!(other_mon, VolatileStatusEffect::Substitute(_)) {
+                    self.emit(BattleEvent::Failed);
+                }
+                else if matches!(other_ability, Ability::ClearBody | Ability::WhiteSmoke | Ability::HyperCutter | Ability::FullMetalBody) ||
+                    self.generation >= 8 && matches!(other_ability, Ability::InnerFocus | Ability::Oblivious | Ability::OwnTempo | Ability::Scrappy)
+                {
+                    self.emit(BattleEvent::AbilityActivated {pokemon: other_mon.get_event_pokemon(), ability: other_ability});
+                }
+                else if other_ability == Ability::GuardDog {
+                    self.change_stat(other_mon, Stat::Attack, 1);
+                }
+                else {
+
+                    self.change_stat(other_mon, Stat::Attack, -1);
+
+                    if self.generation >= 8 && other_ability == Ability::Rattled {
+                        self.change_stat(other_mon, Stat::Speed, 1);
+                    }
+                }
+            }
+            Ability::MistySurge => self.set_terrain(conditions, Terrain::Misty, mon.item),
+            Ability::PrimordialSea => self.set_weather(conditions, Weather::HeavyRain, mon.item, true),
+            Ability::Protosynthesis | Ability::QuarkDrive => {
+
+                let active = if ability == Ability::Protosynthesis {conditions.is_sunny()} else {conditions.is_terrain(Terrain::Electric)};
+
+                if !active && mon.item == Some(Item::BoosterEnergy) {
+                    self.emit(BattleEvent::ItemActivated {pokemon: mon.get_event_pokemon(), item: Item::BoosterEnergy});
+                    mon.item = None;
+                }
+                else if !active {
+                    return;
+                }
+
+                let stat = self.get_paradox_stat(mon);
+                mon.volatile_status.add(VolatileStatusEffect::ParadoxBoost(stat));
+                self.emit(BattleEvent::AbilityActivated {pokemon: mon.get_event_pokemon(), ability});
+
+            }
+            Ability::PsychicSurge => self.set_terrain(conditions, Terrain::Psychic, mon.item),
+            Ability::SandStream => self.set_weather(conditions, Weather::Sandstorm, mon.item, true),
+            // form changes aren't supported yet
+            Ability::Schooling | Ability::ShieldsDown => {}
+            Ability::ScreenCleaner => {
+
+                let mut cleaned = false;
+
+                for side in [&mut *side, &mut *other_side] {
+                    for condition in [SideCondition::Reflect, SideCondition::LightScreen, SideCondition::AuroraVeil] {
+                        if side.effects.is_active(condition) {
+                            side.effects.set_turns(condition, 0);
+                            cleaned = true;
+                        }
+                    }
+                }
+
+                if cleaned {
+                    self.emit(BattleEvent::AbilityActivated {pokemon: side.get_active().get_event_pokemon(), ability});
+                }
+            }
+            Ability::SnowWarning => self.set_weather(conditions, Weather::Hail, mon.item, true),
+            Ability::SupersweetSyrup => if !mon.ability_triggered {
+                mon.ability_triggered = true;
+                self.emit(BattleEvent::AbilityActivated {pokemon: mon.get_event_pokemon(), ability});
+                self.change_stat(other_mon, Stat::Evasion, -1);
+            }
+            Ability::SupremeOverlord => {
+
+                let fainted = side.team.iter().filter(|mon| mon.hp == 0).count().min(5) as u8;
+
+                if fainted > 0 {
+                    let mon = side.get_active_mut();
+                    mon.volatile_status.add(VolatileStatusEffect::SupremeOverlord(fainted));
+                    self.emit(BattleEvent::AbilityActivated {pokemon: mon.get_event_pokemon(), ability});
+                }
+            }
+            Ability::Trace => {
+
+                let traced_ability = self.get_ability(other_mon, conditions);
+
+                if other_mon.hp > 0 && self.can_be_traced(traced_ability) {
+                    self.change_ability(mon, traced_ability);
+                    self.activate_ability(side, other_side, conditions);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn apply_effect_after_use(&mut self, effect: MoveEffect, user_side: &mut Side, target_side: &mut Side, conditions: &mut Conditions, move_damage: u16) {
+
+        let bypasses_screens = self.bypasses_screens(user_side.get_active(), conditions);
+
+        // the user can't be protected from itself
+        let safeguarded = target_side.effects.is_active(SideCondition::Safeguard) && !bypasses_screens;
+        let misted = target_side.effects.is_active(SideCondition::Mist) && !bypasses_screens;
+
+        let using_mon = user_side.get_active_mut();
+        let target_mon = target_side.get_active_mut();
+
+        use MoveEffect as ME;
+        match effect {
+            ME::FaintUser => using_mon.hp = 0,
+            // the drain comes from move_meta, use_move makes it fail on targets that are awake
+            ME::DreamEater => {}
+            ME::UseTargetsLastMove => todo!(),
+            // handled by check_hit
+            ME::NeverMiss => {}
+            ME::ResetTargetStats => self.reset_stats(target_mon),
+            // handled by use_move
+            ME::Bide => {}
+            // trainers can't be forced to switch in gen 1
+            ME::ForceSwitch => if self.generation >= 2 && self.can_force_out(target_side, conditions) {
+                target_side.pending_switch = Some(SwitchOut::Forced);
+            }
+            else {
+                self.emit(BattleEvent::Failed);
+            }
+            // handled by the hit loop
+            ME::Hit2To5Times | ME::HitTwice | ME::Hit3TimesIncreasing => {}
+            ME::Conversion => todo!(),
+            ME::ScatterMoney => todo!(),
+            ME::LightScreen => {
+                let turns = self.get_screen_turns(using_mon);
+                self.start_side_condition(user_side, SideCondition::LightScreen, turns);
+            }
+            ME::TriAttack => {
+                let status = [NonVolatileStatus::Burn, NonVolatileStatus::Freeze, NonVolatileStatus::Paralysis][self.rng.random_range(0..3)];
+                self.try_inflict_status(target_mon, safeguarded, status);
+            }
+            ME::Rest => {
+
+                let ability = self.get_ability(using_mon, conditions);
+
+                if using_mon.hp == using_mon.max_hp || matches!(using_mon.non_volatile_status, Some(NonVolatileStatus::Sleep(_))) || matches!(ability, Ability::Insomnia | Ability::VitalSpirit) || self.is_uproar(using_mon, target_mon) {
+                    self.emit(BattleEvent::Failed);
+                }
+                else {
+                    using_mon.non_volatile_status = None;
+                    self.try_apply_status(using_mon, NonVolatileStatus::Sleep(3));
+                    self.heal(using_mon, using_mon.max_hp);
+                }
+            }
+            // handled by use_move
+            ME::RazorWind => {}
+            // handled by get_fixed_damage
+            ME::SuperFang => {}
+            // handled by get_fixed_damage
+            ME::DragonRage => {}
+            // handled when rolling for a crit
+            ME::IncreasedCrit | ME::AlwaysCrits => {}
+            ME::RecoilOnMiss => todo!(),
+            ME::ProtectStats => self.start_side_condition(user_side, SideCondition::Mist, 5),
+            ME::FocusEnergy => {
+                if has_effect!(using_mon, VolatileStatusEffect::GettingPumped) {
+                    self.emit(BattleEvent::Failed);
+                }
+                else {
+                    self.add_volatile(using_mon, VolatileStatusEffect::GettingPumped);
+                }
+            }
+            ME::Transform => todo!(),
+            ME::Reflect => {
+                let turns = self.get_screen_turns(using_mon);
+                self.start_side_condition(user_side, SideCondition::Reflect, turns);
+            }
+            // handled by use_move
+            ME::ChargeAndFlinchChance => {}
+            ME::Substitute => self.create_substitute(using_mon),
+            // gen 1 skips the recharge turn after a ko
+            ME::Recharge => if self.generation >= 2 || target_mon.hp > 0 {
+                using_mon.volatile_status.add(VolatileStatusEffect::Recharge);
+            }
+            ME::RaiseAttack1IfHit => todo!(),
+            ME::UseTargetsLastMove2 => todo!(),
+            ME::Metronome => todo!(),
+            ME::Seed => {
+                if self.pokemon_has_type(target_mon, Type::Grass) || has_effect!(target_mon, VolatileStatusEffect::Seed) {
+                    self.emit(BattleEvent::Failed);
+                }
+                else {
+                    self.add_volatile(target_mon, VolatileStatusEffect::Seed);
+                }
+            }
+            // does nothing
+            ME::Splash => {}
+            ME::Disable => {
+
+                // gen 1 picks a random move instead of the last one used
+                let disabled = if self.generation == 1 {
+                    let usable = target_mon.moves.iter().flatten().filter(|slot| slot.pp > 0).map(|slot| slot.id).collect::<Vec<_>>();
+                    if usable.is_empty() {None} else {Some(usable[self.rng.random_range(0..usable.len())])}
+                }
+                else {
+                    target_mon.volatile_status.last_move.filter(|&move_id| target_mon.get_move_slot(move_id).is_some_and(|slot| slot.pp > 0))
+                };
+
+                match disabled {
+                    Some(move_id) if !has_effect!(target_mon, VolatileStatusEffect::Disable(..)) => {
+                        let turns = match self.generation {
+                            1 => self.rng.random_range(1..=8),
+                            2 => self.rng.random_range(1..=7),
+                            3 => self.rng.random_range(2..=5),
+                            4 => self.rng.random_range(4..=7),
+                            _ => 4
+                        };
+                        self.add_volatile(target_mon, VolatileStatusEffect::Disable(move_id, turns));
+                    }
+                    _ => self.emit(BattleEvent::Failed)
+                }
+            }
+            // handled by get_fixed_damage
+            ME::DamageByLevel => {}
+            // handled by get_fixed_damage
+            ME::RangeDamageByLevel => {}
+            ME::Counter => todo!(),
+            ME::Encore => {
+
+                let encored = target_mon.volatile_status.last_move.filter(|&move_id| {
+                    target_mon.get_move_slot(move_id).is_some_and(|slot| slot.pp > 0) && !matches!(self.data_handler.get_move_in_generation(move_id, self.generation).effect,
+                        ME::Encore | ME::Transform | ME::UseTargetsLastMove | ME::UseTargetsLastMove2 | ME::Sketch
+                    )
+                });
+
+                match encored {
+                    Some(move_id) if !has_effect!(target_mon, VolatileStatusEffect::Encore(..)) => {
+                        let turns = match self.generation {
+                            ..=3 => self.rng.random_range(2..=6),
+                            4 => self.rng.random_range(4..=8),
+                            _ => 3
+                        };
+                        self.add_volatile(target_mon, VolatileStatusEffect::Encore(move_id, turns));
+                    }
+                    _ => self.emit(BattleEvent::Failed)
+                }
+            }
+            ME::PainSplit => {
+                let total = using_mon.hp + target_mon.hp;
+                using_mon.hp = using_mon.max_hp.min(total / 2);
+                target_mon.hp = target_mon.max_hp.min(total / 2);
+            },
+            ME::Transform2 => todo!(),
+            ME::NextMoveHits => todo!(),
+            ME::Sketch => todo!(),
+            ME::SleepTalk => todo!(),
+            ME::DestinyBond => todo!(),
+            // handled by get_move_power
+            ME::MorePowerWhenLessHP => {}
+            ME::Spite => {
+
+                let amount = if self.generation >= 4 {
+                    4
+                }
+                else {
+                    self.rng.random_range(2..=5)
+                };
+
+                let last_move = target_mon.volatile_status.last_move;
+
+                match last_move.and_then(|move_id| target_mon.get_move_slot_mut(move_id)) {
+                    Some(slot) if slot.pp > 0 => {
+
+                        let amount = amount.min(slot.pp);
+                        slot.pp -= amount;
+
+                        let move_id = slot.id;
+                        let pp_left = slot.pp;
+
+                        self.emit(BattleEvent::PPReduced {pokemon: target_mon.get_event_pokemon(), move_id, amount});
+
+                        if pp_left == 0 && self.generation >= 4 {
+                            self.try_restore_pp_with_berry(target_mon, move_id);
+                        }
+                    }
+                    _ => self.emit(BattleEvent::Failed)
+                }
+            }
+            ME::CurePartyStatus => todo!(),
+            // just has priority
+            ME::NoOtherEffect2 => {}
+            ME::StealItem => todo!(),
+            ME::PreventEscape => if has_effect!(target_mon, VolatileStatusEffect::Trapped) {
+                self.emit(BattleEvent::Failed);
+            }
+            else {
+                self.add_volatile(target_mon, VolatileStatusEffect::Trapped);
+            }
+            ME::ThousandWaves | ME::SpiritShackle => if target_mon.hp > 0 && !has_effect!(target_mon, VolatileStatusEffect::Trapped) {
+                self.add_volatile(target_mon, VolatileStatusEffect::Trapped);
+            }
+            ME::JawLock => if using_mon.hp > 0 && target_mon.hp > 0 && !has_effect!(using_mon, VolatileStatusEffect::JawLock) && !has_effect!(target_mon, VolatileStatusEffect::JawLock) {
+                self.add_volatile(using_mon, VolatileStatusEffect::JawLock);
+                self.add_volatile(target_mon, VolatileStatusEffect::JawLock);
+            }
+            // the stat boosts come from the move's meta
+            ME::NoRetreat => using_mon.volatile_status.add(VolatileStatusEffect::NoRetreat),
+            ME::Octolock => if has_effect!(target_mon, VolatileStatusEffect::Octolock) {
+                self.emit(BattleEvent::Failed);
+            }
+            else {
+                self.add_volatile(target_mon, VolatileStatusEffect::Octolock);
+            }
+            ME::Nightmare => {
+                if matches!(target_mon.non_volatile_status, Some(NonVolatileStatus::Sleep(_))) && !has_effect!(target_mon, VolatileStatusEffect::Nightmare) {
+                    self.add_volatile(target_mon, VolatileStatusEffect::Nightmare);
+                }
+                else {
+                    self.emit(BattleEvent::Failed);
+                }
+            }
+            // the evasion boost comes from move_meta
+            ME::Minimize => if !has_effect!(using_mon, VolatileStatusEffect::Minimize) {
+                using_mon.volatile_status.add(VolatileStatusEffect::Minimize);
+            }
+            ME::Curse => {
+                if self.pokemon_has_type(using_mon, Type::Ghost) {
+                    if has_effect!(target_mon, VolatileStatusEffect::Curse) {
+                        self.emit(BattleEvent::Failed);
+                    }
+                    else {
+
+                        // paid directly so magic guard doesn't stop it
+                        let cost = (using_mon.max_hp / 2).max(1);
+                        using_mon.deal_damage(cost);
+                        self.emit(BattleEvent::IndirectDamage {pokemon: using_mon.get_event_pokemon(), amount: cost, cause: DamageCause::Curse});
+
+                        if using_mon.hp == 0 {
+                            self.emit(BattleEvent::Faint {pokemon: using_mon.get_event_pokemon()});
+                        }
+
+                        self.add_volatile(target_mon, VolatileStatusEffect::Curse);
+                    }
+                }
+                else {
+                    self.change_stat(using_mon, Stat::Attack, 1);
+                    self.change_stat(using_mon, Stat::Defense, 1);
+                    self.change_stat(using_mon, Stat::Speed, -1);
+                }
+            }
+            ME::Protect => self.try_protect(using_mon, VolatileStatusEffect::Protect(ProtectKind::Protect)),
+            ME::Spikes => target_side.effects.add_spikes(),
+            ME::Identify => self.add_volatile(target_mon, VolatileStatusEffect::Identified),
+            ME::PerishSong => {
+                for mon in [using_mon, target_mon] {
+                    if !has_effect!(mon, VolatileStatusEffect::PerishSong(_)) && self.get_ability(mon, conditions) != Ability::Soundproof {
+                        self.add_volatile(mon, VolatileStatusEffect::PerishSong(3));
+                    }
+                }
+            }
+            ME::Sandstorm => self.set_weather(conditions, Weather::Sandstorm, using_mon.item, false),
+            ME::Endure => self.try_protect(using_mon, VolatileStatusEffect::Endure),
+            // handled by update_locked_move
+            ME::Rollout => {}
+            ME::FuryCutter => todo!(),
+            // handled by get_move_power
+            ME::Return => {}
+            ME::Present => todo!(),
+            // handled by get_move_power
+            ME::Frustration => {}
+            ME::Safeguard => self.start_side_condition(user_side, SideCondition::Safeguard, 5),
+            ME::Magnitude => todo!(),
+            ME::BatonPass => if user_side.has_healthy_reserve() {
+                user_side.pending_switch = Some(SwitchOut::BatonPass);
+            }
+            else {
+                self.emit(BattleEvent::Failed);
+            }
+            // the hit on a switching target isn't modelled yet
+            ME::Pursuit => {}
+            ME::RapidSpin => {
+
+                self.rapid_spin(user_side);
+
+                if self.generation >= 8 {
+                    self.change_stat(user_side.get_active_mut(), Stat::Speed, 1);
+                }
+            }
+            // handled by get_fixed_damage
+            ME::SonicBoom => {}
+            ME::Moonlight => {
+
+                let (numerator, denominator) = if conditions.is_sunny() {
+                    (2, 3)
+                }
+                else if conditions.get_weather().is_some_and(|weather| weather != Weather::StrongWind) {
+                    (1, 4)
+                }
+                else {
+                    (1, 2)
+                };
+
+                if using_mon.hp == using_mon.max_hp {
+                    self.emit(BattleEvent::Failed);
+                }
+                else {
+                    let amount = (using_mon.max_hp as u32 * numerator / denominator) as u16;
+                    self.heal(using_mon, amount);
+                }
+            }
+            // the type and power come from ivs, which aren't kept after the stats are calculated
+            ME::HiddenPower => {}
+            ME::RainDance => self.set_weather(conditions, Weather::Rain, using_mon.item, false),
+            ME::SunnyDay => self.set_weather(conditions, Weather::Sun, using_mon.item, false),
+            ME::BellyDrum => todo!(),
+            ME::PsychUp => todo!(),
+            ME::MirrorCoat => todo!(),
+            // handled by use_move
+            ME::SkullBash => {}
+            // reaching semi-invulnerable targets is handled by use_move
+            ME::Earthquake => {}
+            ME::Hits2TurnsLater => todo!(),
+            // reaching semi-invulnerable targets is handled by use_move
+            ME::Gust => {}
+            // handled by get_move_power
+            ME::Stomp => {}
+            // handled by use_move
+            ME::Solarbeam => {}
+            // teleport only works outside of trainer battles until gen 8
+            ME::Teleport => if self.generation >= 8 && user_side.has_healthy_reserve() {
+                user_side.pending_switch = Some(SwitchOut::Pivot);
+            }
+            else {
+                self.emit(BattleEvent::Failed);
+            }
+            ME::BeatUp => todo!(),
+            // handled by use_move
+            ME::Fly => {}
+            ME::DefenseCurl => if !has_effect!(using_mon, VolatileStatusEffect::DefenseCurl) {
+                using_mon.volatile_status.add(VolatileStatusEffect::DefenseCurl);
+            }
+            // flinching comes from move_meta, use_move makes it fail after the first turn
+            ME::FakeOut => {}
+            ME::Uproar => {
+                for mon in [using_mon, target_mon] {
+                    if matches!(mon.non_volatile_status, Some(NonVolatileStatus::Sleep(_))) && self.get_ability(mon, conditions) != Ability::Soundproof {
+                        mon.non_volatile_status = None;
+                        self.emit(BattleEvent::StatusCured {pokemon: mon.get_event_pokemon(), status: NonVolatileStatus::Sleep(0)});
+                    }
+                }
+            }
+            // the defense raises come from move_meta, use_move stops it past 3
+            ME::Stockpile => {
+
+                let mut count = 1;
+
+                find_effect!(using_mon, VolatileStatusEffect::Stockpile(previous) => {
+                    count = previous + 1;
+                });
+
+                remove_effect!(using_mon, VolatileStatusEffect::Stockpile(_));
+                self.add_volatile(using_mon, VolatileStatusEffect::Stockpile(count));
+            }
+            ME::SpitUp => todo!(),
+            ME::Swallow => todo!(),
+            ME::Hail => self.set_weather(conditions, Weather::Hail, using_mon.item, false),
+            ME::Torment => if has_effect!(target_mon, VolatileStatusEffect::Torment) {
+                self.emit(BattleEvent::Failed);
+            }
+            else {
+                self.add_volatile(target_mon, VolatileStatusEffect::Torment);
+            }
+            // the stat drops come from move_meta
+            ME::Memento => using_mon.hp = 0,
+            // handled by get_move_power
+            ME::Facade => {}
+            ME::FocusPunch => todo!(),
+            ME::SmellingSalts => todo!(),
+            ME::FollowMe => todo!(),
+            ME::NaturePower => todo!(),
+            // the sp. def raise comes from move_meta
+            ME::Charge => if !has_effect!(using_mon, VolatileStatusEffect::Charge) {
+                self.add_volatile(using_mon, VolatileStatusEffect::Charge);
+            }
+            ME::Taunt => if has_effect!(target_mon, VolatileStatusEffect::Taunt(_)) || self.generation >= 6 && self.get_ability(target_mon, conditions) == Ability::Oblivious {
+                self.emit(BattleEvent::Failed);
+            }
+            else {
+                let turns = match self.generation {
+                    ..=3 => 2,
+                    4 => self.rng.random_range(3..=5),
+                    _ => 3
+                };
+                self.add_volatile(target_mon, VolatileStatusEffect::Taunt(turns));
+            }
+            ME::HelpingHand => todo!(),
+            ME::Trick => todo!(),
+            ME::RolePlay => todo!(),
+            ME::Wish => todo!(),
+            ME::RandomlySwitchOutTarget => todo!(),
+            ME::Ingrain => {
+                if has_effect!(using_mon, VolatileStatusEffect::Ingrain) {
+                    self.emit(BattleEvent::Failed);
+                }
+                else {
+                    self.add_volatile(using_mon, VolatileStatusEffect::Ingrain);
+                }
+            }
+            ME::MagicCoat => todo!(),
+            ME::Recycle => todo!(),
+            ME::DoubleDamageIfHitBeforeAttacking => todo!(),
+            ME::DestroyScreens => {
+                for condition in [SideCondition::Reflect, SideCondition::LightScreen, SideCondition::AuroraVeil] {
+                    if target_side.effects.is_active(condition) {
+                        target_side.effects.set_turns(condition, 0);
+                        self.emit(BattleEvent::SideConditionEnded {pokemon: target_side.get_active().get_event_pokemon(), condition});
+                    }
+                }
+            }
+            ME::Yawn => todo!(),
+            ME::KnockOff => if self.generation >= 3 && move_damage > 0 && self.get_ability(target_mon, conditions) != Ability::StickyHold {
+                if let Some(item) = target_mon.item.take() {
+                    self.emit(BattleEvent::ItemKnockedOff {pokemon: using_mon.get_event_pokemon(), target: target_mon.get_event_pokemon(), item});
+                }
+            }
+            // handled by get_move_power
+            ME::PowerBasedOnUserHP => {}
+            ME::SkillSwap => todo!(),
+            ME::Imprison => if has_effect!(using_mon, VolatileStatusEffect::Imprison) {
+                self.emit(BattleEvent::Failed);
+            }
+            else {
+                self.add_volatile(using_mon, VolatileStatusEffect::Imprison);
+            }
+            ME::HealUserStatus => todo!(),
+            ME::Grudge => todo!(),
+            ME::Snatch => todo!(),
+            // handled by get_move_power
+            ME::PowerBasedOnWeight => {}
+            ME::SecretPower => todo!(),
+            ME::MudSport => todo!(),
+            // handled by get_move_power, the type change isn't modelled yet
+            ME::WeatherBall => {}
+            // reaching semi-invulnerable targets is handled by use_move
+            ME::HitBounceFly => {}
+            ME::WaterSport => todo!(),
+            ME::Camouflage => todo!(),
+            ME::Roost => todo!(),
+            ME::Gravity => if conditions.is_gravity() {
+                self.emit(BattleEvent::Failed);
+            }
+            else {
+
+                conditions.gravity_turns = 5;
+                self.emit(BattleEvent::GravityStarted);
+
+                remove_effect!(using_mon, VolatileStatusEffect::MagnetRise | VolatileStatusEffect::Telekinesis);
+                remove_effect!(target_mon, VolatileStatusEffect::MagnetRise | VolatileStatusEffect::Telekinesis);
+
+                // anything in the air gets brought down
+                if has_effect!(target_mon, VolatileStatusEffect::SemiInvulernable(SemiInvulnerableKind::Airborne)) {
+                    self.stop_charging(target_side, user_side);
+                }
+            }
+            ME::MiracleEye => self.add_volatile(target_mon, VolatileStatusEffect::Identified),
+            ME::WakeUpSlap => todo!(),
+            // handled by get_move_power
+            ME::GyroBall => {}
+            ME::HealingWish => todo!(),
+            ME::NaturalGift => todo!(),
+            // handled before the hit
+            ME::Feint | ME::DestroyProtect => {}
+            ME::Pluck => todo!(),
+            ME::Tailwind => {
+                let turns = if self.generation >= 5 {4} else {3};
+                self.start_side_condition(user_side, SideCondition::Tailwind, turns);
+            }
+            ME::Acupressure => todo!(),
+            ME::MetalBurst => todo!(),
+            // a red card already decided where the user is going
+            ME::SwitchAfterAttacking => if using_mon.hp > 0 && user_side.has_healthy_reserve() && user_side.pending_switch.is_none() {
+                user_side.pending_switch = Some(SwitchOut::Pivot);
+            }
+            // handled by get_move_power
+            ME::DoublePowerIfTargetAlreadyMoved => {}
+            ME::DoublePowerIfTargetAlreadyTookDamage => todo!(),
+            ME::Embargo => todo!(),
+            ME::Fling => todo!(),
+            ME::PsychoShift => todo!(),
+            ME::TrumpCard => todo!(),
+            ME::HealBlock => if has_effect!(target_mon, VolatileStatusEffect::HealBlock(_)) {
+                self.emit(BattleEvent::Failed);
+            }
+            else {
+                self.add_volatile(target_mon, VolatileStatusEffect::HealBlock(5));
+            }
+            ME::PowerTrick => todo!(),
+            ME::GastroAcid => if has_effect!(target_mon, VolatileStatusEffect::AbilitySuppression) || self.is_unsuppressable(self.get_base_ability(target_mon)) || target_mon.item == Some(Item::AbilityShield) {
+                self.emit(BattleEvent::Failed);
+            }
+            else {
+                self.add_volatile(target_mon, VolatileStatusEffect::AbilitySuppression);
+            }
+            // only once the target has already moved this turn
+            ME::CoreEnforcer => if target_mon.hp > 0 && target_mon.volatile_status.moved_this_turn && !has_effect!(target_mon, VolatileStatusEffect::AbilitySuppression) &&
+                !self.is_unsuppressable(self.get_base_ability(target_mon)) && target_mon.item != Some(Item::AbilityShield)
+            {
+                self.add_volatile(target_mon, VolatileStatusEffect::AbilitySuppression);
+            }
+            ME::LuckyChant => self.start_side_condition(user_side, SideCondition::LuckyChant, 5),
+            ME::MeFirst => todo!(),
+            ME::Copycat => todo!(),
+            ME::PowerSwap => todo!(),
+            ME::GuardSwap => todo!(),
+            // handled by get_move_power
+            ME::Punishment => {}
+            ME::LastResort => todo!(),
+            ME::WorrySeed => todo!(),
+            ME::SuckerPunch => todo!(),
+            ME::ToxicSpikes => target_side.effects.add_toxic_spikes(),
+            ME::HeartSwap => todo!(),
+            ME::AquaRing => {
+                if has_effect!(using_mon, VolatileStatusEffect::AquaRing) {
+                    self.emit(BattleEvent::Failed);
+                }
+                else {
+                    self.add_volatile(using_mon, VolatileStatusEffect::AquaRing);
+                }
+            }
+            ME::MagnetRise => todo!(),
+            ME::Struggle => {
+
+                let recoil = if self.generation >= 4 {
+                    using_mon.max_hp / 4
+                }
+                else if self.generation >= 2 {
+                    move_damage / 4
+                }
+                else {
+                    move_damage / 2
+                };
+
+                self.recoil(using_mon, recoil.max(1));
+
+            }
+            // handled by use_move
+            ME::Dive | ME::Dig => {}
+            // the evasion drop comes from move_meta
+            ME::Defog => {
+
+                let effects = &mut target_side.effects;
+
+                for condition in [SideCondition::Reflect, SideCondition::LightScreen, SideCondition::AuroraVeil, SideCondition::Safeguard, SideCondition::Mist] {
+                    effects.set_turns(condition, 0);
+                }
+
+                if target_side.effects.has_hazards() {
+                    target_side.effects.clear_hazards();
+                    self.emit(BattleEvent::HazardsCleared {pokemon: target_side.get_active().get_event_pokemon()});
+                }
+
+                if self.generation >= 6 && user_side.effects.has_hazards() {
+                    user_side.effects.clear_hazards();
+                    self.emit(BattleEvent::HazardsCleared {pokemon: user_side.get_active().get_event_pokemon()});
+                }
+            }
+            ME::TrickRoom => if conditions.is_trick_room() {
+                conditions.trick_room_turns = 0;
+                self.emit(BattleEvent::TrickRoomEnded);
+            }
+            else {
+                conditions.trick_room_turns = 5;
+                self.emit(BattleEvent::TrickRoomStarted {pokemon: using_mon.get_event_pokemon()});
+            }
+            // handled by use_move
+            ME::Bounce => {}
+            // the sp. atk drop comes from move_meta, use_move checks the genders
+            ME::Captivate => {}
+            ME::StealthRock => target_side.effects.set_stealth_rock(true),
+            ME::LunarDance => todo!(),
+            // handled by use_move
+            ME::Disappear1TurnIgnoreProtect => {}
+            ME::WideGuard => self.try_protect(using_mon, VolatileStatusEffect::Protect(ProtectKind::WideGuard)),
+            ME::GuardSplit => todo!(),
+            ME::PowerSplit => todo!(),
+            ME::WonderRoom => todo!(),
+            // handled by get_defending_stat
+            ME::Psyshock => {}
+            // handled by get_move_power
+            ME::Venoshock => {}
+            // the speed boost comes from move_meta
+            ME::Autotomize => {
+
+                let mut uses = 1;
+
+                find_effect!(using_mon, VolatileStatusEffect::Autotomize(previous) => {
+                    uses = previous + 1;
+                });
+
+                remove_effect!(using_mon, VolatileStatusEffect::Autotomize(_));
+                using_mon.volatile_status.add(VolatileStatusEffect::Autotomize(uses));
+            }
+            ME::Telekinesis => todo!(),
+            ME::MagicRoom => todo!(),
+            ME::SmackDown => if target_mon.hp > 0 && !has_effect!(target_mon, VolatileStatusEffect::Grounded) {
+
+                remove_effect!(target_mon, VolatileStatusEffect::MagnetRise | VolatileStatusEffect::Telekinesis);
+
+                // knocks fly and bounce users out of the sky
+                if has_effect!(target_mon, VolatileStatusEffect::SemiInvulernable(SemiInvulnerableKind::Airborne)) {
+                    remove_effect!(target_mon, VolatileStatusEffect::SemiInvulernable(_) | VolatileStatusEffect::Charging(_));
+                }
+
+                self.add_volatile(target_mon, VolatileStatusEffect::Grounded);
+            }
+            ME::SplashDamage => todo!(),
+            // handled by get_move_power
+            ME::HeavySlam => {}
+            ME::HitIfTypesShared => todo!(),
+            // handled by get_move_power
+            ME::PowerBasedOnSpeedDifference => {}
+            ME::Soak => todo!(),
+            ME::SimpleBeam => todo!(),
+            ME::Entrainment => todo!(),
+            ME::AfterYou => todo!(),
+            // only stronger after an ally's round, which can't happen in singles
+            ME::Round => {}
+            ME::EchoedVoice => todo!(),
+            // ignoring the target's stat stages isn't modelled yet
+            ME::DarkestLariat => {}
+            ME::ClearSmog => if move_damage > 0 && target_mon.hp > 0 {
+                self.reset_stats(target_mon);
+            }
+            // handled by get_move_power
+            ME::PowerTrip => {}
+            ME::QuickGuard => self.try_protect(using_mon, VolatileStatusEffect::Protect(ProtectKind::QuickGuard)),
+            ME::AllySwitch => todo!(),
+            // the healing comes from move_meta
+            ME::HealPulse => {}
+            // handled by use_move
+            ME::SkyDrop => {}
+            ME::Roar => if !has_effect!(target_mon, VolatileStatusEffect::Substitute(_)) && self.can_force_out(target_side, conditions) {
+                target_side.pending_switch = Some(SwitchOut::Forced);
+            }
+            // from gen 7 the user stays in if mist blocked the drops
+            ME::PartingShot => if using_mon.hp > 0 && user_side.has_healthy_reserve() && (self.generation <= 6 || !misted) {
+                user_side.pending_switch = Some(SwitchOut::Pivot);
+            }
+            ME::Incinerate => todo!(),
+            ME::Quash => todo!(),
+            // move_meta raises both by one, sun raises them by two from gen 5
+            ME::Growth => if self.generation >= 5 && conditions.is_sunny() {
+                self.change_stat(using_mon, Stat::Attack, 1);
+                self.change_stat(using_mon, Stat::SpecialAttack, 1);
+            }
+            ME::ReflectType => todo!(),
+            ME::Retaliate => todo!(),
+            ME::FinalGambit => todo!(),
+            ME::Thief => todo!(),
+            ME::WaterPledge => todo!(),
+            ME::FirePledge => todo!(),
+            ME::GrassPledge => todo!(),
+            // sleep comes from move_meta, meloetta's form change isn't modelled
+            ME::RelicSong => {}
+            // the charge is handled by use_move and the ailment by apply_move_meta
+            ME::FreezeShock | ME::IceBurn => {}
+            ME::FlyingPress => todo!(),
+            ME::Belch => todo!(),
+            // raises grounded grass types on both sides, move_meta can't aim at them
+            ME::Rototiller | ME::FlowerShield => {
+
+                let mut raised = false;
+
+                for mon in [using_mon, target_mon] {
+
+                    if !self.pokemon_has_type(mon, Type::Grass) || mon.hp == 0 {
+                        continue;
+                    }
+
+                    if effect == ME::FlowerShield {
+                        self.change_stat(mon, Stat::Defense, 1);
+                    }
+                    else if self.is_grounded(mon, conditions) {
+                        self.change_stat(mon, Stat::Attack, 1);
+                        self.change_stat(mon, Stat::SpecialAttack, 1);
+                    }
+                    else {
+                        continue;
+                    }
+
+                    raised = true;
+                }
+
+                if !raised {
+                    self.emit(BattleEvent::Failed);
+                }
+            }
+            ME::StickyWeb => target_side.effects.set_sticky_web(true),
+            ME::FellStinger => todo!(),
+            ME::CraftyShield => self.try_protect(using_mon, VolatileStatusEffect::Protect(ProtectKind::CraftyShield)),
+            ME::KingsShield => self.try_protect(using_mon, VolatileStatusEffect::Protect(ProtectKind::KingsShield)),
+            ME::SpikyShield => self.try_protect(using_mon, VolatileStatusEffect::Protect(ProtectKind::SpikyShield)),
+            // only works on the first turn out
+            ME::MatBlock => if using_mon.volatile_status.turns_active == 0 {
+                self.try_protect(using_mon, VolatileStatusEffect::Protect(ProtectKind::MatBlock));
+            }
+            else {
+                self.emit(BattleEvent::Failed);
+            }
+            ME::BanefulBunker => self.try_protect(using_mon, VolatileStatusEffect::Protect(ProtectKind::BanefulBunker)),
+            ME::ThroatChop => if !has_effect!(target_mon, VolatileStatusEffect::ThroatChop(_)) {
+                target_mon.volatile_status.add(VolatileStatusEffect::ThroatChop(2));
+            }
+            ME::AuroraVeil => if conditions.is_weather(Weather::Hail) || conditions.is_weather(Weather::Snow) {
+                let turns = self.get_screen_turns(using_mon);
+                self.start_side_condition(user_side, SideCondition::AuroraVeil, turns);
+            }
+            else {
+                self.emit(BattleEvent::Failed);
+            }
+            ME::CourtChange => {
+                std::mem::swap(&mut user_side.effects, &mut target_side.effects);
+                self.emit(BattleEvent::CourtChanged {pokemon: user_side.get_active().get_event_pokemon()});
+            }
+            ME::Obstruct => self.try_protect(using_mon, VolatileStatusEffect::Protect(ProtectKind::Obstruct)),
+            ME::ScaleShot => {
+                self.change_stat(using_mon, Stat::Defense, -1);
+                self.change_stat(using_mon, Stat::Speed, 1);
+            }
+            // secondary effects that fit move_meta are applied by apply_move_meta
+            _ => {}
+        }
+    }
+
+    fn do_switch(&mut self, idx: usize, side: &mut Side, other_side: &mut Side, conditions: &mut Conditions, switch_out: Option<SwitchOut>) {
+
+        self.send_out(idx, side, other_side, conditions, switch_out);
+
+        if side.get_active().hp > 0 {
+            self.activate_ability(side, other_side, conditions);
+        }
+
+    }
+
+    // switches without activating the incoming pokemon's ability
+    fn send_out(&mut self, idx: usize, side: &mut Side, other_side: &mut Side, conditions: &mut Conditions, switch_out: Option<SwitchOut>) {
+
+        let mon = side.get_active_mut();
+        let passed = switch_out.map(|switch_out| self.get_passed_volatiles(mon, switch_out));
+
+        if mon.hp > 0 {
+            match self.get_ability(mon, conditions) {
+                Ability::NaturalCure => mon.non_volatile_status = None,
+                Ability::Regenerator => mon.heal(mon.max_hp / 3),
+                _ => {}
+            }
+        }
+
+        mon.volatile_status.clear();
+
+        if let Some(NonVolatileStatus::BadlyPoison(_)) = mon.non_volatile_status {
+            mon.non_volatile_status = Some(if self.generation <= 2 {
+                NonVolatileStatus::Poison
+            }
+            else {
+                NonVolatileStatus::BadlyPoison(1)
+            });
+        }
+
+        side.active_pokemon = idx;
+
+        if let Some(passed) = passed {
+            side.get_active_mut().volatile_status = passed;
+        }
+
+        // anything the outgoing pokemon was holding in place is let go
+        remove_effect!(other_side.get_active_mut(),
+            VolatileStatusEffect::Infatuation | VolatileStatusEffect::SkyDropped | VolatileStatusEffect::Bind {..} |
+            VolatileStatusEffect::Trapped | VolatileStatusEffect::JawLock | VolatileStatusEffect::Octolock
+        );
+
+        self.emit(BattleEvent::Switch {pokemon: side.get_active().get_event_pokemon()});
+
+        self.update_field_abilities(side, other_side, conditions);
+        self.apply_entry_hazards(side, conditions);
+
+    }
+
+    // pokemon that come in at the same time activate their abilities in speed order
+    fn activate_switch_in_abilities(&mut self, switched_in: [bool; 2], state: &mut BattleState) {
+
+        let side1_first = self.is_side1_faster(state);
+
+        for is_side1 in [side1_first, !side1_first] {
+
+            let (side, other_side, conditions) = state.split_mut(is_side1);
+
+            if switched_in[if is_side1 {0} else {1}] && side.get_active().hp > 0 {
+                self.activate_ability(side, other_side, conditions);
+            }
+        }
+    }
+
+    pub fn start_battle(&mut self, state: &mut BattleState) {
+
+        self.emit(BattleEvent::Switch {pokemon: state.side1.get_active().get_event_pokemon()});
+        self.emit(BattleEvent::Switch {pokemon: state.side2.get_active().get_event_pokemon()});
+
+        let (side, other_side, conditions) = state.split_mut(true);
+        self.update_field_abilities(side, other_side, conditions);
+
+        self.activate_switch_in_abilities([true, true], state);
+
+    }
+
+    fn is_grounded(&self, pokemon: &Pokemon, conditions: &Conditions) -> bool {
+
+        if conditions.is_gravity() || pokemon.item == Some(Item::IronBall) || has_effect!(pokemon, VolatileStatusEffect::Grounded | VolatileStatusEffect::Ingrain) {
+            return true;
+        }
+
+        !(self.pokemon_has_type(pokemon, Type::Flying) ||
+            self.get_ability(pokemon, conditions) == Ability::Levitate ||
+            pokemon.item == Some(Item::AirBalloon) ||
+            has_effect!(pokemon, VolatileStatusEffect::MagnetRise | VolatileStatusEffect::Telekinesis))
+
+    }
+
+    fn apply_entry_hazards(&mut self, side: &mut Side, conditions: &Conditions) {
+
+        let effects = &mut side.effects;
+        let mon = &mut side.team[side.active_pokemon];
+
+        if mon.item == Some(Item::HeavyDutyBoots) {
+            return;
+        }
+
+        let grounded = self.is_grounded(mon, conditions);
+
+        if effects.get_stealth_rock() {
+            let damage = (mon.max_hp as u32 * self.get_type_effectiveness_against(Type::Rock, mon) / 800) as u16;
+            self.deal_residual_damage(mon, damage, DamageCause::StealthRock, conditions);
+        }
+
+        let spikes = effects.get_spikes();
+
+        if spikes > 0 && grounded {
+            let damage = mon.max_hp / [8, 6, 4][spikes as usize - 1];
+            self.deal_residual_damage(mon, damage, DamageCause::Spikes, conditions);
+        }
+
+        let toxic_spikes = effects.get_toxic_spikes();
+
+        if toxic_spikes > 0 && grounded && mon.hp > 0 && !effects.is_active(SideCondition::Safeguard) {
+            if self.pokemon_has_type(mon, Type::Poison) {
+                effects.set_toxic_spikes(0);
+                self.emit(BattleEvent::ToxicSpikesAbsorbed {pokemon: mon.get_event_pokemon()});
+            }
+            else if !self.pokemon_has_type(mon, Type::Steel) {
+                self.try_apply_status(mon, if toxic_spikes == 1 {
+                    NonVolatileStatus::Poison
+                }
+                else {
+                    NonVolatileStatus::BadlyPoison(1)
+                });
+            }
+        }
+
+        if effects.get_sticky_web() && grounded && mon.hp > 0 {
+            self.change_stat(mon, Stat::Speed, -1);
+        }
+    }
+
+    // the replacement for each side, if it needs one
+    pub fn replace_fainted(&mut self, state: &mut BattleState, controllers: [&dyn Controller; 2]) {
+
+        let switch_ins = [true, false].map(|is_side1| {
+            let side = if is_side1 {&state.side1} else {&state.side2};
+            (side.get_active().hp == 0 && side.has_healthy_reserve()).then(|| self.get_switch_in(is_side1, state, controllers))
+        });
+
+        for (is_side1, switch_in) in [(true, switch_ins[0]), (false, switch_ins[1])] {
+            if let Some(idx) = switch_in {
+                let (side, other_side, conditions) = state.split_mut(is_side1);
+                self.send_out(idx, side, other_side, conditions, None);
+            }
+        }
+
+        self.activate_switch_in_abilities(switch_ins.map(|switch_in| switch_in.is_some()), state);
+
+    }
+
+    // asks again when the controller picks the active pokemon, a fainted one or an empty slot
+    fn get_switch_in(&self, is_side1: bool, state: &BattleState, controllers: [&dyn Controller; 2]) -> usize {
+
+        let (side, controller) = if is_side1 {
+            (&state.side1, controllers[0])
+        }
+        else {
+            (&state.side2, controllers[1])
+        };
+
+        for _ in 0..=MAX_PROMPTS {
+
+            let idx = controller.get_switch_in(state, is_side1) as usize;
+
+            if side.is_valid_switch_in(idx) {
+                return idx;
+            }
+        }
+
+        (0..side.team.len()).find(|&idx| side.is_valid_switch_in(idx)).unwrap()
+
+    }
+
+    // returns whether the side switched
+    fn resolve_pending_switch(&mut self, is_side1: bool, state: &mut BattleState, controllers: [&dyn Controller; 2]) -> bool {
+
+        let (side, _, _) = state.split_mut(is_side1);
+
+        let Some(switch_out) = side.pending_switch.take()
+        else {
+            return false;
+        };
+
+        if side.get_active().hp == 0 || !side.has_healthy_reserve() {
+            return false;
+        }
+
+        let idx = if switch_out == SwitchOut::Forced {
+            let reserves = (0..side.team.len()).filter(|&idx| side.is_valid_switch_in(idx)).collect::<Vec<_>>();
+            reserves[self.rng.random_range(0..reserves.len())]
+        }
+        else {
+            self.get_switch_in(is_side1, state, controllers)
+        };
+
+        let (side, other_side, conditions) = state.split_mut(is_side1);
+        self.do_switch(idx, side, other_side, conditions, Some(switch_out));
+
+        true
+
+    }
+
+    fn check_eject_pack(&mut self, side: &mut Side, stages_before: [i8; NUM_STATS]) {
+
+        let mon = side.get_active();
+
+        if mon.item != Some(Item::EjectPack) || mon.hp == 0 || side.pending_switch.is_some() || !side.has_healthy_reserve() {
+            return;
+        }
+
+        if mon.volatile_status.stat_stages.iter().zip(stages_before).any(|(&after, before)| after < before) {
+            self.emit(BattleEvent::ItemActivated {pokemon: mon.get_event_pokemon(), item: Item::EjectPack});
+            side.get_active_mut().item = None;
+            side.pending_switch = Some(SwitchOut::Pivot);
+        }
+    }
+
+    fn perform_action(&mut self, action: BattleAction, used_by_side1: bool, state: &mut BattleState) {
+
+        let (using_side, other_side) = if used_by_side1 {
+            (&mut state.side1, &mut state.side2)
+        }
+        else {
+            (&mut state.side2, &mut state.side1)
+        };
+
+        if using_side.get_active().hp == 0 {
+            return;
+        }
+
+        match action {
+            BattleAction::Move(mut move_id) => {
+
+                using_side.get_active_mut().volatile_status.moved_this_turn = true;
+
+                let user = using_side.get_active();
+
+                // the choice may have been made before an encore landed
+                if move_id != MOVE_STRUGGLE {
+                    if let Some(forced) = self.get_forced_move(user) {
+                        move_id = forced;
+                    }
+                }
+
+                let continuing = user.get_locked_move() == Some(move_id);
+
+                // locked moves were paid for when they started
+                if move_id != MOVE_STRUGGLE && !continuing {
+                    match user.get_move_slot(move_id) {
+                        Some(slot) if slot.pp > 0 => {}
+                        _ if !user.has_usable_move() => move_id = MOVE_STRUGGLE,
+                        _ => {
+                            self.emit(BattleEvent::NoPP {pokemon: user.get_event_pokemon()});
+                            return;
+                        }
+                    }
+                }
+
+                let used_move = self.data_handler.get_move_in_generation(move_id, self.generation);
+
+                let restriction = self.get_move_restriction(used_move, using_side.get_active(), other_side.get_active(), &state.conditions);
+
+                if let Some(reason) = restriction {
+                    self.emit(BattleEvent::CantMove {pokemon: using_side.get_active().get_event_pokemon(), reason});
+                }
+
+                if restriction.is_some() || !self.can_move(used_move, using_side.get_active_mut(), &state.conditions) {
+
+                    self.stop_charging(using_side, other_side);
+
+                    remove_effect!(using_side.get_active_mut(),
+                        VolatileStatusEffect::Rampage(_) | VolatileStatusEffect::Rolling(_) | VolatileStatusEffect::Uproar(_) | VolatileStatusEffect::Bide(..)
+                    );
+
+                    return;
+                }
+
+                let stages_before = [using_side.get_active().volatile_status.stat_stages, other_side.get_active().volatile_status.stat_stages];
+                let ignores_abilities = self.ignores_abilities(self.get_ability(using_side.get_active(), &state.conditions));
+
+                if ignores_abilities {
+                    other_side.get_active_mut().volatile_status.add(VolatileStatusEffect::AbilityIgnored);
+                }
+
+                let succeeded = self.use_move(used_move, using_side, other_side, &mut state.conditions);
+                self.update_locked_move(used_move, using_side.get_active_mut(), continuing, succeeded);
+
+                remove_effect!(other_side.get_active_mut(), VolatileStatusEffect::AbilityIgnored);
+
+                self.check_eject_pack(using_side, stages_before[0]);
+                self.check_eject_pack(other_side, stages_before[1]);
+
+            }
+            BattleAction::Switch(mon_idx) =>  {
+
+                if !using_side.is_valid_switch_in(mon_idx as usize) {
+                    self.emit(BattleEvent::Failed);
+                    return;
+                }
+
+                // trapping can start between choosing the switch and making it
+                if self.is_trapped(using_side.get_active(), other_side.get_active(), &state.conditions) {
+                    self.emit(BattleEvent::CantMove {pokemon: using_side.get_active().get_event_pokemon(), reason: CantMoveReason::Trapped});
+                    return;
+                }
+
+                self.do_switch(mon_idx as usize, using_side, other_side, &mut state.conditions, None);
+            }
+            BattleAction::Item(_) => todo!(),
+            // handled by the battle before the turn starts
+            BattleAction::Forfeit => {}
+        }
+    }
+
+    fn deal_residual_damage(&mut self, pokemon: &mut Pokemon, damage: u16, cause: DamageCause, conditions: &Conditions) {
+
+        if pokemon.hp == 0 || self.get_ability(pokemon, conditions) == Ability::MagicGuard {
+            return;
+        }
+
+        let damage = damage.max(1);
+
+        pokemon.deal_damage(damage);
+        self.emit(BattleEvent::IndirectDamage {pokemon: pokemon.get_event_pokemon(), amount: damage, cause});
+
+        if pokemon.hp == 0 {
+            self.emit(BattleEvent::Faint {pokemon: pokemon.get_event_pokemon()});
+        }
+    }
+
+    fn residual_heal(&mut self, pokemon: &mut Pokemon, hp: u16) {
+
+        if pokemon.hp == 0 || self.generation >= 5 && has_effect!(pokemon, VolatileStatusEffect::HealBlock(_)) {
+            return;
+        }
+
+        self.heal(pokemon, hp.max(1));
+
+    }
+
+    fn is_weather_immune(&self, pokemon: &Pokemon, weather: Weather, conditions: &Conditions) -> bool {
+
+        if pokemon.item == Some(Item::SafetyGoggles) {
+            return true;
+        }
+
+        let ability = self.get_ability(pokemon, conditions);
+
+        if ability == Ability::Overcoat && self.generation >= 5 {
+            return true;
+        }
+
+        match weather {
+            Weather::Sandstorm =>
+                self.pokemon_has_type(pokemon, Type::Rock) ||
+                self.pokemon_has_type(pokemon, Type::Ground) ||
+                self.pokemon_has_type(pokemon, Type::Steel) ||
+                matches!(ability, Ability::SandVeil | Ability::SandRush | Ability::SandForce),
+            Weather::Hail =>
+                self.pokemon_has_type(pokemon, Type::Ice) ||
+                matches!(ability, Ability::IceBody | Ability::SnowCloak),
+            _ => true
+        }
+    }
+
+    fn do_residual(&mut self, step: ResidualStep, side: &mut Side, other_side: &mut Side, conditions: &Conditions) {
+
+        let mon = side.get_active_mut();
+
+        if mon.hp == 0 {
+            return;
+        }
+
+        let ability = self.get_ability(mon, conditions);
+
+        match step {
+            ResidualStep::Weather => {
+
+                if let Some(weather @ (Weather::Sandstorm | Weather::Hail)) = conditions.get_weather() {
+                    if !self.is_weather_immune(mon, weather, conditions) {
+                        self.deal_residual_damage(mon, mon.max_hp / 16, DamageCause::Weather(weather), conditions);
+                    }
+                }
+
+                match ability {
+                    Ability::DrySkin if conditions.is_rain() => self.residual_heal(mon, mon.max_hp / 8),
+                    Ability::DrySkin if conditions.is_sunny() => self.deal_residual_damage(mon, mon.max_hp / 8, DamageCause::Ability(ability), conditions),
+                    Ability::RainDish if conditions.is_rain() => self.residual_heal(mon, mon.max_hp / 16),
+                    Ability::IceBody if conditions.is_weather(Weather::Hail) => self.residual_heal(mon, mon.max_hp / 16),
+                    Ability::SolarPower if conditions.is_sunny() => self.deal_residual_damage(mon, mon.max_hp / 8, DamageCause::Ability(ability), conditions),
+                    _ => {}
+                }
+            }
+            ResidualStep::StatusCure => {
+
+                let Some(status) = mon.non_volatile_status
+                else {
+                    return;
+                };
+
+                let cured = match ability {
+                    _ if status == NonVolatileStatus::Freeze && self.generation == 2 => self.rng.random_ratio(25, 256),
+                    Ability::ShedSkin if self.generation >= 5 => self.rng.random_ratio(3, 10),
+                    Ability::ShedSkin => self.rng.random_ratio(1, 3),
+                    Ability::Hydration => conditions.is_rain(),
+                    _ => false
+                };
+
+                if cured {
+                    mon.non_volatile_status = None;
+                    self.emit(BattleEvent::StatusCured {pokemon: mon.get_event_pokemon(), status});
+                }
+            }
+            ResidualStep::HeldItem => {
+                match mon.item {
+                    Some(Item::Leftovers) => self.residual_heal(mon, mon.max_hp / 16),
+                    Some(Item::BlackSludge) => if self.pokemon_has_type(mon, Type::Poison) {
+                        self.residual_heal(mon, mon.max_hp / 16);
+                    }
+                    else {
+                        self.deal_residual_damage(mon, mon.max_hp / 8, DamageCause::Item(Item::BlackSludge), conditions);
+                    }
+                    _ => {}
+                }
+            }
+            ResidualStep::AquaRing | ResidualStep::Ingrain => {
+
+                let has_heal = match step {
+                    ResidualStep::AquaRing => has_effect!(mon, VolatileStatusEffect::AquaRing),
+                    _ => has_effect!(mon, VolatileStatusEffect::Ingrain)
+                };
+
+                if has_heal {
+
+                    let mut hp = mon.max_hp / 16;
+
+                    if mon.item == Some(Item::BigRoot) {
+                        hp = hp * 13 / 10;
+                    }
+
+                    self.residual_heal(mon, hp);
+
+                }
+            }
+            ResidualStep::LeechSeed => {
+
+                if !has_effect!(mon, VolatileStatusEffect::Seed) || ability == Ability::MagicGuard {
+                    return;
+                }
+
+                let drained = (if self.generation == 1 {mon.max_hp / 16} else {mon.max_hp / 8}).max(1).min(mon.hp);
+
+                self.deal_residual_damage(mon, drained, DamageCause::LeechSeed, conditions);
+
+                let seeder = other_side.get_active_mut();
+
+                if ability == Ability::LiquidOoze {
+                    self.deal_residual_damage(seeder, drained, DamageCause::Ability(Ability::LiquidOoze), conditions);
+                }
+                else {
+
+                    let mut hp = drained;
+
+                    if seeder.item == Some(Item::BigRoot) {
+                        hp = hp * 13 / 10;
+                    }
+
+                    self.residual_heal(seeder, hp);
+
+                }
+            }
+            ResidualStep::StatusDamage => {
+                match mon.non_volatile_status {
+                    Some(NonVolatileStatus::Burn) => {
+
+                        let mut damage = if self.generation >= 7 || self.generation == 1 {
+                            mon.max_hp / 16
+                        }
+                        else {
+                            mon.max_hp / 8
+                        };
+
+                        if ability == Ability::Heatproof {
+                            damage /= 2;
+                        }
+
+                        self.deal_residual_damage(mon, damage, DamageCause::Burn, conditions);
+
+                    }
+                    Some(NonVolatileStatus::Poison | NonVolatileStatus::BadlyPoison(_)) if ability == Ability::PoisonHeal => {
+                        self.residual_heal(mon, mon.max_hp / 8);
+                    }
+                    Some(NonVolatileStatus::Poison) => {
+                        let damage = if self.generation == 1 {mon.max_hp / 16} else {mon.max_hp / 8};
+                        self.deal_residual_damage(mon, damage, DamageCause::Poison, conditions);
+                    }
+                    Some(NonVolatileStatus::BadlyPoison(turns)) => {
+                        mon.non_volatile_status = Some(NonVolatileStatus::BadlyPoison((turns + 1).min(15)));
+                        self.deal_residual_damage(mon, mon.max_hp / 16 * turns as u16, DamageCause::Poison, conditions);
+                    }
+                    _ => {}
+                }
+            }
+            ResidualStep::Nightmare => {
+                if has_effect!(mon, VolatileStatusEffect::Nightmare) {
+                    if matches!(mon.non_volatile_status, Some(NonVolatileStatus::Sleep(_))) {
+                        self.deal_residual_damage(mon, mon.max_hp / 4, DamageCause::Nightmare, conditions);
+                    }
+                    else {
+                        remove_effect!(mon, VolatileStatusEffect::Nightmare);
+                    }
+                }
+            }
+            ResidualStep::Curse => {
+                if has_effect!(mon, VolatileStatusEffect::Curse) {
+                    self.deal_residual_damage(mon, mon.max_hp / 4, DamageCause::Curse, conditions);
+                }
+            }
+            ResidualStep::Bind => {
+                find_effect!(mon, VolatileStatusEffect::Bind {health_fraction, ..} => {
+                    let damage = mon.max_hp / *health_fraction as u16;
+                    self.deal_residual_damage(mon, damage, DamageCause::Bind, conditions);
+                    break;
+                });
+            }
+            ResidualStep::Octolock => if has_effect!(mon, VolatileStatusEffect::Octolock) && other_side.get_active().hp > 0 {
+                self.change_stat(mon, Stat::Defense, -1);
+                self.change_stat(mon, Stat::SpecialDefense, -1);
+            }
+            ResidualStep::PerishSong => {
+
+                let mut perish_count = None;
+
+                for effect in &mut mon.volatile_status.effects {
+                    if let VolatileStatusEffect::PerishSong(count) = effect {
+                        *count -= 1;
+                        perish_count = Some(*count);
+                    }
+                }
+
+                if let Some(count) = perish_count {
+
+                    self.emit(BattleEvent::PerishCount {pokemon: mon.get_event_pokemon(), count});
+
+                    if count == 0 {
+                        mon.hp = 0;
+                        self.emit(BattleEvent::Faint {pokemon: mon.get_event_pokemon()});
+                    }
+                }
+            }
+            ResidualStep::SpeedBoost => {
+                match ability {
+                    Ability::SpeedBoost => self.change_stat(mon, Stat::Speed, 1),
+                    Ability::Moody => {
+
+                        let stats = if self.generation >= 8 {
+                            &[Stat::Attack, Stat::Defense, Stat::SpecialAttack, Stat::SpecialDefense, Stat::Speed][..]
+                        }
+                        else {
+                            &[Stat::Attack, Stat::Defense, Stat::SpecialAttack, Stat::SpecialDefense, Stat::Speed, Stat::Accuracy, Stat::Evasion][..]
+                        };
+
+                        let raised_options = stats.iter().filter(|stat| mon.get_stat_stage(**stat) < 6).copied().collect::<Vec<_>>();
+                        let raised = (!raised_options.is_empty()).then(|| raised_options[self.rng.random_range(0..raised_options.len())]);
+
+                        if let Some(stat) = raised {
+                            self.change_stat(mon, stat, 2);
+                        }
+
+                        let lowered_options = stats.iter().filter(|stat| mon.get_stat_stage(**stat) > -6 && Some(**stat) != raised).copied().collect::<Vec<_>>();
+
+                        if !lowered_options.is_empty() {
+                            let stat = lowered_options[self.rng.random_range(0..lowered_options.len())];
+                            self.change_stat(mon, stat, -1);
+                        }
+                    }
+                    Ability::BadDreams => {
+                        let other_mon = other_side.get_active_mut();
+                        if matches!(other_mon.non_volatile_status, Some(NonVolatileStatus::Sleep(_))) {
+                            self.deal_residual_damage(other_mon, other_mon.max_hp / 8, DamageCause::Ability(Ability::BadDreams), conditions);
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            ResidualStep::Orb => {
+                match mon.item {
+                    Some(Item::ToxicOrb) => self.try_apply_status(mon, NonVolatileStatus::BadlyPoison(1)),
+                    Some(Item::FlameOrb) => self.try_apply_status(mon, NonVolatileStatus::Burn),
+                    Some(Item::StickyBarb) => self.deal_residual_damage(mon, mon.max_hp / 8, DamageCause::Item(Item::StickyBarb), conditions),
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    fn on_turn_end(&mut self, state: &mut BattleState) {
+
+        // nothing happens at the end of the turn in gen 1
+        let order: &[ResidualStep] = match self.generation {
+            1 => &[],
+            2 => &GEN_2_RESIDUAL_ORDER,
+            3 | 4 => &GEN_3_4_RESIDUAL_ORDER,
+            _ => &RESIDUAL_ORDER
+        };
+
+        let side1_first = self.is_side1_faster(state);
+
+        for step in order {
+            for is_side1 in [side1_first, !side1_first] {
+                let (side, other_side, conditions) = state.split_mut(is_side1);
+                self.do_residual(*step, side, other_side, conditions);
+            }
+        }
+
+        let (side, other_side, conditions) = state.split_mut(true);
+        self.update_field_abilities(side, other_side, conditions);
+        
+        for side in [&mut state.side1, &mut state.side2] {
+
+            let mon = side.get_active_mut();
+            remove_effect!(mon, VolatileStatusEffect::Flinch | VolatileStatusEffect::Endure | VolatileStatusEffect::Protect(_));
+
+            let mut ended = mon.volatile_status.decriment_counters();
+
+            // an encore also ends once the move runs out of pp
+            let mut out_of_pp = None;
+
+            find_effect!(mon, VolatileStatusEffect::Encore(move_id, turns) => {
+                if mon.get_move_slot(*move_id).is_none_or(|slot| slot.pp == 0) {
+                    out_of_pp = Some(VolatileStatusEffect::Encore(*move_id, *turns));
+                }
+            });
+
+            if let Some(effect) = out_of_pp {
+                remove_effect!(mon, VolatileStatusEffect::Encore(..));
+                ended.push(effect);
+            }
+
+            for effect in ended {
+                self.emit(BattleEvent::VolatileEnded {pokemon: mon.get_event_pokemon(), effect});
+            }
+            mon.volatile_status.turns_active = mon.volatile_status.turns_active.saturating_add(1);
+            mon.volatile_status.moved_this_turn = false;
+
+            for condition in side.effects.decriment_counters() {
+                self.emit(BattleEvent::SideConditionEnded {pokemon: side.get_active().get_event_pokemon(), condition});
+            }
+        }
+
+        let weather = state.conditions.weather.map(|(weather, _)| weather);
+        let trick_room = state.conditions.is_trick_room();
+        let gravity = state.conditions.is_gravity();
+
+        state.conditions.decriment_counters();
+
+        if trick_room && !state.conditions.is_trick_room() {
+            self.emit(BattleEvent::TrickRoomEnded);
+        }
+
+        if gravity && !state.conditions.is_gravity() {
+            self.emit(BattleEvent::GravityEnded);
+        }
+
+        if let (Some(weather), None) = (weather, state.conditions.weather) {
+            self.emit(BattleEvent::WeatherEnded {weather});
+        }
+    }
+
+    pub fn simulate_turn(&mut self, side1_action: BattleAction, side2_action: BattleAction, state: &mut BattleState, controllers: [&dyn Controller; 2]) {
+        
+        let side1_action = self.check_action(side1_action, true, state, controllers[0]);
+        let side2_action = self.check_action(side2_action, false, state, controllers[1]);
+
+        self.turn += 1;
+        self.emit(BattleEvent::TurnStart(self.turn));
+
+        let mut queue = vec![
+            self.queue_action(side1_action, true, state),
+            self.queue_action(side2_action, false, state)
+        ];
+
+        queue.sort_by_key(|queued| std::cmp::Reverse(queued.order_key()));
+
+        while !queue.is_empty() {
+
+            let queued = queue.remove(0);
+            self.perform_action(queued.action, queued.is_side1, state);
+
+            if self.generation <= 2 {
+                for step in GEN_1_2_AFTER_ACTION_ORDER {
+                    let (side, other_side, conditions) = state.split_mut(queued.is_side1);
+                    self.do_residual(step, side, other_side, conditions);
+                }
+            }
+
+            let (side, other_side, conditions) = state.split_mut(true);
+            self.update_field_abilities(side, other_side, conditions);
+
+            // the target goes first, and ejecting it keeps the user from pivoting out
+            if self.resolve_pending_switch(!queued.is_side1, state, controllers) {
+
+                let (user_side, _, _) = state.split_mut(queued.is_side1);
+
+                if user_side.pending_switch == Some(SwitchOut::Pivot) {
+                    user_side.pending_switch = None;
+                }
+
+                // a pokemon brought in partway through the turn doesn't get to act
+                queue.retain(|other| other.is_side1 == queued.is_side1);
+
+            }
+
+            self.resolve_pending_switch(queued.is_side1, state, controllers);
+
+            // speed changes take effect immediately from gen 8
+            if self.generation >= 8 {
+
+                for queued in &mut queue {
+                    let (side, _, conditions) = state.split_mut(queued.is_side1);
+                    queued.speed = self.get_order_speed(side, conditions);
+                }
+
+                queue.sort_by_key(|queued| std::cmp::Reverse(queued.order_key()));
+
+            }
+        }
+
+        self.on_turn_end(state);
+
+        // println!("{:#?}", state.side1);
+        // println!("{:#?}", state.side2);
+        
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::test_utils::*;
+
+    const ATTACKER: &str = "
+Blastoise
+Level: 50
+Ability: Torrent
+- Hydro Pump
+- Swift
+- Body Slam";
+
+    const DEFENDER: &str = "
+Chansey
+Level: 100
+Ability: Natural Cure
+- Soft-Boiled";
+
+    const TEAM: &str = "
+Jolteon
+Level: 50
+Ability: Volt Absorb
+- Growl
+- Thunderbolt
+
+Snorlax
+Level: 50
+Ability: Thick Fat
+- Growl
+
+Lapras
+Level: 50
+Ability: Water Absorb
+- Growl";
+
+    fn new_state(side1: &str, side2: &str, generation: u8) -> BattleState {
+        BattleState::new(parse_team(side1, generation), parse_team(side2, generation))
+    }
+
+    fn get_hit_chance(generation: u8, move_name: &str, state: &BattleState) -> Option<u32> {
+        let sim = Simulator::new(get_data_handler(), generation, 0);
+        sim.get_hit_chance(get_move(move_name, generation), state.side1.get_active(), state.side2.get_active(), &state.conditions)
+    }
+
+    #[test]
+    fn never_miss_moves_ignore_accuracy_and_evasion() {
+
+        let recorder = EventRecorder::default();
+        let mut sim = Simulator::new(get_data_handler(), 5, 1);
+        sim.add_event_sink(Box::new(recorder.clone()));
+
+        let mut state = new_state(ATTACKER, DEFENDER, 5);
+        state.side1.get_active_mut().apply_stat_changes(Stat::Accuracy, -6);
+        state.side2.get_active_mut().apply_stat_changes(Stat::Evasion, 6);
+
+        let swift = get_move("Swift", 5);
+
+        for _ in 0..20 {
+            let (side, other_side, conditions) = state.split_mut(true);
+            other_side.get_active_mut().hp = other_side.get_active().max_hp;
+            assert!(sim.use_move(swift, side, other_side, conditions));
+        }
+
+        assert!(!recorder.events().iter().any(|event| matches!(event, BattleEvent::Miss {..})));
+        assert_eq!(get_hit_chance(5, "Swift", &state), None);
+    }
+
+    #[test]
+    fn accuracy_and_evasion_stage_tables() {
+
+        // (generation, accuracy stages, evasion stages, chance for an 80% move)
+        let cases = [
+            (2, 0, 1, 52),
+            (2, -1, 0, 52),
+            (2, 1, 0, 120),
+            (3, 0, 1, 60),
+            (4, 2, 0, 132),
+            (4, 6, 6, 80),
+            (5, 0, 1, 60),
+            (5, 2, 0, 132),
+            (5, -6, 0, 26),
+            (9, 0, 6, 26)
+        ];
+
+        for (generation, accuracy, evasion, expected) in cases {
+
+            let mut state = new_state(ATTACKER, DEFENDER, generation);
+            state.side1.get_active_mut().apply_stat_changes(Stat::Accuracy, accuracy);
+            state.side2.get_active_mut().apply_stat_changes(Stat::Evasion, evasion);
+
+            assert_eq!(get_hit_chance(generation, "Hydro Pump", &state), Some(expected), "gen {generation} {accuracy:+} accuracy {evasion:+} evasion");
+        }
+    }
+
+    #[test]
+    fn accuracy_modifiers() {
+
+        let mut state = new_state(ATTACKER, DEFENDER, 5);
+        state.side1.get_active_mut().ability = Ability::CompoundEyes;
+        assert_eq!(get_hit_chance(5, "Hydro Pump", &state), Some(104));
+
+        state.side1.get_active_mut().ability = Ability::Torrent;
+        state.side1.get_active_mut().item = Some(Item::WideLens);
+        assert_eq!(get_hit_chance(5, "Hydro Pump", &state), Some(87));
+
+        state.side1.get_active_mut().ability = Ability::NoGuard;
+        assert_eq!(get_hit_chance(5, "Hydro Pump", &state), None);
+
+        let mut state = new_state(ATTACKER, DEFENDER, 5);
+        state.conditions.weather = Some((Weather::Rain, 5));
+        assert_eq!(get_hit_chance(5, "Thunder", &state), None);
+
+        state.conditions.weather = Some((Weather::Sun, 5));
+        assert_eq!(get_hit_chance(5, "Thunder", &state), Some(50));
+    }
+
+    #[test]
+    fn gen_1_sure_hits_can_miss() {
+
+        let mut sim = Simulator::new(get_data_handler(), 1, 3);
+        let state = new_state(ATTACKER, DEFENDER, 1);
+        let body_slam = get_move("Body Slam", 1);
+
+        let misses = (0..25600).filter(|_| !sim.check_hit(body_slam, state.side1.get_active(), state.side2.get_active(), &state.conditions)).count();
+
+        // about 1 in 256
+        assert!((50..=150).contains(&misses), "{misses} misses");
+    }
+
+    #[test]
+    fn crit_rate_tables() {
+
+        // (generation, stage, chance)
+        let cases = [
+            (2, 0, (17, 256)),
+            (2, 3, (85, 256)),
+            (2, 4, (1, 2)),
+            (3, 0, (1, 16)),
+            (5, 2, (1, 4)),
+            (5, 3, (1, 3)),
+            (5, 9, (1, 2)),
+            (6, 0, (1, 16)),
+            (6, 2, (1, 2)),
+            (7, 0, (1, 24)),
+            (9, 1, (1, 8)),
+            (9, 3, (1, 1))
+        ];
+
+        for (generation, stage, expected) in cases {
+            let sim = Simulator::new(get_data_handler(), generation, 0);
+            assert_eq!(sim.get_crit_chance(stage), expected, "gen {generation} stage {stage}");
+        }
+
+        let sim = Simulator::new(get_data_handler(), 5, 0);
+        let mut state = new_state("Machamp\nLevel: 50\nAbility: Guts\n- Karate Chop", DEFENDER, 5);
+        let machamp = state.side1.get_active_mut();
+        machamp.item = Some(Item::ScopeLens);
+        machamp.volatile_status.add(VolatileStatusEffect::GettingPumped);
+
+        assert_eq!(sim.get_crit_stage(get_move("Karate Chop", 5), state.side1.get_active(), &state.conditions), 4);
+    }
+
+    #[test]
+    fn gen_1_crit_threshold() {
+
+        let sim = Simulator::new(get_data_handler(), 1, 0);
+        let mut state = new_state("Persian\nLevel: 50\n- Slash\n- Scratch", DEFENDER, 1);
+
+        // base speed 115
+        assert_eq!(sim.get_crit_threshold_gen_1(get_move("Scratch", 1), state.side1.get_active()), 57);
+        assert_eq!(sim.get_crit_threshold_gen_1(get_move("Slash", 1), state.side1.get_active()), 255);
+
+        state.side1.get_active_mut().volatile_status.add(VolatileStatusEffect::GettingPumped);
+        assert_eq!(sim.get_crit_threshold_gen_1(get_move("Scratch", 1), state.side1.get_active()), 14);
+    }
+
+    #[test]
+    fn multi_hit_effects_roll_once() {
+
+        let recorder = EventRecorder::default();
+        let mut sim = Simulator::new(get_data_handler(), 9, 0);
+        sim.add_event_sink(Box::new(recorder.clone()));
+
+        let mut state = new_state("Dragapult\nLevel: 50\nAbility: Clear Body\n- Scale Shot", DEFENDER, 9);
+        state.side1.get_active_mut().ability = Ability::SkillLink;
+
+        let (side, other_side, conditions) = state.split_mut(true);
+        sim.use_move(get_move("Scale Shot", 9), side, other_side, conditions);
+
+        let events = recorder.events();
+        assert!(events.iter().any(|event| matches!(event, BattleEvent::HitCount {count: 5, ..})));
+
+        let dragapult = state.side1.get_active();
+        assert_eq!(dragapult.get_stat_stage(Stat::Speed), 1);
+        assert_eq!(dragapult.get_stat_stage(Stat::Defense), -1);
+    }
+
+    #[test]
+    fn autotomize_lightens_the_user() {
+
+        let mut sim = Simulator::new(get_data_handler(), 5, 0);
+        let mut state = new_state("Aggron\nLevel: 50\nAbility: Sturdy\n- Autotomize", DEFENDER, 5);
+
+        for _ in 0..2 {
+            let (side, other_side, conditions) = state.split_mut(true);
+            sim.use_move(get_move("Autotomize", 5), side, other_side, conditions);
+        }
+
+        let aggron = state.side1.get_active();
+        assert_eq!(aggron.get_stat_stage(Stat::Speed), 4);
+        assert_eq!(sim.get_weight(aggron), 3600.0 - 2000.0);
+    }
+
+    #[test]
+    fn moonlight_heals_by_weather() {
+
+        let mut sim = Simulator::new(get_data_handler(), 5, 0);
+
+        for (weather, numerator, denominator) in [(None, 1, 2), (Some(Weather::Sun), 2, 3), (Some(Weather::Rain), 1, 4)] {
+
+            let mut state = new_state("Clefable\nLevel: 50\nAbility: Magic Guard\n- Moonlight", DEFENDER, 5);
+            state.conditions.weather = weather.map(|weather| (weather, 5));
+
+            let clefable = state.side1.get_active_mut();
+            clefable.hp = 1;
+
+            let (side, other_side, conditions) = state.split_mut(true);
+            sim.use_move(get_move("Moonlight", 5), side, other_side, conditions);
+
+            let clefable = state.side1.get_active();
+            assert_eq!(clefable.hp, 1 + clefable.max_hp * numerator / denominator, "{weather:?}");
+        }
+    }
+
+    #[test]
+    fn unique_stat_changes_go_to_the_right_side() {
+
+        let mut sim = Simulator::new(get_data_handler(), 5, 0);
+        let mut state = new_state("Cloyster\nLevel: 50\nAbility: Skill Link\n- Shell Smash\n- Defog", DEFENDER, 5);
+
+        let (side, other_side, conditions) = state.split_mut(true);
+        sim.use_move(get_move("Shell Smash", 5), side, other_side, conditions);
+
+        let cloyster = state.side1.get_active();
+        assert_eq!(cloyster.volatile_status.stat_stages[..5], [2, -1, 2, -1, 2]);
+        assert_eq!(state.side2.get_active().volatile_status.stat_stages, [0; NUM_STATS]);
+
+        let (side, other_side, conditions) = state.split_mut(true);
+        sim.use_move(get_move("Defog", 5), side, other_side, conditions);
+
+        assert_eq!(state.side2.get_active().get_stat_stage(Stat::Evasion), -1);
+    }
+
+    #[test]
+    fn fake_out_only_works_on_the_first_turn() {
+
+        let recorder = EventRecorder::default();
+        let mut sim = Simulator::new(get_data_handler(), 5, 0);
+        sim.add_event_sink(Box::new(recorder.clone()));
+
+        let mut state = new_state("Ambipom\nLevel: 50\nAbility: Technician\n- Fake Out", DEFENDER, 5);
+
+        let (side, other_side, conditions) = state.split_mut(true);
+        sim.use_move(get_move("Fake Out", 5), side, other_side, conditions);
+
+        assert!(has_effect!(state.side2.get_active(), VolatileStatusEffect::Flinch));
+
+        state.side1.get_active_mut().volatile_status.turns_active = 1;
+        recorder.clear();
+
+        let (side, other_side, conditions) = state.split_mut(true);
+        assert!(!sim.use_move(get_move("Fake Out", 5), side, other_side, conditions));
+        assert!(recorder.events().iter().any(|event| matches!(event, BattleEvent::Failed)));
+    }
+
+    #[test]
+    fn knock_off_and_clear_smog() {
+
+        let recorder = EventRecorder::default();
+        let mut sim = Simulator::new(get_data_handler(), 6, 0);
+        sim.add_event_sink(Box::new(recorder.clone()));
+
+        let mut state = new_state("Weavile\nLevel: 50\nAbility: Pressure\n- Knock Off\n- Clear Smog", DEFENDER, 6);
+
+        let chansey = state.side2.get_active_mut();
+        chansey.item = Some(Item::Leftovers);
+        chansey.volatile_status.stat_stages[Stat::Defense as usize] = 2;
+
+        // knock off is stronger against a held item
+        let power = sim.get_move_power(get_move("Knock Off", 6), state.side1.get_active(), state.side2.get_active(), &state.conditions, 0);
+        assert_eq!(power, 97);
+
+        let (side, other_side, conditions) = state.split_mut(true);
+        sim.use_move(get_move("Knock Off", 6), side, other_side, conditions);
+
+        assert_eq!(state.side2.get_active().item, None);
+        assert!(recorder.events().iter().any(|event| matches!(event, BattleEvent::ItemKnockedOff {item: Item::Leftovers, ..})));
+
+        // sticky hold keeps the item
+        let chansey = state.side2.get_active_mut();
+        chansey.item = Some(Item::Leftovers);
+        chansey.ability = Ability::StickyHold;
+        chansey.hp = chansey.max_hp;
+
+        let (side, other_side, conditions) = state.split_mut(true);
+        sim.use_move(get_move("Knock Off", 6), side, other_side, conditions);
+        assert_eq!(state.side2.get_active().item, Some(Item::Leftovers));
+
+        let chansey = state.side2.get_active_mut();
+        chansey.hp = chansey.max_hp;
+
+        let (side, other_side, conditions) = state.split_mut(true);
+        sim.use_move(get_move("Clear Smog", 6), side, other_side, conditions);
+        assert_eq!(state.side2.get_active().get_stat_stage(Stat::Defense), 0);
+    }
+
+    #[test]
+    fn variable_power() {
+
+        let sim = Simulator::new(get_data_handler(), 5, 0);
+        let mut state = new_state("Blaziken\nLevel: 50\nAbility: Blaze\n- Reversal", "Snorlax\nLevel: 50\nAbility: Thick Fat\n- Growl", 5);
+
+        let get_power = |state: &BattleState, move_name: &str| {
+            sim.get_move_power(get_move(move_name, 5), state.side1.get_active(), state.side2.get_active(), &state.conditions, 0)
+        };
+
+        // snorlax weighs 460 kg against blaziken's 52
+        assert_eq!(get_power(&state, "Low Kick"), 120);
+        assert_eq!(get_power(&state, "Heavy Slam"), 40);
+        assert_eq!(get_power(&state, "Reversal"), 20);
+        assert_eq!(get_power(&state, "Eruption"), 150);
+        assert_eq!(get_power(&state, "Weather Ball"), 50);
+
+        let blaziken = state.side1.get_active_mut();
+        blaziken.hp = 1;
+        blaziken.volatile_status.stat_stages[Stat::Attack as usize] = 2;
+        blaziken.volatile_status.stat_stages[Stat::Speed as usize] = 1;
+        state.conditions.weather = Some((Weather::Sun, 5));
+
+        assert_eq!(get_power(&state, "Reversal"), 200);
+        assert_eq!(get_power(&state, "Eruption"), 1);
+        assert_eq!(get_power(&state, "Stored Power"), 80);
+        assert_eq!(get_power(&state, "Weather Ball"), 100);
+    }
+
+    #[test]
+    fn fixed_damage() {
+
+        let mut sim = Simulator::new(get_data_handler(), 5, 0);
+        let state = new_state("Alakazam\nLevel: 50\nAbility: Synchronize\n- Night Shade", DEFENDER, 5);
+
+        let alakazam = state.side1.get_active();
+        let chansey = state.side2.get_active();
+
+        assert_eq!(sim.get_fixed_damage(get_move("Seismic Toss", 5), alakazam, chansey), Some(50));
+        assert_eq!(sim.get_fixed_damage(get_move("Super Fang", 5), alakazam, chansey), Some(chansey.hp / 2));
+        assert_eq!(sim.get_fixed_damage(get_move("Dragon Rage", 5), alakazam, chansey), Some(40));
+        assert_eq!(sim.get_fixed_damage(get_move("Psychic", 5), alakazam, chansey), None);
+
+        // normal types are immune to night shade, except in gen 1
+        assert_eq!(sim.get_fixed_damage(get_move("Night Shade", 5), alakazam, chansey), Some(0));
+
+        let mut sim = Simulator::new(get_data_handler(), 1, 0);
+        assert_eq!(sim.get_fixed_damage(get_move("Night Shade", 1), alakazam, chansey), Some(50));
+    }
+
+    #[test]
+    fn rototiller_raises_grounded_grass_types() {
+
+        let mut sim = Simulator::new(get_data_handler(), 6, 0);
+        let mut state = new_state("Venusaur\nLevel: 50\nAbility: Overgrow\n- Rototiller", "Tropius\nLevel: 50\nAbility: Harvest\n- Growl", 6);
+
+        let (side, other_side, conditions) = state.split_mut(true);
+        sim.use_move(get_move("Rototiller", 6), side, other_side, conditions);
+
+        assert_eq!(state.side1.get_active().get_stat_stage(Stat::Attack), 1);
+        assert_eq!(state.side1.get_active().get_stat_stage(Stat::SpecialAttack), 1);
+        assert_eq!(state.side2.get_active().get_stat_stage(Stat::Attack), 0);
+
+        let (side, other_side, conditions) = state.split_mut(true);
+        sim.use_move(get_move("Flower Shield", 6), side, other_side, conditions);
+
+        assert_eq!(state.side1.get_active().get_stat_stage(Stat::Defense), 1);
+        assert_eq!(state.side2.get_active().get_stat_stage(Stat::Defense), 1);
+    }
+
+    // the names of the pokemon in the order they used their first moves
+    fn get_move_order(side1: &str, side2: &str, setup: impl Fn(&mut BattleState)) -> Vec<Box<str>> {
+
+        let recorder = EventRecorder::default();
+        let mut sim = Simulator::new(get_data_handler(), 5, 0);
+        sim.add_event_sink(Box::new(recorder.clone()));
+
+        let mut state = new_state(side1, side2, 5);
+        setup(&mut state);
+
+        let action1 = BattleAction::Move(state.side1.get_active().moves[0].as_ref().unwrap().id);
+        let action2 = BattleAction::Move(state.side2.get_active().moves[0].as_ref().unwrap().id);
+        sim.simulate_turn(action1, action2, &mut state, [&FirstChoiceController, &FirstChoiceController]);
+
+        recorder.events().into_iter().filter_map(|event| match event {
+            BattleEvent::MoveUsed {pokemon, ..} => Some(pokemon.name),
+            _ => None
+        }).collect()
+    }
+
+    #[test]
+    fn turn_order() {
+
+        const JOLTEON: &str = "Jolteon\nLevel: 50\nAbility: Volt Absorb\n- Growl";
+        const SNORLAX: &str = "Snorlax\nLevel: 50\nAbility: Thick Fat\n- Growl";
+        const SNORLAX_QUICK_ATTACK: &str = "Snorlax\nLevel: 50\nAbility: Thick Fat\n- Quick Attack";
+        const SABLEYE: &str = "Sableye\nLevel: 50\nAbility: Prankster\n- Growl";
+
+        let jolteon_first: [Box<str>; 2] = ["Jolteon".into(), "Snorlax".into()];
+        let snorlax_first: [Box<str>; 2] = ["Snorlax".into(), "Jolteon".into()];
+
+        assert_eq!(get_move_order(JOLTEON, SNORLAX, |_| {}), jolteon_first);
+        assert_eq!(get_move_order(JOLTEON, SNORLAX_QUICK_ATTACK, |_| {}), snorlax_first);
+        assert_eq!(get_move_order(JOLTEON, SNORLAX, |state| state.conditions.trick_room_turns = 5), snorlax_first);
+        assert_eq!(get_move_order(JOLTEON, SNORLAX, |state| state.side1.get_active_mut().item = Some(Item::LaggingTail)), snorlax_first);
+
+        // prankster puts growl in quick attack's bracket where sableye is faster
+        assert_eq!(get_move_order(SABLEYE, SNORLAX_QUICK_ATTACK, |_| {}), [Box::from("Sableye"), Box::from("Snorlax")]);
+    }
+
+    #[test]
+    fn legal_actions() {
+
+        let sim = Simulator::new(get_data_handler(), 5, 0);
+        let mut state = new_state(TEAM, DEFENDER, 5);
+        state.side1.team[1].hp = 0;
+
+        let growl = BattleAction::Move(get_move("Growl", 5).id);
+        let thunderbolt = BattleAction::Move(get_move("Thunderbolt", 5).id);
+
+        assert_eq!(sim.legal_actions(&state, true), [growl, thunderbolt, BattleAction::Switch(2)]);
+
+        state.side1.get_active_mut().moves[1].as_mut().unwrap().pp = 0;
+        assert_eq!(sim.legal_actions(&state, true), [growl, BattleAction::Switch(2)]);
+
+        state.side1.get_active_mut().moves[0].as_mut().unwrap().pp = 0;
+        assert_eq!(sim.legal_actions(&state, true), [BattleAction::Move(MOVE_STRUGGLE), BattleAction::Switch(2)]);
+
+        let mut state = new_state(TEAM, DEFENDER, 5);
+        state.side1.get_active_mut().volatile_status.add(VolatileStatusEffect::Taunt(3));
+        assert_eq!(sim.legal_actions(&state, true), [thunderbolt, BattleAction::Switch(1), BattleAction::Switch(2)]);
+    }
+
+    fn get_rampage_turns(pokemon: &Pokemon) -> Option<u8> {
+        pokemon.volatile_status.effects.iter().find_map(|effect| match effect {
+            VolatileStatusEffect::Rampage(turns) => Some(*turns),
+            _ => None
+        })
+    }
+
+    #[test]
+    fn rampage_length() {
+
+        // the turns left after the first
+        for (generation, expected) in [(1, [2, 3]), (2, [1, 2]), (5, [1, 2])] {
+
+            let mut sim = Simulator::new(get_data_handler(), generation, 1);
+            let mut state = new_state(ATTACKER, DEFENDER, generation);
+            let thrash = get_move("Thrash", generation);
+            let blastoise = state.side1.get_active_mut();
+
+            let mut seen = Vec::new();
+
+            for _ in 0..50 {
+
+                sim.update_locked_move(thrash, blastoise, false, true);
+
+                let turns = get_rampage_turns(blastoise).unwrap();
+                assert!(expected.contains(&turns), "gen {generation} {turns} turns");
+
+                if !seen.contains(&turns) {
+                    seen.push(turns);
+                }
+
+                remove_effect!(blastoise, VolatileStatusEffect::Rampage(_));
+            }
+
+            assert_eq!(seen.len(), 2, "gen {generation}");
+        }
+    }
+
+    #[test]
+    fn rampage_ends_in_confusion() {
+
+        // (generation, whether the last turn went through, confused afterwards)
+        let cases = [
+            (4, true, true),
+            (4, false, true),
+            (5, true, true),
+            (5, false, false)
+        ];
+
+        for (generation, succeeded, confused) in cases {
+
+            let mut sim = Simulator::new(get_data_handler(), generation, 0);
+            let mut state = new_state(ATTACKER, DEFENDER, generation);
+            let blastoise = state.side1.get_active_mut();
+            blastoise.volatile_status.add(VolatileStatusEffect::Rampage(1));
+
+            sim.update_locked_move(get_move("Outrage", generation), blastoise, true, succeeded);
+
+            assert_eq!(get_rampage_turns(blastoise), None);
+            assert_eq!(has_effect!(blastoise, VolatileStatusEffect::Confusion(_)), confused, "gen {generation} succeeded {succeeded}");
+        }
+    }
+
+    #[test]
+    fn rollout_doubles_each_turn() {
+
+        let mut sim = Simulator::new(get_data_handler(), 5, 0);
+        let mut state = new_state(ATTACKER, DEFENDER, 5);
+        let rollout = get_move("Rollout", 5);
+
+        let get_power = |sim: &Simulator, state: &BattleState| {
+            sim.get_move_power(rollout, state.side1.get_active(), state.side2.get_active(), &state.conditions, 0)
+        };
+
+        assert_eq!(get_power(&sim, &state), 30);
+
+        // the fifth hit ends the roll
+        for expected in [60, 120, 240, 480, 30] {
+            sim.update_locked_move(rollout, state.side1.get_active_mut(), true, true);
+            assert_eq!(get_power(&sim, &state), expected);
+        }
+
+        let blastoise = state.side1.get_active_mut();
+        blastoise.volatile_status.add(VolatileStatusEffect::DefenseCurl);
+        assert_eq!(get_power(&sim, &state), 60);
+
+        // a miss starts over
+        let blastoise = state.side1.get_active_mut();
+        sim.update_locked_move(rollout, blastoise, true, true);
+        sim.update_locked_move(rollout, blastoise, true, false);
+        assert!(!has_effect!(blastoise, VolatileStatusEffect::Rolling(_)));
+    }
+
+    #[test]
+    fn losing_the_choice_item_releases_the_lock() {
+
+        let mut sim = Simulator::new(get_data_handler(), 5, 0);
+        let mut state = new_state(ATTACKER, DEFENDER, 5);
+        state.side1.get_active_mut().item = Some(Item::ChoiceBand);
+
+        let swift = get_move("Swift", 5);
+        sim.update_locked_move(swift, state.side1.get_active_mut(), false, true);
+
+        assert_eq!(sim.legal_actions(&state, true), [BattleAction::Move(swift.id)]);
+
+        state.side1.get_active_mut().item = None;
+        assert_eq!(sim.legal_actions(&state, true).len(), 3);
+    }
+
+    #[test]
+    fn two_turn_moves_lock_the_user() {
+
+        let mut sim = Simulator::new(get_data_handler(), 5, 0);
+        let mut state = new_state(ATTACKER, DEFENDER, 5);
+        let solar_beam = get_move("Solar Beam", 5);
+
+        let (side, other_side, conditions) = state.split_mut(true);
+        assert!(sim.use_move(solar_beam, side, other_side, conditions));
+
+        let chansey = state.side2.get_active();
+        assert_eq!(chansey.hp, chansey.max_hp);
+        assert_eq!(state.side1.get_active().get_locked_move(), Some(solar_beam.id));
+        assert_eq!(sim.legal_actions(&state, true), [BattleAction::Move(solar_beam.id)]);
+
+        let (side, other_side, conditions) = state.split_mut(true);
+        assert!(sim.use_move(solar_beam, side, other_side, conditions));
+
+        let chansey = state.side2.get_active();
+        assert!(chansey.hp < chansey.max_hp);
+        assert_eq!(state.side1.get_active().get_locked_move(), None);
+    }
+
+    #[test]
+    fn power_herb_and_sun_skip_the_charge() {
+
+        let recorder = EventRecorder::default();
+        let mut sim = Simulator::new(get_data_handler(), 5, 0);
+        sim.add_event_sink(Box::new(recorder.clone()));
+
+        let mut state = new_state(ATTACKER, DEFENDER, 5);
+        state.side1.get_active_mut().item = Some(Item::PowerHerb);
+
+        let (side, other_side, conditions) = state.split_mut(true);
+        sim.use_move(get_move("Skull Bash", 5), side, other_side, conditions);
+
+        let chansey = state.side2.get_active();
+        assert!(chansey.hp < chansey.max_hp);
+        assert_eq!(state.side1.get_active().item, None);
+        assert_eq!(state.side1.get_active().get_locked_move(), None);
+        assert!(recorder.events().iter().any(|event| matches!(event, BattleEvent::ItemActivated {item: Item::PowerHerb, ..})));
+
+        // skull bash still raises defense on the way
+        assert_eq!(state.side1.get_active().get_stat_stage(Stat::Defense), 1);
+
+        let mut state = new_state(ATTACKER, DEFENDER, 5);
+        state.conditions.weather = Some((Weather::Sun, 5));
+
+        let (side, other_side, conditions) = state.split_mut(true);
+        sim.use_move(get_move("Solar Beam", 5), side, other_side, conditions);
+
+        let chansey = state.side2.get_active();
+        assert!(chansey.hp < chansey.max_hp);
+        assert_eq!(state.side1.get_active().get_locked_move(), None);
+    }
+
+    #[test]
+    fn earthquake_hits_dig_for_double() {
+
+        let mut sim = Simulator::new(get_data_handler(), 5, 0);
+        let mut state = new_state(ATTACKER, DEFENDER, 5);
+
+        let get_power = |sim: &Simulator, state: &BattleState| {
+            sim.get_move_power(get_move("Earthquake", 5), state.side1.get_active(), state.side2.get_active(), &state.conditions, 0)
+        };
+
+        assert_eq!(get_power(&sim, &state), 100);
+
+        let (side, other_side, conditions) = state.split_mut(false);
+        sim.use_move(get_move("Dig", 5), side, other_side, conditions);
+
+        assert!(has_effect!(state.side2.get_active(), VolatileStatusEffect::SemiInvulernable(SemiInvulnerableKind::Underground)));
+        assert_eq!(get_power(&sim, &state), 200);
+
+        let (side, other_side, conditions) = state.split_mut(true);
+        assert!(!sim.use_move(get_move("Body Slam", 5), side, other_side, conditions));
+
+        let (side, other_side, conditions) = state.split_mut(true);
+        assert!(sim.use_move(get_move("Earthquake", 5), side, other_side, conditions));
+
+        let chansey = state.side2.get_active();
+        assert!(chansey.hp < chansey.max_hp);
+    }
+
+    #[test]
+    fn hyper_beam_recharge() {
+
+        let recorder = EventRecorder::default();
+        let mut sim = Simulator::new(get_data_handler(), 5, 0);
+        sim.add_event_sink(Box::new(recorder.clone()));
+
+        let mut state = new_state(ATTACKER, DEFENDER, 5);
+        let hyper_beam = get_move("Hyper Beam", 5);
+
+        let (side, other_side, conditions) = state.split_mut(true);
+        sim.use_move(hyper_beam, side, other_side, conditions);
+
+        assert!(has_effect!(state.side1.get_active(), VolatileStatusEffect::Recharge));
+        assert_eq!(sim.legal_actions(&state, true), [BattleAction::Move(hyper_beam.id)]);
+
+        let blastoise = state.side1.get_active_mut();
+        assert!(!sim.can_move(hyper_beam, blastoise, &state.conditions));
+        assert!(!has_effect!(blastoise, VolatileStatusEffect::Recharge));
+        assert_eq!(cant_move_reasons(&recorder), [CantMoveReason::Recharge]);
+
+        // gen 1 skips the recharge after a ko
+        let mut sim = Simulator::new(get_data_handler(), 1, 0);
+        let mut state = new_state(ATTACKER, DEFENDER, 1);
+        state.side2.get_active_mut().hp = 1;
+
+        let (side, other_side, conditions) = state.split_mut(true);
+        sim.use_move(get_move("Hyper Beam", 1), side, other_side, conditions);
+
+        assert_eq!(state.side2.get_active().hp, 0);
+        assert!(!has_effect!(state.side1.get_active(), VolatileStatusEffect::Recharge));
+    }
+
+    #[test]
+    fn protect_chain_decay() {
+
+        // (generation, previous successes, expected chance)
+        let cases = [
+            (3, 0, 1.0),
+            (4, 1, 0.5),
+            (4, 5, 0.125),
+            (5, 2, 0.25),
+            (5, 5, 1.0 / 32.0),
+            (6, 1, 1.0 / 3.0),
+            (6, 2, 1.0 / 9.0)
+        ];
+
+        for (generation, chain, expected) in cases {
+
+            let mut sim = Simulator::new(get_data_handler(), generation, 1);
+            let mut state = new_state(ATTACKER, DEFENDER, generation);
+            let blastoise = state.side1.get_active_mut();
+
+            let trials = 4000;
+            let mut successes = 0;
+
+            for _ in 0..trials {
+
+                blastoise.volatile_status.protect_chain = chain;
+                remove_effect!(blastoise, VolatileStatusEffect::Protect(_));
+
+                sim.try_protect(blastoise, VolatileStatusEffect::Protect(ProtectKind::Protect));
+
+                if has_effect!(blastoise, VolatileStatusEffect::Protect(_)) {
+                    successes += 1;
+                }
+            }
+
+            let rate = successes as f64 / trials as f64;
+            assert!((rate - expected).abs() < 0.03, "gen {generation} chain {chain} rate {rate}");
+        }
+    }
+
+    #[test]
+    fn protection_punishes_contact() {
+
+        let cases = [
+            (ProtectKind::SpikyShield, None),
+            (ProtectKind::BanefulBunker, Some(NonVolatileStatus::Poison)),
+            (ProtectKind::BurningBulwark, Some(NonVolatileStatus::Burn))
+        ];
+
+        for (kind, status) in cases {
+            for (move_name, contact) in [("Body Slam", true), ("Hydro Pump", false)] {
+
+                let mut sim = Simulator::new(get_data_handler(), 9, 0);
+                let mut state = new_state(ATTACKER, DEFENDER, 9);
+                state.side2.get_active_mut().volatile_status.add(VolatileStatusEffect::Protect(kind));
+
+                let (side, other_side, conditions) = state.split_mut(true);
+                assert!(!sim.use_move(get_move(move_name, 9), side, other_side, conditions));
+
+                let blastoise = state.side1.get_active();
+                let chansey = state.side2.get_active();
+                assert_eq!(chansey.hp, chansey.max_hp);
+
+                if !contact {
+                    assert_eq!(blastoise.hp, blastoise.max_hp, "{kind:?} {move_name}");
+                    assert_eq!(blastoise.non_volatile_status, None, "{kind:?} {move_name}");
+                }
+                else if kind == ProtectKind::SpikyShield {
+                    assert_eq!(blastoise.hp, blastoise.max_hp - blastoise.max_hp / 8);
+                }
+                else {
+                    assert_eq!(blastoise.non_volatile_status, status, "{kind:?}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn feint_needs_protection_in_gen_4() {
+
+        for (generation, works) in [(4, false), (5, true)] {
+
+            let recorder = EventRecorder::default();
+            let mut sim = Simulator::new(get_data_handler(), generation, 0);
+            sim.add_event_sink(Box::new(recorder.clone()));
+
+            let mut state = new_state(ATTACKER, DEFENDER, generation);
+
+            let (side, other_side, conditions) = state.split_mut(true);
+            assert_eq!(sim.use_move(get_move("Feint", generation), side, other_side, conditions), works, "gen {generation}");
+
+            let chansey = state.side2.get_active();
+            assert_eq!(chansey.hp < chansey.max_hp, works, "gen {generation}");
+
+            // breaking through protect works in both
+            state.side2.get_active_mut().volatile_status.add(VolatileStatusEffect::Protect(ProtectKind::Protect));
+
+            let (side, other_side, conditions) = state.split_mut(true);
+            assert!(sim.use_move(get_move("Feint", generation), side, other_side, conditions), "gen {generation}");
+            assert!(!has_effect!(state.side2.get_active(), VolatileStatusEffect::Protect(_)));
+        }
+    }
+
+    fn get_substitute_hp(pokemon: &Pokemon) -> Option<u16> {
+        pokemon.volatile_status.effects.iter().find_map(|effect| match effect {
+            VolatileStatusEffect::Substitute(hp) => Some(*hp),
+            _ => None
+        })
+    }
+
+    #[test]
+    fn substitute_absorbs_damage() {
+
+        let mut sim = Simulator::new(get_data_handler(), 5, 0);
+        let mut state = new_state(ATTACKER, DEFENDER, 5);
+
+        let (side, other_side, conditions) = state.split_mut(false);
+        sim.use_move(get_move("Substitute", 5), side, other_side, conditions);
+
+        let chansey = state.side2.get_active();
+        let substitute_hp = chansey.max_hp / 4;
+        assert_eq!(chansey.hp, chansey.max_hp - substitute_hp);
+        assert_eq!(get_substitute_hp(chansey), Some(substitute_hp));
+
+        let (side, other_side, conditions) = state.split_mut(true);
+        sim.use_move(get_move("Body Slam", 5), side, other_side, conditions);
+
+        let chansey = state.side2.get_active();
+        assert_eq!(chansey.hp, chansey.max_hp - substitute_hp);
+        assert!(get_substitute_hp(chansey).is_none_or(|hp| hp < substitute_hp));
+    }
+
+    #[test]
+    fn sound_moves_and_infiltrator_bypass_substitute() {
+
+        // (generation, attacker ability, move, goes through)
+        let cases = [
+            (5, Ability::Torrent, "Hyper Voice", false),
+            (6, Ability::Torrent, "Hyper Voice", true),
+            (6, Ability::Torrent, "Body Slam", false),
+            (5, Ability::Infiltrator, "Body Slam", false),
+            (6, Ability::Infiltrator, "Body Slam", true)
+        ];
+
+        for (generation, ability, move_name, bypassed) in cases {
+
+            let mut sim = Simulator::new(get_data_handler(), generation, 0);
+            let mut state = new_state(ATTACKER, DEFENDER, generation);
+            state.side1.get_active_mut().ability = ability;
+
+            let chansey = state.side2.get_active_mut();
+            chansey.volatile_status.add(VolatileStatusEffect::Substitute(chansey.max_hp / 4));
+
+            let (side, other_side, conditions) = state.split_mut(true);
+            sim.use_move(get_move(move_name, generation), side, other_side, conditions);
+
+            let chansey = state.side2.get_active();
+            assert_eq!(chansey.hp < chansey.max_hp, bypassed, "gen {generation} {ability:?} {move_name}");
+            assert_eq!(get_substitute_hp(chansey) == Some(chansey.max_hp / 4), bypassed, "gen {generation} {ability:?} {move_name}");
+        }
+    }
+
+    #[test]
+    fn passing_a_substitute() {
+
+        let sim = Simulator::new(get_data_handler(), 9, 0);
+        let mut state = new_state(ATTACKER, DEFENDER, 9);
+
+        let blastoise = state.side1.get_active_mut();
+        blastoise.volatile_status.add(VolatileStatusEffect::Substitute(40));
+        blastoise.apply_stat_changes(Stat::Attack, 2);
+
+        let passed = sim.get_passed_volatiles(state.side1.get_active(), SwitchOut::BatonPass);
+        assert!(passed.effects.iter().any(|effect| matches!(effect, VolatileStatusEffect::Substitute(40))));
+        assert_eq!(passed.stat_stages[Stat::Attack as usize], 2);
+
+        let passed = sim.get_passed_volatiles(state.side1.get_active(), SwitchOut::Pivot);
+        assert!(passed.effects.is_empty());
+        assert_eq!(passed.stat_stages[Stat::Attack as usize], 0);
+
+        // shed tail only passes the new substitute
+        let passed = sim.get_passed_volatiles(state.side1.get_active(), SwitchOut::ShedTail(25));
+        assert_eq!(passed.effects.len(), 1);
+        assert!(matches!(passed.effects[0], VolatileStatusEffect::Substitute(25)));
+        assert_eq!(passed.stat_stages[Stat::Attack as usize], 0);
+    }
+
+    #[test]
+    fn screen_durations() {
+
+        for (item, expected) in [(None, 5), (Some(Item::LightClay), 8)] {
+
+            let mut sim = Simulator::new(get_data_handler(), 5, 0);
+            let mut state = new_state(ATTACKER, DEFENDER, 5);
+            state.side1.get_active_mut().item = item;
+
+            for screen in ["Reflect", "Light Screen"] {
+                let (side, other_side, conditions) = state.split_mut(true);
+                sim.use_move(get_move(screen, 5), side, other_side, conditions);
+            }
+
+            assert_eq!(state.side1.effects.get_turns(SideCondition::Reflect), expected);
+            assert_eq!(state.side1.effects.get_turns(SideCondition::LightScreen), expected);
+
+            for _ in 0..expected - 1 {
+                sim.on_turn_end(&mut state);
+            }
+
+            assert!(state.side1.effects.is_active(SideCondition::Reflect));
+
+            sim.on_turn_end(&mut state);
+            assert!(!state.side1.effects.is_active(SideCondition::Reflect));
+            assert!(!state.side1.effects.is_active(SideCondition::LightScreen));
+        }
+    }
+
+    #[test]
+    fn crits_and_infiltrator_bypass_screens() {
+
+        let get_damage = |generation: u8, ability: Ability, crit: bool, reflect: bool| {
+
+            let mut sim = Simulator::new(get_data_handler(), generation, 0);
+            let mut state = new_state(ATTACKER, DEFENDER, generation);
+            state.side1.get_active_mut().ability = ability;
+
+            if reflect {
+                state.side2.effects.set_turns(SideCondition::Reflect, 5);
+            }
+
+            sim.calc_damage(get_move("Body Slam", generation), state.side1.get_active(), &state.side2, &state.conditions, crit, 0, false)
+        };
+
+        assert!(get_damage(6, Ability::Torrent, false, true) < get_damage(6, Ability::Torrent, false, false));
+        assert_eq!(get_damage(6, Ability::Torrent, true, true), get_damage(6, Ability::Torrent, true, false));
+
+        // infiltrator only ignores screens from gen 6
+        assert_eq!(get_damage(6, Ability::Infiltrator, false, true), get_damage(6, Ability::Infiltrator, false, false));
+        assert!(get_damage(5, Ability::Infiltrator, false, true) < get_damage(5, Ability::Infiltrator, false, false));
+    }
+
+    #[test]
+    fn safeguard_and_mist() {
+
+        for (ability, blocked) in [(Ability::Torrent, true), (Ability::Infiltrator, false)] {
+
+            let recorder = EventRecorder::default();
+            let mut sim = Simulator::new(get_data_handler(), 6, 0);
+            sim.add_event_sink(Box::new(recorder.clone()));
+
+            let mut state = new_state(ATTACKER, DEFENDER, 6);
+            state.side1.get_active_mut().ability = ability;
+            state.side2.effects.set_turns(SideCondition::Safeguard, 5);
+            state.side2.effects.set_turns(SideCondition::Mist, 5);
+
+            for move_name in ["Spore", "Growl"] {
+                let (side, other_side, conditions) = state.split_mut(true);
+                sim.use_move(get_move(move_name, 6), side, other_side, conditions);
+            }
+
+            let chansey = state.side2.get_active();
+            assert_eq!(chansey.non_volatile_status.is_none(), blocked, "{ability:?}");
+            assert_eq!(chansey.get_stat_stage(Stat::Attack) == 0, blocked, "{ability:?}");
+
+            let blocks = recorder.events().iter().filter(|event| matches!(event, BattleEvent::SideConditionBlocked {..})).count();
+            assert_eq!(blocks, if blocked {2} else {0});
+        }
+    }
+
+    // sends the first pokemon of the team into the given hazards and returns it
+    fn enter_hazards(team: &str, set_hazards: impl Fn(&mut SideEffects)) -> Pokemon {
+
+        let mut sim = Simulator::new(get_data_handler(), 8, 0);
+        let mut state = new_state(team, DEFENDER, 8);
+        set_hazards(&mut state.side1.effects);
+
+        let (side, _, conditions) = state.split_mut(true);
+        sim.apply_entry_hazards(side, conditions);
+
+        state.side1.get_active().clone()
+    }
+
+    #[test]
+    fn stealth_rock_scales_with_type() {
+
+        // (pokemon, fraction of max hp lost)
+        let cases = [
+            ("Blastoise\nLevel: 50\nAbility: Torrent\n- Surf", 8),
+            ("Charizard\nLevel: 50\nAbility: Blaze\n- Ember", 2),
+            ("Steelix\nLevel: 50\nAbility: Sturdy\n- Tackle", 32)
+        ];
+
+        for (team, fraction) in cases {
+            let mon = enter_hazards(team, |effects| effects.set_stealth_rock(true));
+            assert_eq!(mon.hp, mon.max_hp - mon.max_hp / fraction, "{}", mon.name);
+        }
+    }
+
+    #[test]
+    fn spikes_layers() {
+
+        for (layers, fraction) in [(1, 8), (2, 6), (3, 4)] {
+            let mon = enter_hazards(ATTACKER, |effects| effects.set_spikes(layers));
+            assert_eq!(mon.hp, mon.max_hp - mon.max_hp / fraction, "{layers} layers");
+        }
+
+        // flying types aren't grounded
+        let mon = enter_hazards("Charizard\nLevel: 50\nAbility: Blaze\n- Ember", |effects| effects.set_spikes(3));
+        assert_eq!(mon.hp, mon.max_hp);
+    }
+
+    #[test]
+    fn toxic_spikes() {
+
+        let mon = enter_hazards(ATTACKER, |effects| effects.set_toxic_spikes(1));
+        assert_eq!(mon.non_volatile_status, Some(NonVolatileStatus::Poison));
+
+        let mon = enter_hazards(ATTACKER, |effects| effects.set_toxic_spikes(2));
+        assert_eq!(mon.non_volatile_status, Some(NonVolatileStatus::BadlyPoison(1)));
+
+        let mon = enter_hazards("Charizard\nLevel: 50\nAbility: Blaze\n- Ember", |effects| effects.set_toxic_spikes(2));
+        assert_eq!(mon.non_volatile_status, None);
+
+        // grounded poison types absorb them
+        let mut sim = Simulator::new(get_data_handler(), 8, 0);
+        let mut state = new_state("Muk\nLevel: 50\nAbility: Stench\n- Sludge", DEFENDER, 8);
+        state.side1.effects.set_toxic_spikes(2);
+
+        let (side, _, conditions) = state.split_mut(true);
+        sim.apply_entry_hazards(side, conditions);
+
+        assert_eq!(state.side1.get_active().non_volatile_status, None);
+        assert_eq!(state.side1.effects.get_toxic_spikes(), 0);
+    }
+
+    #[test]
+    fn heavy_duty_boots_ignore_hazards() {
+
+        let team = "Charizard @ Heavy-Duty Boots\nLevel: 50\nAbility: Blaze\n- Ember";
+
+        let mon = enter_hazards(team, |effects| {
+            effects.set_stealth_rock(true);
+            effects.set_spikes(3);
+            effects.set_toxic_spikes(2);
+            effects.set_sticky_web(true);
+        });
+
+        assert_eq!(mon.item, Some(Item::HeavyDutyBoots));
+        assert_eq!(mon.hp, mon.max_hp);
+        assert_eq!(mon.non_volatile_status, None);
+        assert_eq!(mon.get_stat_stage(Stat::Speed), 0);
+    }
+
+    fn cant_move_reasons(recorder: &EventRecorder) -> Vec<CantMoveReason> {
+        recorder.events().iter().filter_map(|event| match event {
+            BattleEvent::CantMove {reason, ..} => Some(*reason),
+            _ => None
+        }).collect()
+    }
+
+    #[test]
+    fn sleep_counter_and_early_bird() {
+
+        let recorder = EventRecorder::default();
+        let mut sim = Simulator::new(get_data_handler(), 5, 0);
+        sim.add_event_sink(Box::new(recorder.clone()));
+
+        let mut state = new_state(ATTACKER, DEFENDER, 5);
+        let swift = get_move("Swift", 5);
+
+        let blastoise = state.side1.get_active_mut();
+        blastoise.non_volatile_status = Some(NonVolatileStatus::Sleep(2));
+
+        assert!(!sim.can_move(swift, blastoise, &state.conditions));
+        assert_eq!(blastoise.non_volatile_status, Some(NonVolatileStatus::Sleep(1)));
+        assert_eq!(cant_move_reasons(&recorder), [CantMoveReason::Sleep]);
+
+        // waking up doesn't cost the turn after gen 1
+        assert!(sim.can_move(swift, blastoise, &state.conditions));
+        assert_eq!(blastoise.non_volatile_status, None);
+
+        // early bird counts down twice as fast
+        recorder.clear();
+        blastoise.ability = Ability::EarlyBird;
+        blastoise.non_volatile_status = Some(NonVolatileStatus::Sleep(3));
+
+        assert!(!sim.can_move(swift, blastoise, &state.conditions));
+        assert_eq!(blastoise.non_volatile_status, Some(NonVolatileStatus::Sleep(1)));
+        assert!(sim.can_move(swift, blastoise, &state.conditions));
+        assert_eq!(cant_move_reasons(&recorder), [CantMoveReason::Sleep]);
+    }
+
+    #[test]
+    fn gen_1_waking_up_uses_the_turn() {
+
+        let recorder = EventRecorder::default();
+        let mut sim = Simulator::new(get_data_handler(), 1, 0);
+        sim.add_event_sink(Box::new(recorder.clone()));
+
+        let mut state = new_state(ATTACKER, DEFENDER, 1);
+        let blastoise = state.side1.get_active_mut();
+        blastoise.non_volatile_status = Some(NonVolatileStatus::Sleep(1));
+
+        assert!(!sim.can_move(get_move("Swift", 1), blastoise, &state.conditions));
+        assert_eq!(blastoise.non_volatile_status, None);
+        assert!(recorder.events().iter().any(|event| matches!(event, BattleEvent::StatusCured {..})));
+        assert!(cant_move_reasons(&recorder).is_empty());
+    }
+
+    #[test]
+    fn defrost_moves_thaw_the_user() {
+
+        let recorder = EventRecorder::default();
+        let mut sim = Simulator::new(get_data_handler(), 2, 0);
+        sim.add_event_sink(Box::new(recorder.clone()));
+
+        let mut state = new_state(ATTACKER, DEFENDER, 2);
+        let blastoise = state.side1.get_active_mut();
+
+        // gen 2 never thaws at random before moving
+        for _ in 0..20 {
+            blastoise.non_volatile_status = Some(NonVolatileStatus::Freeze);
+            assert!(!sim.can_move(get_move("Swift", 2), blastoise, &state.conditions));
+        }
+
+        assert!(cant_move_reasons(&recorder).iter().all(|reason| *reason == CantMoveReason::Freeze));
+
+        assert!(sim.can_move(get_move("Flame Wheel", 2), blastoise, &state.conditions));
+        assert_eq!(blastoise.non_volatile_status, None);
+    }
+
+    #[test]
+    fn confusion_self_hit_rate() {
+
+        for (generation, expected) in [(6, 0.5), (7, 1.0 / 3.0)] {
+
+            let recorder = EventRecorder::default();
+            let mut sim = Simulator::new(get_data_handler(), generation, 1);
+            sim.add_event_sink(Box::new(recorder.clone()));
+
+            let mut state = new_state(ATTACKER, DEFENDER, generation);
+            let swift = get_move("Swift", generation);
+            let blastoise = state.side1.get_active_mut();
+
+            let trials = 3000;
+
+            for _ in 0..trials {
+                blastoise.hp = blastoise.max_hp;
+                blastoise.volatile_status.add(VolatileStatusEffect::Confusion(5));
+                sim.can_move(swift, blastoise, &state.conditions);
+                remove_effect!(blastoise, VolatileStatusEffect::Confusion(_));
+            }
+
+            let hits = recorder.events().iter().filter(|event| matches!(event, BattleEvent::IndirectDamage {cause: DamageCause::Confusion, ..})).count();
+            let rate = hits as f64 / trials as f64;
+
+            assert!((rate - expected).abs() < 0.04, "gen {generation} rate {rate}");
+        }
+    }
+
+    #[test]
+    fn steadfast_raises_speed_on_flinch() {
+
+        for (generation, expected) in [(3, 0), (4, 1)] {
+
+            let recorder = EventRecorder::default();
+            let mut sim = Simulator::new(get_data_handler(), generation, 0);
+            sim.add_event_sink(Box::new(recorder.clone()));
+
+            let mut state = new_state(ATTACKER, DEFENDER, generation);
+            let blastoise = state.side1.get_active_mut();
+            blastoise.ability = Ability::Steadfast;
+            blastoise.volatile_status.add(VolatileStatusEffect::Flinch);
+
+            assert!(!sim.can_move(get_move("Swift", generation), blastoise, &state.conditions));
+            assert_eq!(blastoise.get_stat_stage(Stat::Speed), expected, "gen {generation}");
+            assert_eq!(cant_move_reasons(&recorder), [CantMoveReason::Flinch]);
+        }
+    }
+
+    #[test]
+    fn running_out_of_pp_leaves_struggle() {
+
+        let recorder = EventRecorder::default();
+        let mut sim = Simulator::new(get_data_handler(), 5, 0);
+        sim.add_event_sink(Box::new(recorder.clone()));
+
+        let mut state = new_state("Jolteon\nLevel: 50\nAbility: Volt Absorb\n- Growl", DEFENDER, 5);
+        let growl = get_move("Growl", 5).id;
+        state.side1.get_active_mut().moves[0].as_mut().unwrap().pp = 2;
+
+        for _ in 0..2 {
+            sim.perform_action(BattleAction::Move(growl), true, &mut state);
+        }
+
+        assert_eq!(state.side1.get_active().get_move_slot(growl).unwrap().pp, 0);
+        assert_eq!(sim.legal_actions(&state, true), [BattleAction::Move(MOVE_STRUGGLE)]);
+
+        // a stale choice of the empty move turns into struggle
+        recorder.clear();
+        sim.perform_action(BattleAction::Move(growl), true, &mut state);
+        assert!(recorder.events().iter().any(|event| matches!(event, BattleEvent::MoveUsed {move_id: MOVE_STRUGGLE, ..})));
+    }
+
+    #[test]
+    fn pressure_and_leppa_berry() {
+
+        for (generation, expected) in [(2, 14), (3, 13), (5, 13)] {
+
+            let mut sim = Simulator::new(get_data_handler(), generation, 0);
+            let mut state = new_state(ATTACKER, DEFENDER, generation);
+            state.side2.get_active_mut().ability = Ability::Pressure;
+
+            let swift = get_move("Swift", generation);
+            state.side1.get_active_mut().get_move_slot_mut(swift.id).unwrap().pp = 15;
+
+            let (side, other_side, conditions) = state.split_mut(true);
+            sim.use_move(swift, side, other_side, conditions);
+
+            assert_eq!(state.side1.get_active().get_move_slot(swift.id).unwrap().pp, expected, "gen {generation}");
+        }
+
+        let recorder = EventRecorder::default();
+        let mut sim = Simulator::new(get_data_handler(), 5, 0);
+        sim.add_event_sink(Box::new(recorder.clone()));
+
+        let mut state = new_state(ATTACKER, DEFENDER, 5);
+        state.side2.get_active_mut().ability = Ability::Pressure;
+
+        let blastoise = state.side1.get_active_mut();
+        let swift = get_move("Swift", 5);
+        blastoise.item = Some(Item::LeppaBerry);
+        blastoise.get_move_slot_mut(swift.id).unwrap().pp = 2;
+
+        let (side, other_side, conditions) = state.split_mut(true);
+        sim.use_move(swift, side, other_side, conditions);
+
+        assert_eq!(state.side1.get_active().get_move_slot(swift.id).unwrap().pp, 10);
+        assert_eq!(state.side1.get_active().item, None);
+        assert!(recorder.events().iter().any(|event| matches!(event, BattleEvent::ItemActivated {item: Item::LeppaBerry, ..})));
+    }
+
+    #[test]
+    fn trapped_pokemon_cant_switch() {
+
+        let mut sim = Simulator::new(get_data_handler(), 5, 0);
+        let mut state = new_state(TEAM, DEFENDER, 5);
+        state.side1.get_active_mut().volatile_status.add(VolatileStatusEffect::Trapped);
+
+        assert!(!sim.legal_actions(&state, true).iter().any(|action| matches!(action, BattleAction::Switch(_))));
+
+        // a switch chosen before the trap still doesn't go through
+        sim.perform_action(BattleAction::Switch(1), true, &mut state);
+        assert_eq!(state.side1.active_pokemon, 0);
+
+        let mut state = new_state(TEAM, "Wobbuffet\nLevel: 50\nAbility: Shadow Tag\n- Counter", 5);
+        assert_eq!(sim.legal_actions(&state, true).len(), 2);
+
+        sim.perform_action(BattleAction::Switch(1), true, &mut state);
+        assert_eq!(state.side1.active_pokemon, 0);
+
+        // ghosts can't be trapped from gen 6
+        let mut state = new_state("Gengar\nLevel: 50\nAbility: Levitate\n- Curse\n\nSnorlax\nLevel: 50\nAbility: Thick Fat\n- Growl", "Wobbuffet\nLevel: 50\nAbility: Shadow Tag\n- Counter", 6);
+        let mut sim = Simulator::new(get_data_handler(), 6, 0);
+        sim.perform_action(BattleAction::Switch(1), true, &mut state);
+        assert_eq!(state.side1.active_pokemon, 1);
+    }
+
+    // always asks for the first pokemon on the team whether or not it can come in
+    struct FirstSlotController;
+
+    impl Controller for FirstSlotController {
+
+        fn get_action(&self, _state: &BattleState, _is_side1: bool, legal_actions: &[BattleAction]) -> BattleAction {
+            legal_actions[0]
+        }
+
+        fn get_switch_in(&self, _state: &BattleState, _is_side1: bool) -> u8 {
+            0
+        }
+    }
+
+    #[test]
+    fn invalid_switch_ins_are_replaced() {
+
+        let mut sim = Simulator::new(get_data_handler(), 5, 0);
+
+        let mut state = new_state(TEAM, DEFENDER, 5);
+        state.side1.team[0].hp = 0;
+        state.side1.team[1].hp = 0;
+
+        sim.replace_fainted(&mut state, [&FirstSlotController, &FirstSlotController]);
+        assert_eq!(state.side1.active_pokemon, 2);
+        assert_eq!(state.side2.active_pokemon, 0);
+
+        let mut state = new_state(TEAM, DEFENDER, 5);
+        state.side1.team[1].hp = 0;
+        state.side1.pending_switch = Some(SwitchOut::Pivot);
+
+        assert!(sim.resolve_pending_switch(true, &mut state, [&FirstSlotController, &FirstSlotController]));
+        assert_eq!(state.side1.active_pokemon, 2);
+    }
+
+    #[test]
+    fn pivot_moves_prompt_a_switch() {
+
+        for move_name in ["U-turn", "Volt Switch"] {
+
+            let mut sim = Simulator::new(get_data_handler(), 5, 0);
+            let mut state = new_state(TEAM, DEFENDER, 5);
+
+            let (side, other_side, conditions) = state.split_mut(true);
+            sim.use_move(get_move(move_name, 5), side, other_side, conditions);
+
+            assert_eq!(state.side1.pending_switch, Some(SwitchOut::Pivot), "{move_name}");
+            assert!(sim.resolve_pending_switch(true, &mut state, [&FirstChoiceController, &FirstChoiceController]));
+            assert_eq!(state.side1.active_pokemon, 1);
+
+            // nothing to switch to
+            let mut state = new_state(ATTACKER, DEFENDER, 5);
+
+            let (side, other_side, conditions) = state.split_mut(true);
+            sim.use_move(get_move(move_name, 5), side, other_side, conditions);
+
+            assert_eq!(state.side1.pending_switch, None, "{move_name}");
+        }
+    }
+
+    #[test]
+    fn forcing_moves_pick_a_random_reserve() {
+
+        for move_name in ["Roar", "Dragon Tail"] {
+
+            let mut sim = Simulator::new(get_data_handler(), 5, 1);
+            let mut replacements = Vec::new();
+
+            for _ in 0..30 {
+
+                let mut state = new_state(ATTACKER, TEAM, 5);
+
+                // dragon tail can miss
+                state.side1.get_active_mut().ability = Ability::NoGuard;
+
+                let (side, other_side, conditions) = state.split_mut(true);
+                sim.use_move(get_move(move_name, 5), side, other_side, conditions);
+
+                assert_eq!(state.side2.pending_switch, Some(SwitchOut::Forced), "{move_name}");
+
+                // the controller doesn't get a say
+                assert!(sim.resolve_pending_switch(false, &mut state, [&FirstSlotController, &FirstSlotController]));
+
+                if !replacements.contains(&state.side2.active_pokemon) {
+                    replacements.push(state.side2.active_pokemon);
+                }
+            }
+
+            replacements.sort();
+            assert_eq!(replacements, [1, 2], "{move_name}");
+
+            // suction cups holds on
+            let mut state = new_state(ATTACKER, TEAM, 5);
+            state.side2.get_active_mut().ability = Ability::SuctionCups;
+
+            let (side, other_side, conditions) = state.split_mut(true);
+            sim.use_move(get_move(move_name, 5), side, other_side, conditions);
+
+            assert_eq!(state.side2.pending_switch, None, "{move_name}");
+        }
+    }
+
+    #[test]
+    fn eject_button_cancels_the_pivot() {
+
+        let mut sim = Simulator::new(get_data_handler(), 5, 0);
+
+        let side1 = "Jolteon\nLevel: 50\nAbility: Volt Absorb\n- Volt Switch\n\nSnorlax\nLevel: 50\nAbility: Thick Fat\n- Growl";
+        let side2 = "Chansey @ Eject Button\nLevel: 100\nAbility: Natural Cure\n- Soft-Boiled\n\nLapras\nLevel: 50\nAbility: Water Absorb\n- Growl";
+
+        let mut state = new_state(side1, side2, 5);
+
+        let volt_switch = BattleAction::Move(get_move("Volt Switch", 5).id);
+        let soft_boiled = BattleAction::Move(get_move("Soft-Boiled", 5).id);
+
+        sim.simulate_turn(volt_switch, soft_boiled, &mut state, [&FirstChoiceController, &FirstChoiceController]);
+
+        assert_eq!(state.side2.active_pokemon, 1);
+        assert_eq!(state.side2.team[0].item, None);
+        assert_eq!(state.side1.active_pokemon, 0);
+        assert_eq!(state.side1.pending_switch, None);
+    }
+
+    #[test]
+    fn switch_in_abilities_activate_in_speed_order() {
+
+        let politoed = "Politoed\nLevel: 50\nAbility: Drizzle\n- Surf";
+        let ninetales = "Ninetales\nLevel: 50\nAbility: Drought\n- Ember";
+
+        // the slower pokemon's weather wins
+        for (side1, side2, expected) in [(politoed, ninetales, Weather::Rain), (ninetales, politoed, Weather::Rain)] {
+
+            let mut sim = Simulator::new(get_data_handler(), 5, 0);
+            let mut state = new_state(side1, side2, 5);
+
+            sim.activate_switch_in_abilities([true, true], &mut state);
+            assert_eq!(state.conditions.get_weather(), Some(expected));
+
+            // only the side that switched in activates
+            let mut state = new_state(side1, side2, 5);
+
+            sim.activate_switch_in_abilities([false, true], &mut state);
+            assert_eq!(state.conditions.get_weather(), Some(if side2 == politoed {Weather::Rain} else {Weather::Sun}));
+        }
+    }
+
+    #[test]
+    fn trace_copies_traceable_abilities() {
+
+        let gardevoir = "Gardevoir\nLevel: 50\nAbility: Trace\n- Psychic";
+
+        for (ability, traced) in [(Ability::ThickFat, true), (Ability::Trace, false), (Ability::Multitype, false), (Ability::Illusion, false)] {
+
+            let mut sim = Simulator::new(get_data_handler(), 5, 0);
+            let mut state = new_state(gardevoir, "Snorlax\nLevel: 50\nAbility: Thick Fat\n- Growl", 5);
+            state.side2.get_active_mut().ability = ability;
+
+            sim.activate_switch_in_abilities([true, false], &mut state);
+
+            let expected = if traced {ability} else {Ability::Trace};
+            assert_eq!(sim.get_ability(state.side1.get_active(), &state.conditions), expected, "{ability:?}");
+        }
+
+        // forecast only became traceable in gen 4
+        assert!(!Simulator::new(get_data_handler(), 3, 0).can_be_traced(Ability::Forecast));
+        assert!(Simulator::new(get_data_handler(), 4, 0).can_be_traced(Ability::Forecast));
+    }
+
+    #[test]
+    fn download_picks_the_weaker_defense() {
+
+        let porygon = "Porygon2\nLevel: 50\nAbility: Download\n- Tri Attack";
+
+        // chansey has far less defense than special defense, cloyster the other way around
+        for (foe, stat) in [(DEFENDER, Stat::Attack), ("Cloyster\nLevel: 50\nAbility: Shell Armor\n- Clamp", Stat::SpecialAttack)] {
+
+            let mut sim = Simulator::new(get_data_handler(), 5, 0);
+            let mut state = new_state(porygon, foe, 5);
+
+            sim.activate_switch_in_abilities([true, false], &mut state);
+
+            let porygon = state.side1.get_active();
+            assert_eq!(porygon.get_stat_stage(stat), 1, "{stat:?}");
+            assert_eq!(porygon.get_stat_stage(Stat::Attack) + porygon.get_stat_stage(Stat::SpecialAttack), 1);
+        }
+    }
+
+    #[test]
+    fn intrepid_sword_once_per_battle_in_gen_9() {
+
+        for (generation, expected) in [(8, 2), (9, 1)] {
+
+            let mut sim = Simulator::new(get_data_handler(), generation, 0);
+            let mut state = new_state(ATTACKER, DEFENDER, generation);
+            state.side1.get_active_mut().ability = Ability::IntrepidSword;
+
+            for _ in 0..2 {
+                sim.activate_switch_in_abilities([true, false], &mut state);
+            }
+
+            assert_eq!(state.side1.get_active().get_stat_stage(Stat::Attack), expected, "gen {generation}");
+        }
+    }
+
+    #[test]
+    fn neutralizing_gas_layers() {
+
+        let recorder = EventRecorder::default();
+        let mut sim = Simulator::new(get_data_handler(), 8, 0);
+        sim.add_event_sink(Box::new(recorder.clone()));
+
+        let mut state = new_state(ATTACKER, TEAM, 8);
+        state.side1.get_active_mut().ability = Ability::NeutralizingGas;
+        state.side2.get_active_mut().ability = Ability::NeutralizingGas;
+
+        let update = |sim: &mut Simulator, state: &mut BattleState| {
+            let (side, other_side, conditions) = state.split_mut(true);
+            sim.update_field_abilities(side, other_side, conditions);
+        };
+
+        update(&mut sim, &mut state);
+        assert!(state.conditions.neutralizing_gas);
+
+        // the gas stays while either user is still out
+        state.side2.get_active_mut().hp = 0;
+        update(&mut sim, &mut state);
+        assert!(state.conditions.neutralizing_gas);
+        assert_eq!(sim.get_ability(&state.side2.team[1], &state.conditions), Ability::None);
+
+        // unsuppressable abilities and the ability shield are untouched
+        let mut shielded = state.side2.team[1].clone();
+        shielded.item = Some(Item::AbilityShield);
+        assert_eq!(sim.get_ability(&shielded, &state.conditions), Ability::ThickFat);
+        shielded.ability = Ability::Multitype;
+        shielded.item = None;
+        assert_eq!(sim.get_ability(&shielded, &state.conditions), Ability::Multitype);
+
+        assert!(!recorder.events().iter().any(|event| matches!(event, BattleEvent::NeutralizingGasEnded)));
+
+        state.side1.get_active_mut().hp = 0;
+        update(&mut sim, &mut state);
+        assert!(!state.conditions.neutralizing_gas);
+        assert!(recorder.events().iter().any(|event| matches!(event, BattleEvent::NeutralizingGasEnded)));
+        assert_eq!(sim.get_ability(&state.side2.team[1], &state.conditions), Ability::ThickFat);
+    }
+
+    #[test]
+    fn mold_breaker_ignores_abilities_for_the_move() {
+
+        for (ability, forced_out) in [(Ability::Torrent, false), (Ability::MoldBreaker, true)] {
+
+            let mut sim = Simulator::new(get_data_handler(), 6, 0);
+            let mut state = new_state("Blastoise\nLevel: 50\nAbility: Torrent\n- Roar", TEAM, 6);
+            state.side1.get_active_mut().ability = ability;
+            state.side2.get_active_mut().ability = Ability::SuctionCups;
+
+            sim.perform_action(BattleAction::Move(get_move("Roar", 6).id), true, &mut state);
+
+            assert_eq!(state.side2.pending_switch.is_some(), forced_out, "{ability:?}");
+
+            // the window closes once the move is over
+            let jolteon = state.side2.get_active();
+            assert!(!has_effect!(jolteon, VolatileStatusEffect::AbilityIgnored));
+            assert_eq!(sim.get_ability(jolteon, &state.conditions), Ability::SuctionCups);
+        }
+
+        // only breakable abilities are ignored
+        let sim = Simulator::new(get_data_handler(), 5, 0);
+        let mut state = new_state(ATTACKER, TEAM, 5);
+        let jolteon = state.side2.get_active_mut();
+        jolteon.volatile_status.add(VolatileStatusEffect::AbilityIgnored);
+
+        assert_eq!(sim.get_ability(jolteon, &state.conditions), Ability::None);
+        jolteon.ability = Ability::Intimidate;
+        assert_eq!(sim.get_ability(jolteon, &state.conditions), Ability::Intimidate);
+    }
+
+    #[test]
+    fn air_lock_hides_the_weather() {
+
+        let mut sim = Simulator::new(get_data_handler(), 5, 0);
+        let mut state = new_state("Rayquaza\nLevel: 50\nAbility: Air Lock\n- Outrage", DEFENDER, 5);
+        state.conditions.weather = Some((Weather::Rain, 5));
+
+        let (side, other_side, conditions) = state.split_mut(true);
+        sim.update_field_abilities(side, other_side, conditions);
+
+        assert!(state.conditions.weather_negated);
+        assert_eq!(state.conditions.get_weather(), None);
+        assert!(!state.conditions.is_rain());
+        assert_eq!(state.conditions.weather, Some((Weather::Rain, 5)));
+
+        // the weather comes back once air lock leaves
+        state.side1.get_active_mut().hp = 0;
+
+        let (side, other_side, conditions) = state.split_mut(true);
+        sim.update_field_abilities(side, other_side, conditions);
+
+        assert!(!state.conditions.weather_negated);
+        assert_eq!(state.conditions.get_weather(), Some(Weather::Rain));
+    }
+
+    #[test]
+    fn illegal_actions_are_replaced() {
+
+        let growl = get_move("Growl", 5).id;
+
+        for illegal in [BattleAction::Switch(1), BattleAction::Switch(0), BattleAction::Switch(6), BattleAction::Move(get_move("Hydro Pump", 5).id)] {
+
+            let recorder = EventRecorder::default();
+            let mut sim = Simulator::new(get_data_handler(), 5, 0);
+            sim.add_event_sink(Box::new(recorder.clone()));
+
+            let mut state = new_state(TEAM, DEFENDER, 5);
+            state.side1.team[1].hp = 0;
+
+            sim.simulate_turn(illegal, BattleAction::Move(get_move("Soft-Boiled", 5).id), &mut state, [&FirstChoiceController, &FirstChoiceController]);
+
+            // the first choice controller falls back to growl
+            assert_eq!(state.side1.active_pokemon, 0, "{illegal:?}");
+            assert!(recorder.events().iter().any(|event| matches!(event, BattleEvent::MoveUsed {pokemon, move_id, ..} if pokemon.is_side1 && pokemon.team_slot == 0 && *move_id == growl)), "{illegal:?}");
+        }
+    }
+
+    #[test]
+    fn contact_abilities() {
+
+        let mut sim = Simulator::new(get_data_handler(), 9, 0);
+        let tackle = get_move("Tackle", 9);
+
+        let mut state = new_state("Snorlax @ Leftovers\nLevel: 50\nAbility: Thick Fat\n- Tackle", DEFENDER, 9);
+        state.side2.get_active_mut().ability = Ability::PerishBody;
+
+        let (side, other_side, conditions) = state.split_mut(true);
+        sim.use_move(tackle, side, other_side, conditions);
+
+        assert!(has_effect!(state.side1.get_active(), VolatileStatusEffect::PerishSong(3)));
+        assert!(has_effect!(state.side2.get_active(), VolatileStatusEffect::PerishSong(3)));
+
+        state.side2.get_active_mut().ability = Ability::Pickpocket;
+
+        let (side, other_side, conditions) = state.split_mut(true);
+        sim.use_move(tackle, side, other_side, conditions);
+
+        assert_eq!(state.side1.get_active().item, None);
+        assert_eq!(state.side2.get_active().item, Some(Item::Leftovers));
+
+        state.side2.get_active_mut().ability = Ability::WanderingSpirit;
+
+        let (side, other_side, conditions) = state.split_mut(true);
+        sim.use_move(tackle, side, other_side, conditions);
+
+        assert_eq!(sim.get_ability(state.side1.get_active(), &state.conditions), Ability::WanderingSpirit);
+        assert_eq!(sim.get_ability(state.side2.get_active(), &state.conditions), Ability::ThickFat);
+
+        // protective pads skip all of it
+        let mut state = new_state("Snorlax\nLevel: 50\nAbility: Thick Fat\n- Tackle", DEFENDER, 9);
+        state.side2.get_active_mut().ability = Ability::RoughSkin;
+
+        for (item, expected_loss) in [(Some(Item::ProtectivePads), 0), (None, state.side1.get_active().max_hp / 8)] {
+
+            let snorlax = state.side1.get_active_mut();
+            snorlax.item = item;
+            snorlax.hp = snorlax.max_hp;
+
+            let (side, other_side, conditions) = state.split_mut(true);
+            sim.use_move(tackle, side, other_side, conditions);
+
+            let snorlax = state.side1.get_active();
+            assert_eq!(snorlax.max_hp - snorlax.hp, expected_loss);
+        }
+    }
+
+    #[test]
+    fn events_tell_apart_pokemon_with_the_same_name() {
+
+        let recorder = EventRecorder::default();
+        let mut sim = Simulator::new(get_data_handler(), 5, 0);
+        sim.add_event_sink(Box::new(recorder.clone()));
+
+        let mut state = new_state(TEAM, TEAM, 5);
+        state.side1.active_pokemon = 2;
+
+        let growl = BattleAction::Move(get_move("Growl", 5).id);
+        sim.simulate_turn(growl, growl, &mut state, [&FirstChoiceController, &FirstChoiceController]);
+
+        let mut users = recorder.events().into_iter().filter_map(|event| match event {
+            BattleEvent::MoveUsed {pokemon, ..} => Some((pokemon.is_side1, pokemon.team_slot, pokemon.name)),
+            _ => None
+        }).collect::<Vec<_>>();
+
+        users.sort();
+        assert_eq!(users, [(false, 0, "Jolteon".into()), (true, 2, "Lapras".into())]);
+
+        let mut state = new_state(TEAM, TEAM, 5);
+        recorder.clear();
+        sim.simulate_turn(growl, growl, &mut state, [&FirstChoiceController, &FirstChoiceController]);
+
+        let sides = recorder.events().into_iter().filter_map(|event| match event {
+            BattleEvent::StatChange {pokemon, ..} => Some(pokemon.is_side1),
+            _ => None
+        }).collect::<Vec<_>>();
+
+        // both jolteon lower each other's attack
+        assert_eq!(sides.len(), 2);
+        assert_ne!(sides[0], sides[1]);
+    }
+
+    #[test]
+    fn ghost_curse_cost_ignores_magic_guard() {
+
+        let mut sim = Simulator::new(get_data_handler(), 5, 0);
+        let mut state = new_state("Gengar\nLevel: 50\nAbility: Levitate\n- Curse", DEFENDER, 5);
+        state.side1.get_active_mut().ability = Ability::MagicGuard;
+
+        let (side, other_side, conditions) = state.split_mut(true);
+        sim.use_move(get_move("Curse", 5), side, other_side, conditions);
+
+        let gengar = state.side1.get_active();
+        assert_eq!(gengar.hp, gengar.max_hp - gengar.max_hp / 2);
+        assert!(has_effect!(state.side2.get_active(), VolatileStatusEffect::Curse));
+    }
+
+    #[test]
+    fn gen_1_2_status_damage_follows_each_action() {
+
+        for (generation, fraction) in [(1, 16), (2, 8)] {
+
+            let recorder = EventRecorder::default();
+            let mut sim = Simulator::new(get_data_handler(), generation, 0);
+            sim.add_event_sink(Box::new(recorder.clone()));
+
+            let mut state = new_state("Jolteon\nLevel: 50\n- Growl", "Snorlax\nLevel: 50\n- Growl", generation);
+            state.side1.get_active_mut().non_volatile_status = Some(NonVolatileStatus::Poison);
+
+            let growl = BattleAction::Move(get_move("Growl", generation).id);
+            sim.simulate_turn(growl, growl, &mut state, [&FirstChoiceController, &FirstChoiceController]);
+
+            let jolteon = state.side1.get_active();
+            assert_eq!(jolteon.hp, jolteon.max_hp - jolteon.max_hp / fraction, "gen {generation}");
+
+            // jolteon moves first and takes its damage before snorlax gets to move
+            let events = recorder.events();
+            let poison = events.iter().position(|event| matches!(event, BattleEvent::IndirectDamage {cause: DamageCause::Poison, ..})).unwrap();
+            let last_move = events.iter().rposition(|event| matches!(event, BattleEvent::MoveUsed {..})).unwrap();
+            assert!(poison < last_move, "gen {generation}");
+        }
     }
 }
\ No newline at end of file
@@ -0,0 +1,64 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::sync::OnceLock;
+
+use crate::battle::*;
+use crate::controller::*;
+use crate::database::*;
+use crate::logging::*;
+use crate::parser::*;
+use crate::pokemon::*;
+
+static DATA_HANDLER: OnceLock<DataHandler> = OnceLock::new();
+
+pub fn get_data_handler() -> &'static DataHandler {
+    DATA_HANDLER.get_or_init(|| DataHandler::new().unwrap())
+}
+
+pub fn parse_team(team: &str, generation: u8) -> Vec<Pokemon> {
+    parse_showdown_team(team, get_data_handler(), generation)
+}
+
+pub fn get_move(name: &str, generation: u8) -> &'static Move {
+    let id = get_data_handler().move_name_table.lookup_id(name).unwrap();
+    get_data_handler().get_move_in_generation(id, generation)
+}
+
+// keeps every event so tests can look back over what happened
+#[derive(Clone, Default)]
+pub struct EventRecorder(pub Rc<RefCell<Vec<BattleEvent>>>);
+
+impl EventRecorder {
+
+    pub fn events(&self) -> Vec<BattleEvent> {
+        self.0.borrow().clone()
+    }
+
+    pub fn clear(&self) {
+        self.0.borrow_mut().clear();
+    }
+}
+
+impl EventSink for EventRecorder {
+    fn on_event(&mut self, event: &BattleEvent) {
+        self.0.borrow_mut().push(event.clone());
+    }
+}
+
+// always picks the first legal action and the first healthy reserve
+pub struct FirstChoiceController;
+
+impl Controller for FirstChoiceController {
+
+    fn get_action(&self, _state: &BattleState, _is_side1: bool, legal_actions: &[BattleAction]) -> BattleAction {
+        legal_actions[0]
+    }
+
+    fn get_switch_in(&self, state: &BattleState, is_side1: bool) -> u8 {
+
+        let side = if is_side1 {&state.side1} else {&state.side2};
+
+        (0..side.team.len()).find(|&idx| side.is_valid_switch_in(idx)).unwrap() as u8
+
+    }
+}